@@ -0,0 +1,283 @@
+// surfman/surfman/benches/swap_chains.rs
+//
+//! Compares `SwapChains` with a table behind a single `RwLock`, which is how `SwapChains` used to
+//! store its swap chains. Measures lookups as more threads make them at once, lookups while the
+//! producer creates and destroys swap chains, and iteration. Run with:
+//!
+//!     cargo bench --features chains --bench swap_chains
+//!
+//! How much sharding helps depends on how many cores the lookups can run on at once, so results
+//! from a single-core machine understate it.
+
+use euclid::default::Size2D;
+use fnv::{FnvHashMap, FnvHashSet};
+use std::collections::hash_map::Entry;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use surfman::chains::{SwapChain, SwapChains, SwapChainsAPI};
+use surfman::{
+    ContextAttributeFlags, ContextAttributes, ContextID, Error, GLVersion, SurfaceAccess,
+};
+
+// The surfaceless backend doesn't need a display, so the benchmark can run anywhere on Linux.
+#[cfg(target_os = "linux")]
+use surfman::platform::unix::generic::{connection::Connection, device::Device};
+#[cfg(not(target_os = "linux"))]
+use surfman::{Connection, Device};
+
+type Context = <Device as surfman::device::Device>::Context;
+
+// Enough swap chains for a page full of WebGL canvases.
+const SWAP_CHAIN_COUNT: usize = 256;
+const LOOKUPS_PER_THREAD: usize = 200_000;
+const CHURN_COUNT: usize = 2_000;
+const ITERATIONS: usize = 20_000;
+const THREAD_COUNTS: [usize; 4] = [1, 2, 4, 8];
+
+// The operations the benchmark measures, so it can run the same code against both tables.
+trait Registry: Sync {
+    fn create(&self, id: usize, device: &mut Device, context: &mut Context) -> Result<(), Error>;
+    fn destroy(&self, id: usize, device: &mut Device, context: &mut Context) -> Result<(), Error>;
+    fn get(&self, id: usize) -> Option<SwapChain<Device>>;
+    // Iterate over the swap chains of `context`, returning how many there were.
+    fn count(&self, device: &mut Device, context: &mut Context) -> usize;
+}
+
+impl Registry for SwapChains<usize, Device> {
+    fn create(&self, id: usize, device: &mut Device, context: &mut Context) -> Result<(), Error> {
+        self.create_detached_swap_chain(
+            id,
+            Size2D::new(1, 1),
+            device,
+            context,
+            SurfaceAccess::GPUOnly,
+        )
+    }
+
+    fn destroy(&self, id: usize, device: &mut Device, context: &mut Context) -> Result<(), Error> {
+        SwapChains::destroy(self, id, device, context)
+    }
+
+    fn get(&self, id: usize) -> Option<SwapChain<Device>> {
+        SwapChainsAPI::get(self, id)
+    }
+
+    fn count(&self, device: &mut Device, context: &mut Context) -> usize {
+        self.iter(device, context).count()
+    }
+}
+
+// A table of swap chains behind a single lock, as `SwapChains` used to store them: swap chains
+// are created and destroyed with the table locked for writing, and iteration looks each swap
+// chain up and collects them into a new vector.
+#[derive(Default)]
+struct SingleLockTable {
+    ids: Mutex<FnvHashMap<ContextID, FnvHashSet<usize>>>,
+    table: RwLock<FnvHashMap<usize, SwapChain<Device>>>,
+}
+
+impl Registry for SingleLockTable {
+    fn create(&self, id: usize, device: &mut Device, context: &mut Context) -> Result<(), Error> {
+        match self.table.write().unwrap().entry(id) {
+            Entry::Occupied(_) => Err(Error::Failed)?,
+            Entry::Vacant(entry) => entry.insert(SwapChain::create_detached(
+                device,
+                context,
+                SurfaceAccess::GPUOnly,
+                Size2D::new(1, 1),
+            )?),
+        };
+        self.ids
+            .lock()
+            .unwrap()
+            .entry(device.context_id(context))
+            .or_default()
+            .insert(id);
+        Ok(())
+    }
+
+    fn destroy(&self, id: usize, device: &mut Device, context: &mut Context) -> Result<(), Error> {
+        if let Some(swap_chain) = self.table.write().unwrap().remove(&id) {
+            swap_chain.destroy(device, context)?;
+        }
+        if let Some(ids) = self
+            .ids
+            .lock()
+            .unwrap()
+            .get_mut(&device.context_id(context))
+        {
+            ids.remove(&id);
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: usize) -> Option<SwapChain<Device>> {
+        self.table.read().unwrap().get(&id).cloned()
+    }
+
+    fn count(&self, device: &mut Device, context: &mut Context) -> usize {
+        self.ids
+            .lock()
+            .unwrap()
+            .get(&device.context_id(context))
+            .iter()
+            .flat_map(|ids| ids.iter())
+            .filter_map(|id| Some((*id, self.table.read().unwrap().get(id)?.clone())))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .count()
+    }
+}
+
+// The id of the `index`th lookup made by a thread.
+fn lookup_id(thread_index: usize, index: usize) -> usize {
+    (index * 7919 + thread_index) % SWAP_CHAIN_COUNT
+}
+
+// Time `LOOKUPS_PER_THREAD` lookups on each of `thread_count` threads.
+// Returns the mean time per lookup.
+fn time_lookups(registry: &impl Registry, thread_count: usize) -> Duration {
+    let start = Instant::now();
+    thread::scope(|scope| {
+        for thread_index in 0..thread_count {
+            scope.spawn(move || {
+                for index in 0..LOOKUPS_PER_THREAD {
+                    assert!(registry.get(lookup_id(thread_index, index)).is_some());
+                }
+            });
+        }
+    });
+    start.elapsed() / (thread_count * LOOKUPS_PER_THREAD) as u32
+}
+
+// Make lookups on `thread_count` threads while this thread creates and destroys `CHURN_COUNT`
+// swap chains. Returns the mean and the longest time a lookup took.
+fn time_lookups_during_churn(
+    registry: &impl Registry,
+    thread_count: usize,
+    device: &mut Device,
+    context: &mut Context,
+) -> (Duration, Duration) {
+    let done = AtomicBool::new(false);
+    let results: Vec<(Duration, Duration, u32)> = thread::scope(|scope| {
+        let threads: Vec<_> = (0..thread_count)
+            .map(|thread_index| {
+                let done = &done;
+                scope.spawn(move || {
+                    let (mut total, mut longest, mut count) = (Duration::ZERO, Duration::ZERO, 0);
+                    while !done.load(Ordering::Relaxed) {
+                        let start = Instant::now();
+                        assert!(registry.get(lookup_id(thread_index, count)).is_some());
+                        let elapsed = start.elapsed();
+                        total += elapsed;
+                        longest = longest.max(elapsed);
+                        count += 1;
+                    }
+                    (total, longest, count as u32)
+                })
+            })
+            .collect();
+        for index in 0..CHURN_COUNT {
+            let id = SWAP_CHAIN_COUNT + index;
+            registry.create(id, device, context).unwrap();
+            registry.destroy(id, device, context).unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect()
+    });
+    let total: Duration = results.iter().map(|(total, _, _)| *total).sum();
+    let longest = results.iter().map(|(_, longest, _)| *longest).max();
+    let count: u32 = results.iter().map(|(_, _, count)| *count).sum();
+    (total / count.max(1), longest.unwrap_or_default())
+}
+
+// Time `ITERATIONS` iterations over the swap chains of `context`.
+// Returns the mean time per iteration.
+fn time_iteration(
+    registry: &impl Registry,
+    device: &mut Device,
+    context: &mut Context,
+) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        assert_eq!(registry.count(device, context), SWAP_CHAIN_COUNT);
+    }
+    start.elapsed() / ITERATIONS as u32
+}
+
+fn main() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection.create_low_power_adapter().unwrap();
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            eprintln!("Can't run this benchmark on this hardware.");
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+    let descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::empty(),
+        })
+        .unwrap();
+    let mut context = device.create_context(&descriptor, None).unwrap();
+
+    let single_lock_table = SingleLockTable::default();
+    let swap_chains = SwapChains::new();
+    for id in 0..SWAP_CHAIN_COUNT {
+        single_lock_table
+            .create(id, &mut device, &mut context)
+            .unwrap();
+        Registry::create(&swap_chains, id, &mut device, &mut context).unwrap();
+    }
+
+    println!("Lookups of {} swap chains:", SWAP_CHAIN_COUNT);
+    println!("threads   single lock    SwapChains");
+    for thread_count in THREAD_COUNTS {
+        println!(
+            "{:>7} {:>10?}/op {:>10?}/op",
+            thread_count,
+            time_lookups(&single_lock_table, thread_count),
+            time_lookups(&swap_chains, thread_count),
+        );
+    }
+
+    println!(
+        "Lookups while {} swap chains are created and destroyed (mean / longest):",
+        CHURN_COUNT
+    );
+    println!("threads            single lock             SwapChains");
+    for thread_count in THREAD_COUNTS {
+        let single_lock =
+            time_lookups_during_churn(&single_lock_table, thread_count, &mut device, &mut context);
+        let sharded =
+            time_lookups_during_churn(&swap_chains, thread_count, &mut device, &mut context);
+        println!(
+            "{:>7} {:>10?} / {:>10?} {:>10?} / {:>10?}",
+            thread_count, single_lock.0, single_lock.1, sharded.0, sharded.1,
+        );
+    }
+
+    println!("Iterations over {} swap chains:", SWAP_CHAIN_COUNT);
+    println!("  single lock    SwapChains");
+    println!(
+        "{:>10?}/op {:>10?}/op",
+        time_iteration(&single_lock_table, &mut device, &mut context),
+        time_iteration(&swap_chains, &mut device, &mut context),
+    );
+
+    for id in 0..SWAP_CHAIN_COUNT {
+        single_lock_table
+            .destroy(id, &mut device, &mut context)
+            .unwrap();
+    }
+    swap_chains.destroy_all(&mut device, &mut context).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
@@ -0,0 +1,477 @@
+// surfman/surfman/src/bin/surfman-info.rs
+//
+//! Reports what `surfman` sees on this system as JSON, in the spirit of `eglinfo`.
+//!
+//! For each backend that the default connection type can use, this prints whether a connection
+//! could be opened, the adapters and devices it provides, every context attribute combination that
+//! works, and whether generic surfaces, surface textures, and context sharing work. Attach the
+//! output to bug reports.
+//!
+//! Usage: `surfman-info [--compact]`
+
+use euclid::default::Size2D;
+use glow::HasContext;
+use std::env;
+use std::fmt::Write;
+use surfman::connection::Connection as ConnectionAPI;
+use surfman::{Connection, ContextAttributeFlags, ContextAttributes, ContextDescriptor, Device};
+use surfman::{Error, GLApi, GLVersion, SurfaceAccess, SurfaceType};
+
+static GL_VERSIONS: [GLVersion; 13] = [
+    GLVersion { major: 2, minor: 0 },
+    GLVersion { major: 2, minor: 1 },
+    GLVersion { major: 3, minor: 0 },
+    GLVersion { major: 3, minor: 1 },
+    GLVersion { major: 3, minor: 2 },
+    GLVersion { major: 3, minor: 3 },
+    GLVersion { major: 4, minor: 0 },
+    GLVersion { major: 4, minor: 1 },
+    GLVersion { major: 4, minor: 2 },
+    GLVersion { major: 4, minor: 3 },
+    GLVersion { major: 4, minor: 4 },
+    GLVersion { major: 4, minor: 5 },
+    GLVersion { major: 4, minor: 6 },
+];
+
+static GL_ES_VERSIONS: [GLVersion; 4] = [
+    GLVersion { major: 2, minor: 0 },
+    GLVersion { major: 3, minor: 0 },
+    GLVersion { major: 3, minor: 1 },
+    GLVersion { major: 3, minor: 2 },
+];
+
+const SURFACE_SIZE: i32 = 64;
+
+fn main() {
+    let compact = env::args().skip(1).any(|arg| arg == "--compact");
+
+    let backends = Connection::backend_names()
+        .into_iter()
+        .map(probe_backend)
+        .collect();
+    let report = Json::Object(vec![
+        ("surfman_version", Json::from(env!("CARGO_PKG_VERSION"))),
+        ("backends", Json::Array(backends)),
+    ]);
+
+    let mut output = String::new();
+    report.write(&mut output, if compact { None } else { Some(0) });
+    println!("{}", output);
+}
+
+fn probe_backend(backend: &'static str) -> Json {
+    let connection = match Connection::new_with_backend(backend) {
+        Ok(connection) => connection,
+        Err(err) => {
+            return Json::Object(vec![
+                ("name", Json::from(backend)),
+                ("connection", error_json(err)),
+            ])
+        }
+    };
+
+    let adapters = vec![
+        probe_adapter(
+            &connection,
+            "hardware",
+            connection.create_hardware_adapter(),
+        ),
+        probe_adapter(
+            &connection,
+            "low_power",
+            connection.create_low_power_adapter(),
+        ),
+        probe_adapter(
+            &connection,
+            "software",
+            connection.create_software_adapter(),
+        ),
+    ];
+
+    Json::Object(vec![
+        ("name", Json::from(backend)),
+        ("connection", ok_json()),
+        ("gl_api", gl_api_json(connection.gl_api())),
+        ("adapters", Json::Array(adapters)),
+    ])
+}
+
+fn probe_adapter(
+    connection: &Connection,
+    kind: &'static str,
+    adapter: Result<<Connection as ConnectionAPI>::Adapter, Error>,
+) -> Json {
+    let mut fields = vec![("kind", Json::from(kind))];
+
+    let adapter = match adapter {
+        Ok(adapter) => adapter,
+        Err(err) => {
+            fields.push(("adapter", error_json(err)));
+            return Json::Object(fields);
+        }
+    };
+    fields.push(("adapter", ok_json()));
+
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(err) => {
+            fields.push(("device", error_json(err)));
+            return Json::Object(fields);
+        }
+    };
+    fields.push(("device", ok_json()));
+
+    fields.push((
+        "adapter_info",
+        match device.adapter_info() {
+            Ok(info) => Json::Object(vec![
+                ("vendor", Json::from(info.vendor)),
+                ("renderer", Json::from(info.renderer)),
+                ("driver_version", Json::from(info.driver_version)),
+                ("is_software", Json::Bool(info.is_software)),
+            ]),
+            Err(err) => error_json(err),
+        },
+    ));
+
+    fields.push((
+        "capabilities",
+        match device.capabilities() {
+            Ok(capabilities) => {
+                let flags = capabilities
+                    .flags
+                    .iter_names()
+                    .map(|(name, _)| Json::from(name.to_lowercase()))
+                    .collect();
+                Json::Object(vec![
+                    ("gl_api", gl_api_json(capabilities.gl_api)),
+                    ("min_version", version_json(capabilities.min_version)),
+                    ("max_version", version_json(capabilities.max_version)),
+                    ("flags", Json::Array(flags)),
+                    (
+                        "max_surface_size",
+                        Json::Array(vec![
+                            Json::Number(capabilities.max_surface_size.width as i64),
+                            Json::Number(capabilities.max_surface_size.height as i64),
+                        ]),
+                    ),
+                    (
+                        "surface_formats",
+                        Json::Array(
+                            capabilities
+                                .surface_formats
+                                .iter()
+                                .map(|format| Json::from(format!("{:?}", format).to_lowercase()))
+                                .collect(),
+                        ),
+                    ),
+                    (
+                        "platform_extensions",
+                        Json::Array(
+                            capabilities
+                                .platform_extensions
+                                .into_iter()
+                                .map(Json::from)
+                                .collect(),
+                        ),
+                    ),
+                ])
+            }
+            Err(err) => error_json(err),
+        },
+    ));
+
+    let (contexts, first_descriptor, gl_extensions) = probe_contexts(&mut device);
+    fields.push(("contexts", Json::Array(contexts)));
+    fields.push(("gl_extensions", Json::Array(gl_extensions)));
+
+    if let Some(descriptor) = first_descriptor {
+        fields.push(("surfaces", probe_surfaces(&mut device, &descriptor)));
+    }
+
+    Json::Object(fields)
+}
+
+// Tries every version and flag combination, returning the ones that work, a descriptor that
+// works for further tests, and the OpenGL extensions of the first context created.
+fn probe_contexts(device: &mut Device) -> (Vec<Json>, Option<ContextDescriptor>, Vec<Json>) {
+    let versions = match device.gl_api() {
+        GLApi::GL => &GL_VERSIONS[..],
+        GLApi::GLES => &GL_ES_VERSIONS[..],
+    };
+
+    let mut contexts = vec![];
+    let mut first_descriptor = None;
+    let mut gl_extensions = vec![];
+    // Hints such as `NO_ERROR` never prevent a context from being created, so leave them out.
+    let probed_flags = ContextAttributeFlags::ALPHA
+        | ContextAttributeFlags::DEPTH
+        | ContextAttributeFlags::STENCIL
+        | ContextAttributeFlags::COMPATIBILITY_PROFILE;
+    for &version in versions {
+        for flag_bits in 0..(probed_flags.bits() + 1) {
+            let flags = ContextAttributeFlags::from_bits_truncate(flag_bits);
+            let attributes = ContextAttributes { version, flags };
+            let descriptor = match device.create_context_descriptor(&attributes) {
+                Ok(descriptor) => descriptor,
+                Err(_) => continue,
+            };
+            let mut context = match device.create_context(&descriptor, None) {
+                Ok(context) => context,
+                Err(_) => continue,
+            };
+
+            let actual_descriptor = device.context_descriptor(&context);
+            let actual_attributes = device.context_descriptor_attributes(&actual_descriptor);
+            let gl_strings = device.with_context_current(&context, |gl| unsafe {
+                let mut extensions: Vec<_> = gl.supported_extensions().iter().cloned().collect();
+                extensions.sort();
+                (gl.get_parameter_string(glow::VERSION), extensions)
+            });
+            drop(device.destroy_context(&mut context));
+
+            let (version_string, extensions) = match gl_strings {
+                Ok(gl_strings) => gl_strings,
+                Err(_) => continue,
+            };
+            if first_descriptor.is_none() {
+                first_descriptor = Some(descriptor);
+                gl_extensions = extensions.into_iter().map(Json::from).collect();
+            }
+
+            let profile = if actual_attributes
+                .flags
+                .contains(ContextAttributeFlags::COMPATIBILITY_PROFILE)
+            {
+                "compatibility"
+            } else {
+                "core"
+            };
+            contexts.push(Json::Object(vec![
+                ("requested_version", version_json(version)),
+                ("requested_flags", flags_json(flags)),
+                ("version", version_json(actual_attributes.version)),
+                ("flags", flags_json(actual_attributes.flags)),
+                ("profile", Json::from(profile)),
+                ("version_string", Json::from(version_string)),
+            ]));
+        }
+    }
+
+    (contexts, first_descriptor, gl_extensions)
+}
+
+fn probe_surfaces(device: &mut Device, descriptor: &ContextDescriptor) -> Json {
+    let mut context = match device.create_context(descriptor, None) {
+        Ok(context) => context,
+        Err(err) => return error_json(err),
+    };
+
+    let size = Size2D::new(SURFACE_SIZE, SURFACE_SIZE);
+    let generic_surface = device
+        .create_surface(
+            &context,
+            SurfaceAccess::GPUOnly,
+            SurfaceType::Generic { size },
+        )
+        .and_then(|mut surface| device.destroy_surface(&mut context, &mut surface));
+
+    let sharing = device
+        .create_context(descriptor, Some(&context))
+        .and_then(|mut shared_context| device.destroy_context(&mut shared_context));
+
+    // Read a surface from a second context, as a consumer on another thread would.
+    let surface_texture =
+        device
+            .create_context(descriptor, None)
+            .and_then(|mut consumer_context| {
+                let result = device
+                    .create_surface(
+                        &context,
+                        SurfaceAccess::GPUOnly,
+                        SurfaceType::Generic { size },
+                    )
+                    .and_then(|surface| {
+                        match device.create_surface_texture(&mut consumer_context, surface) {
+                            Ok(surface_texture) => {
+                                match device
+                                    .destroy_surface_texture(&mut consumer_context, surface_texture)
+                                {
+                                    Ok(mut surface) => {
+                                        device.destroy_surface(&mut context, &mut surface)
+                                    }
+                                    Err((err, surface_texture)) => {
+                                        // Leak rather than panic; we're about to exit anyway.
+                                        std::mem::forget(surface_texture);
+                                        Err(err)
+                                    }
+                                }
+                            }
+                            Err((err, mut surface)) => {
+                                drop(device.destroy_surface(&mut context, &mut surface));
+                                Err(err)
+                            }
+                        }
+                    });
+                drop(device.destroy_context(&mut consumer_context));
+                result
+            });
+
+    drop(device.destroy_context(&mut context));
+
+    Json::Object(vec![
+        ("generic_surface", status_json(generic_surface)),
+        ("surface_texture", status_json(surface_texture)),
+        ("sharing", status_json(sharing)),
+    ])
+}
+
+fn ok_json() -> Json {
+    Json::Object(vec![("ok", Json::Bool(true))])
+}
+
+fn error_json(error: Error) -> Json {
+    Json::Object(vec![
+        ("ok", Json::Bool(false)),
+        ("error", Json::from(format!("{:?}", error))),
+    ])
+}
+
+fn status_json(result: Result<(), Error>) -> Json {
+    match result {
+        Ok(()) => ok_json(),
+        Err(err) => error_json(err),
+    }
+}
+
+fn gl_api_json(gl_api: GLApi) -> Json {
+    Json::from(match gl_api {
+        GLApi::GL => "gl",
+        GLApi::GLES => "gles",
+    })
+}
+
+fn version_json(version: GLVersion) -> Json {
+    Json::from(format!("{}.{}", version.major, version.minor))
+}
+
+fn flags_json(flags: ContextAttributeFlags) -> Json {
+    Json::Array(
+        flags
+            .iter_names()
+            .map(|(name, _)| Json::from(name.to_lowercase()))
+            .collect(),
+    )
+}
+
+// Just enough JSON to print the report, so that this tool needs no extra dependencies.
+enum Json {
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl From<&str> for Json {
+    fn from(string: &str) -> Json {
+        Json::String(string.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(string: String) -> Json {
+        Json::String(string)
+    }
+}
+
+impl Json {
+    // Writes the value, pretty-printed at the given indentation level, or compactly if `None`.
+    fn write(&self, output: &mut String, indent: Option<usize>) {
+        match *self {
+            Json::Bool(value) => write!(output, "{}", value).unwrap(),
+            Json::Number(value) => write!(output, "{}", value).unwrap(),
+            Json::String(ref value) => write_string(output, value),
+            Json::Array(ref values) => write_list(
+                output,
+                indent,
+                '[',
+                ']',
+                values.iter(),
+                |output, value, indent| value.write(output, indent),
+            ),
+            Json::Object(ref fields) => write_list(
+                output,
+                indent,
+                '{',
+                '}',
+                fields.iter(),
+                |output, (key, value), indent| {
+                    write_string(output, key);
+                    output.push(':');
+                    if indent.is_some() {
+                        output.push(' ');
+                    }
+                    value.write(output, indent);
+                },
+            ),
+        }
+    }
+}
+
+fn write_list<'a, T, I, F>(
+    output: &mut String,
+    indent: Option<usize>,
+    open: char,
+    close: char,
+    items: I,
+    mut write_item: F,
+) where
+    T: 'a,
+    I: ExactSizeIterator<Item = &'a T>,
+    F: FnMut(&mut String, &'a T, Option<usize>),
+{
+    output.push(open);
+    if items.len() == 0 {
+        output.push(close);
+        return;
+    }
+
+    let inner_indent = indent.map(|indent| indent + 1);
+    for (index, item) in items.enumerate() {
+        if index > 0 {
+            output.push(',');
+        }
+        write_newline(output, inner_indent);
+        write_item(output, item, inner_indent);
+    }
+    write_newline(output, indent);
+    output.push(close);
+}
+
+fn write_newline(output: &mut String, indent: Option<usize>) {
+    if let Some(indent) = indent {
+        output.push('\n');
+        for _ in 0..indent {
+            output.push_str("  ");
+        }
+    }
+}
+
+fn write_string(output: &mut String, string: &str) {
+    output.push('"');
+    for character in string.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                write!(output, "\\u{:04x}", character as u32).unwrap()
+            }
+            character => output.push(character),
+        }
+    }
+    output.push('"');
+}
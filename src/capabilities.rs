@@ -0,0 +1,295 @@
+// surfman/surfman/src/capabilities.rs
+//
+//! Queryable information about what a device supports.
+
+use crate::device::Device as DeviceInterface;
+use crate::gl;
+use crate::gl_utils;
+use crate::info::{AdapterInfo, GLApi, GLVersion};
+use crate::{ContextAttributeFlags, ContextAttributes, Error, Gl, SurfaceFormat};
+
+use euclid::default::Size2D;
+use glow::HasContext;
+
+bitflags! {
+    /// Optional features that a device may or may not support.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct CapabilityFlags: u8 {
+        /// Images backing surfaces can be exported as Linux DMA-BUF file descriptors
+        /// (`EGL_MESA_image_dma_buf_export`).
+        const DMABUF_EXPORT = 0x01;
+        /// Fence sync objects are available, either from the platform API or from OpenGL itself.
+        const FENCES        = 0x02;
+        /// Robust buffer access and graphics reset notification are available.
+        const ROBUSTNESS    = 0x04;
+        /// Damage regions can be supplied when presenting (`EGL_KHR_partial_update` or
+        /// `EGL_KHR_swap_buffers_with_damage`).
+        const DAMAGE        = 0x08;
+        /// The age of back buffers can be queried (`EGL_EXT_buffer_age`).
+        const BUFFER_AGE    = 0x10;
+    }
+}
+
+/// Describes what a device supports.
+///
+/// Call `Device::capabilities()` to fetch this. Applications can use it to choose code paths up
+/// front rather than discovering missing features by trial and error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    /// The OpenGL API flavor (OpenGL or OpenGL ES) that contexts on this device use.
+    pub gl_api: GLApi,
+    /// The lowest version that a context could be created with on this device.
+    pub min_version: GLVersion,
+    /// The highest version that a context created on this device supports.
+    pub max_version: GLVersion,
+    /// The extensions of the platform windowing API (EGL, WGL, or CGL).
+    ///
+    /// On EGL, this includes both client and display extensions. CGL has no extension mechanism,
+    /// so this is always empty on macOS.
+    pub platform_extensions: Vec<String>,
+    /// Optional features that this device supports.
+    pub flags: CapabilityFlags,
+    /// The largest surface that can be created on this device.
+    pub max_surface_size: Size2D<i32>,
+    /// The color formats that generic surfaces can be created with on this device.
+    pub surface_formats: Vec<SurfaceFormat>,
+    /// The OpenGL vendor string (`GL_VENDOR`).
+    pub vendor: String,
+    /// The OpenGL renderer string (`GL_RENDERER`).
+    pub renderer: String,
+}
+
+impl Capabilities {
+    /// Returns true if the platform windowing API supports the given extension.
+    #[inline]
+    pub fn has_platform_extension(&self, name: &str) -> bool {
+        self.platform_extensions
+            .iter()
+            .any(|extension| extension == name)
+    }
+
+    /// Returns true if generic surfaces can be created with the given color format.
+    #[inline]
+    pub fn supports_surface_format(&self, format: SurfaceFormat) -> bool {
+        self.surface_formats.contains(&format)
+    }
+}
+
+// Fills in the fields of `Capabilities` that OpenGL itself knows about by creating a throwaway
+// context. The caller is responsible for restoring whatever context was current beforehand.
+//
+// Only the renderable surface formats that `supports_surface_format` accepts are listed. It is
+// called with the throwaway context current, for backends whose surfaces restrict the formats.
+pub(crate) fn probe<D, F>(
+    device: &mut D,
+    platform_extensions: Vec<String>,
+    flags: CapabilityFlags,
+    supports_surface_format: F,
+) -> Result<Capabilities, Error>
+where
+    D: DeviceInterface,
+    F: Fn(&Gl, SurfaceFormat) -> bool,
+{
+    let gl_api = device.gl_api();
+    let gl_info = GLInfo::probe(device, &supports_surface_format)?;
+    let min_version = probe_min_version(device, gl_info.version)?;
+    Ok(Capabilities {
+        gl_api,
+        min_version,
+        max_version: gl_info.version,
+        platform_extensions,
+        flags: flags | gl_info.flags,
+        max_surface_size: gl_info.max_surface_size,
+        surface_formats: gl_info.surface_formats,
+        vendor: gl_info.vendor,
+        renderer: gl_info.renderer,
+    })
+}
+
+// The versions tried when probing a device, highest first.
+const GL_CANDIDATE_VERSIONS: [GLVersion; 10] = [
+    GLVersion::new(4, 6),
+    GLVersion::new(4, 5),
+    GLVersion::new(4, 3),
+    GLVersion::new(4, 1),
+    GLVersion::new(3, 3),
+    GLVersion::new(3, 2),
+    GLVersion::new(3, 1),
+    GLVersion::new(3, 0),
+    GLVersion::new(2, 1),
+    GLVersion::new(2, 0),
+];
+const GLES_CANDIDATE_VERSIONS: [GLVersion; 4] = [
+    GLVersion::new(3, 2),
+    GLVersion::new(3, 1),
+    GLVersion::new(3, 0),
+    GLVersion::new(2, 0),
+];
+
+fn candidate_versions(gl_api: GLApi) -> &'static [GLVersion] {
+    match gl_api {
+        GLApi::GL => &GL_CANDIDATE_VERSIONS,
+        GLApi::GLES => &GLES_CANDIDATE_VERSIONS,
+    }
+}
+
+// Creates and destroys a context with the given version, returning whether that worked.
+fn try_create_context<D>(device: &mut D, version: GLVersion) -> Result<bool, Error>
+where
+    D: DeviceInterface,
+{
+    let attributes = ContextAttributes {
+        version,
+        flags: ContextAttributeFlags::empty(),
+    };
+    let descriptor = match device.create_context_descriptor(&attributes) {
+        Ok(descriptor) => descriptor,
+        Err(_) => return Ok(false),
+    };
+    match device.create_context(&descriptor, None) {
+        Ok(mut context) => {
+            device.destroy_context(&mut context)?;
+            Ok(true)
+        }
+        Err(_) => Ok(false),
+    }
+}
+
+// Finds the lowest candidate version that a context can be created with. `max_version` is known
+// to work, so it is the answer if nothing lower does.
+fn probe_min_version<D>(device: &mut D, max_version: GLVersion) -> Result<GLVersion, Error>
+where
+    D: DeviceInterface,
+{
+    for &version in candidate_versions(device.gl_api()).iter().rev() {
+        if version >= max_version {
+            break;
+        }
+        if try_create_context(device, version)? {
+            return Ok(version);
+        }
+    }
+    Ok(max_version)
+}
+
+// Identifies the renderer behind a device by creating a throwaway context. `is_software` lets the
+// platform report a software renderer that the renderer string alone wouldn't reveal.
+pub(crate) fn probe_adapter_info<D>(device: &mut D, is_software: bool) -> Result<AdapterInfo, Error>
+where
+    D: DeviceInterface,
+{
+    let gl_info = GLInfo::probe(device, &|_, _| true)?;
+    Ok(AdapterInfo::new(
+        gl_info.vendor,
+        gl_info.renderer,
+        gl_info.version_string,
+        is_software,
+    ))
+}
+
+pub(crate) struct GLInfo {
+    pub(crate) version: GLVersion,
+    pub(crate) version_string: String,
+    pub(crate) flags: CapabilityFlags,
+    pub(crate) max_surface_size: Size2D<i32>,
+    pub(crate) surface_formats: Vec<SurfaceFormat>,
+    pub(crate) vendor: String,
+    pub(crate) renderer: String,
+}
+
+impl GLInfo {
+    // Creates a throwaway context on the device and queries it. The caller is responsible for
+    // restoring whatever context was current beforehand.
+    pub(crate) fn probe<D>(
+        device: &mut D,
+        supports_surface_format: &dyn Fn(&Gl, SurfaceFormat) -> bool,
+    ) -> Result<GLInfo, Error>
+    where
+        D: DeviceInterface,
+    {
+        let gl_api = device.gl_api();
+        let mut last_error = Error::Failed;
+        for &version in candidate_versions(gl_api) {
+            let attributes = ContextAttributes {
+                version,
+                flags: ContextAttributeFlags::empty(),
+            };
+            let descriptor = match device.create_context_descriptor(&attributes) {
+                Ok(descriptor) => descriptor,
+                Err(err) => {
+                    last_error = err;
+                    continue;
+                }
+            };
+            let mut context = match device.create_context(&descriptor, None) {
+                Ok(context) => context,
+                Err(err) => {
+                    last_error = err;
+                    continue;
+                }
+            };
+
+            let result = device.with_context_current(&context, |gl| {
+                GLInfo::current(gl, gl_api, supports_surface_format)
+            });
+            device.destroy_context(&mut context)?;
+            // Drivers may hand out a newer version than was asked for.
+            return result.map(|gl_info| GLInfo {
+                version: gl_info.version.max(version),
+                ..gl_info
+            });
+        }
+
+        Err(last_error)
+    }
+
+    fn current(
+        gl: &Gl,
+        gl_api: GLApi,
+        supports_surface_format: &dyn Fn(&Gl, SurfaceFormat) -> bool,
+    ) -> GLInfo {
+        unsafe {
+            let version = GLVersion::current(gl);
+            let extensions = gl.supported_extensions();
+
+            let mut flags = CapabilityFlags::empty();
+            let core_fences = match gl_api {
+                GLApi::GL => version.major > 3 || (version.major == 3 && version.minor >= 2),
+                GLApi::GLES => version.major >= 3,
+            };
+            if core_fences
+                || extensions.contains("GL_ARB_sync")
+                || extensions.contains("GL_APPLE_sync")
+            {
+                flags.insert(CapabilityFlags::FENCES);
+            }
+            if extensions.contains("GL_KHR_robustness")
+                || extensions.contains("GL_ARB_robustness")
+                || extensions.contains("GL_EXT_robustness")
+            {
+                flags.insert(CapabilityFlags::ROBUSTNESS);
+            }
+
+            // Surfaces are backed by a texture or renderbuffer, so both limits apply.
+            let max_texture_size = gl.get_parameter_i32(gl::MAX_TEXTURE_SIZE);
+            let max_renderbuffer_size = gl.get_parameter_i32(gl::MAX_RENDERBUFFER_SIZE);
+            let max_size = max_texture_size.min(max_renderbuffer_size);
+
+            GLInfo {
+                version,
+                version_string: gl.get_parameter_string(gl::VERSION),
+                flags,
+                max_surface_size: Size2D::new(max_size, max_size),
+                surface_formats: SurfaceFormat::ALL
+                    .into_iter()
+                    .filter(|&format| {
+                        gl_utils::texture_format(gl, format).is_some()
+                            && supports_surface_format(gl, format)
+                    })
+                    .collect(),
+                vendor: gl.get_parameter_string(gl::VENDOR),
+                renderer: gl.get_parameter_string(gl::RENDERER),
+            }
+        }
+    }
+}
@@ -15,26 +15,48 @@
 //! The producer may *swap* these buffers when it has finished drawing and has a surface ready to display.
 //!
 //! The consumer may *take* the front buffer, display it, then *recycle* it.
+//! By default only the latest front buffer is kept, and a front buffer nobody took is recycled
+//! at the next swap (*mailbox* queueing). With *FIFO* queueing, every front buffer is queued
+//! for the consumer in order, up to a fixed depth.
+//! Every swapped front buffer carries a `FrameInfo`, with an increasing frame id and the time of the swap,
+//! so consumers can spot stale frames and measure latency.
+//! Rather than polling, a consumer may block until the producer swaps with `wait_for_frame`,
+//! await `next_frame`, or register a callback to be told about every swap.
+//!
+//! A swap chain can be split into a `SwapChainProducer`, which only the producer context holds,
+//! and any number of `SwapChainConsumer`s, so that calling a producer method from a consumer
+//! is a type error rather than an `IncompatibleContext` error.
 //!
 //! Each producer context has one *attached* swap chain, whose back buffer is the current surface of the context.
 //! The producer may change the attached swap chain, attaching a currently unattached swap chain,
 //! and detaching the currently attached one.
+//!
+//! A *broadcast* swap chain hands every front buffer to all of its registered consumers at once,
+//! as a `PublishedSurface`. Each consumer receives either every frame, up to a fixed queue depth,
+//! or only the latest one, and the producer recycles a surface once every consumer has returned it.
 
 #![allow(missing_docs)]
 
 use crate::device::Device as DeviceAPI;
-use crate::{ContextID, Error, SurfaceAccess, SurfaceInfo, SurfaceType};
-use euclid::default::Size2D;
-use fnv::{FnvHashMap, FnvHashSet};
+use crate::published::PublishedSurface;
+use crate::{ContextID, Error, SurfaceAccess, SurfaceAttributes, SurfaceInfo, SurfaceType};
+use euclid::default::{Point2D, Rect, Size2D};
+use fnv::{FnvHashMap, FnvHasher};
 use glow as gl;
 use glow::Context as Gl;
 use glow::HasContext;
 use log::debug;
+use std::any::Any;
 use std::collections::hash_map::Entry;
+use std::collections::VecDeque;
 use std::fmt::Debug;
-use std::hash::Hash;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
 use std::mem;
-use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::task::{Context as TaskContext, Poll, Waker};
+use std::time::{Duration, Instant};
 
 // The data stored for each swap chain.
 struct SwapChainData<Device: DeviceAPI> {
@@ -44,19 +66,143 @@ struct SwapChainData<Device: DeviceAPI> {
     context_id: ContextID,
     // The surface access mode for the context.
     surface_access: SurfaceAccess,
+    // The buffers of surfaces created by the swap chain, or None to follow the context.
+    surface_attributes: Option<SurfaceAttributes>,
     // The back buffer of the swap chain.
     back_buffer: BackBuffer<Device>,
-    // Some if the producing context has finished drawing a new front buffer, ready to be displayed.
-    pending_surface: Option<Device::Surface>,
+    // The front buffers the producing context has finished drawing, ready to be displayed, oldest first.
+    pending_surfaces: VecDeque<(Device::Surface, FrameInfo)>,
+    // How front buffers are queued for the consumer.
+    queueing_mode: QueueingMode,
+    // The id of the next frame to be swapped.
+    next_frame_id: u64,
+    // Statistics about the frames and surfaces that have passed through the swap chain.
+    stats: SwapChainStats,
     // All of the surfaces that have already been displayed, ready to be recycled.
     recycled_surfaces: Vec<Device::Surface>,
+    // The tasks waiting for the producer to swap.
+    frame_wakers: Vec<Waker>,
+    // Called after every swap, once the swap chain is unlocked.
+    swap_callback: Option<SwapCallback>,
+}
+
+// The state shared by every handle to a swap chain.
+struct SwapChainShared<Device: DeviceAPI> {
+    data: Mutex<SwapChainData<Device>>,
+    // Signalled whenever the producer swaps.
+    frame_swapped: Condvar,
+    // Signalled whenever a consumer takes a front buffer.
+    frame_taken: Condvar,
+}
+
+/// How a swap chain queues front buffers for its consumer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueingMode {
+    /// Only the latest front buffer is kept. Swapping recycles a front buffer that nobody took.
+    Mailbox,
+    /// Every front buffer is queued, and consumers take them in order.
+    Fifo {
+        /// The most front buffers that can be waiting for a consumer at once. Must be at least 1.
+        depth: usize,
+        /// What swapping does when `depth` front buffers are already waiting.
+        when_full: QueueFull,
+    },
+}
+
+/// What swapping a FIFO swap chain does when its queue is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueFull {
+    /// Block until a consumer takes a front buffer.
+    Block,
+    /// Fail with `Error::SwapChainFull`, leaving the back buffer untouched.
+    Fail,
+}
+
+type SwapCallback = Arc<dyn Fn() + Send + Sync>;
+
+/// Data attached to a frame by the producer, passed on untouched to the consumer.
+pub type FrameUserData = Arc<dyn Any + Send + Sync>;
+
+/// Information about a front buffer, recorded when the producer swapped it.
+#[derive(Clone, Debug)]
+pub struct FrameInfo {
+    /// The id of the frame. Ids start at 0 and increase by one with every swap.
+    pub frame_id: u64,
+    /// When the producer swapped the frame.
+    pub swap_time: Instant,
+    /// The data the producer passed to `swap_buffers_with_user_data`, if any.
+    pub user_data: Option<FrameUserData>,
+}
+
+/// Statistics about the frames and surfaces that have passed through a swap chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapChainStats {
+    /// The number of times the producer has swapped.
+    pub frames_swapped: u64,
+    /// The number of swapped frames a consumer has taken.
+    pub frames_taken: u64,
+    /// The number of swapped frames recycled before any consumer took them.
+    pub frames_dropped: u64,
+    /// The number of back buffers reused from recycled surfaces.
+    pub recycle_hits: u64,
+    /// The number of surfaces the swap chain has created.
+    pub surfaces_allocated: u64,
+    /// The total time between swapping frames and consumers taking them.
+    pub total_latency: Duration,
+    /// The longest time between swapping a frame and a consumer taking it.
+    pub max_latency: Duration,
+}
+
+impl SwapChainStats {
+    /// The mean time between swapping a frame and a consumer taking it.
+    /// Returns `None` if no frames have been taken.
+    pub fn mean_latency(&self) -> Option<Duration> {
+        if self.frames_taken == 0 {
+            return None;
+        }
+        Some(self.total_latency.div_f64(self.frames_taken as f64))
+    }
 }
 
+/// Whether the contents of the old back buffer are copied into the new one.
 pub enum PreserveBuffer<'a> {
+    /// Copy the color, depth and stencil buffers in full.
     Yes(&'a Gl),
+    /// Copy the parts chosen by the options.
+    With(&'a Gl, PreserveOptions),
+    /// Leave the new back buffer's contents undefined.
     No,
 }
 
+/// Which parts of a back buffer `PreserveBuffer::With` copies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PreserveOptions {
+    /// Copy the depth and stencil buffers as well as the color buffer.
+    pub depth_stencil: bool,
+    /// Only copy the pixels inside this rectangle, in framebuffer coordinates
+    /// (origin at the bottom left). The rest of the new back buffer keeps whatever it held before.
+    pub damage: Option<Rect<i32>>,
+}
+
+impl Default for PreserveOptions {
+    fn default() -> PreserveOptions {
+        PreserveOptions {
+            depth_stencil: true,
+            damage: None,
+        }
+    }
+}
+
+impl PreserveBuffer<'_> {
+    fn options(&self) -> Option<(&Gl, PreserveOptions)> {
+        match *self {
+            PreserveBuffer::Yes(gl) => Some((gl, PreserveOptions::default())),
+            PreserveBuffer::With(gl, options) => Some((gl, options)),
+            PreserveBuffer::No => None,
+        }
+    }
+}
+
 enum BackBuffer<Device: DeviceAPI> {
     Attached,
     Detached(Device::Surface),
@@ -147,37 +293,50 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         device: &mut Device,
         context: &mut Device::Context,
         preserve_buffer: PreserveBuffer<'_>,
+        user_data: Option<FrameUserData>,
     ) -> Result<(), Error> {
         debug!("Swap buffers on context {:?}", self.context_id);
         self.validate_context(device, context)?;
 
-        // Recycle the old front buffer
-        if let Some(old_front_buffer) = self.pending_surface.take() {
-            let SurfaceInfo { id, size, .. } = device.surface_info(&old_front_buffer);
-            debug!(
-                "Recycling surface {:?} ({:?}) for context {:?}",
-                id, size, self.context_id
-            );
-            self.recycle_surface(old_front_buffer);
+        match self.queueing_mode {
+            // Recycle the old front buffer
+            QueueingMode::Mailbox => {
+                for (old_front_buffer, frame_info) in mem::take(&mut self.pending_surfaces) {
+                    let SurfaceInfo { id, size, .. } = device.surface_info(&old_front_buffer);
+                    debug!(
+                        "Dropping frame {} and recycling surface {:?} ({:?}) for context {:?}",
+                        frame_info.frame_id, id, size, self.context_id
+                    );
+                    self.stats.frames_dropped += 1;
+                    self.recycle_surface(old_front_buffer);
+                }
+            }
+            QueueingMode::Fifo { depth, .. } => {
+                if self.is_full(depth) {
+                    return Err(Error::SwapChainFull);
+                }
+            }
         }
 
         // Fetch a new back buffer, recycling presented buffers if possible.
-        let new_back_buffer = self
+        let new_back_buffer = match self
             .recycled_surfaces
             .iter()
             .position(|surface| device.surface_info(surface).size == self.size)
-            .map(|index| {
+        {
+            Some(index) => {
                 debug!("Recycling surface for context {:?}", self.context_id);
-                Ok(self.recycled_surfaces.swap_remove(index))
-            })
-            .unwrap_or_else(|| {
+                self.stats.recycle_hits += 1;
+                self.recycled_surfaces.swap_remove(index)
+            }
+            None => {
                 debug!(
                     "Creating a new surface ({:?}) for context {:?}",
                     self.size, self.context_id
                 );
-                let surface_type = SurfaceType::Generic { size: self.size };
-                device.create_surface(context, self.surface_access, surface_type)
-            })?;
+                self.create_surface(device, context, self.size)?
+            }
+        };
 
         let back_info = device.surface_info(&new_back_buffer);
 
@@ -191,27 +350,9 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         self.back_buffer
             .replace_surface(device, context, new_back_buffer)?;
 
-        if let PreserveBuffer::Yes(gl) = preserve_buffer {
+        if let Some((gl, options)) = preserve_buffer.options() {
             let front_info = device.surface_info(&new_front_buffer);
-            unsafe {
-                gl.bind_framebuffer(gl::READ_FRAMEBUFFER, front_info.framebuffer_object);
-                debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
-                gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, back_info.framebuffer_object);
-                debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
-                gl.blit_framebuffer(
-                    0,
-                    0,
-                    front_info.size.width,
-                    front_info.size.height,
-                    0,
-                    0,
-                    back_info.size.width,
-                    back_info.size.height,
-                    gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT,
-                    gl::NEAREST,
-                );
-                debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
-            }
+            preserve_contents(gl, &front_info, &back_info, &options);
         }
 
         // Update the state
@@ -220,7 +361,15 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
             device.surface_info(&new_front_buffer).id,
             self.context_id
         );
-        self.pending_surface = Some(new_front_buffer);
+        let frame_info = FrameInfo {
+            frame_id: self.next_frame_id,
+            swap_time: Instant::now(),
+            user_data,
+        };
+        self.next_frame_id += 1;
+        self.stats.frames_swapped += 1;
+        self.pending_surfaces
+            .push_back((new_front_buffer, frame_info));
         for mut surface in self.recycled_surfaces.drain(..) {
             debug!("Destroying a surface for context {:?}", self.context_id);
             device.destroy_surface(context, &mut surface)?;
@@ -229,6 +378,11 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         Ok(())
     }
 
+    // Are there already `depth` front buffers waiting for a consumer?
+    fn is_full(&self, depth: usize) -> bool {
+        self.pending_surfaces.len() >= depth
+    }
+
     // Swap the attached swap chain.
     // Called by the producer.
     // Returns an error if `context` is not the producer context for both swap chains.
@@ -263,6 +417,7 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         device: &mut Device,
         context: &mut Device::Context,
         size: Size2D<i32>,
+        preserve_buffer: PreserveBuffer<'_>,
     ) -> Result<(), Error> {
         debug!(
             "Resizing context {:?} to {:?}",
@@ -273,11 +428,15 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         if (size.width < 1) || (size.height < 1) {
             return Err(Error::Failed);
         }
-        let surface_type = SurfaceType::Generic { size };
-        let new_back_buffer = device.create_surface(context, self.surface_access, surface_type)?;
+        let new_back_buffer = self.create_surface(device, context, size)?;
+        let new_info = device.surface_info(&new_back_buffer);
         let mut old_back_buffer = self.back_buffer.take_surface(device, context)?;
         self.back_buffer
             .replace_surface(device, context, new_back_buffer)?;
+        if let Some((gl, options)) = preserve_buffer.options() {
+            let old_info = device.surface_info(&old_back_buffer);
+            preserve_contents(gl, &old_info, &new_info, &options);
+        }
         device.destroy_surface(context, &mut old_back_buffer)?;
         self.size = size;
         Ok(())
@@ -289,6 +448,25 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
         self.size
     }
 
+    // Create a new surface for this swap chain.
+    // Called by the producer.
+    fn create_surface(
+        &mut self,
+        device: &mut Device,
+        context: &Device::Context,
+        size: Size2D<i32>,
+    ) -> Result<Device::Surface, Error> {
+        let surface = create_surface(
+            device,
+            context,
+            self.surface_access,
+            size,
+            self.surface_attributes.as_ref(),
+        )?;
+        self.stats.surfaces_allocated += 1;
+        Ok(surface)
+    }
+
     // Take the current back buffer.
     // Called by a producer.
     fn take_surface_texture(
@@ -317,16 +495,32 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
     // Returns the most recent recycled surface if there is no current front buffer.
     // Called by a consumer.
     fn take_surface(&mut self) -> Option<Device::Surface> {
-        self.pending_surface
-            .take()
+        self.take_pending_surface()
+            .map(|(surface, _)| surface)
             .or_else(|| self.recycled_surfaces.pop())
     }
 
-    // Take the current front buffer.
+    // Take the current front buffer, and the information recorded when it was swapped.
+    // With FIFO queueing, this is the oldest front buffer in the queue.
     // Returns `None` if there is no current front buffer.
     // Called by a consumer.
-    fn take_pending_surface(&mut self) -> Option<Device::Surface> {
-        self.pending_surface.take()
+    fn take_pending_surface(&mut self) -> Option<(Device::Surface, FrameInfo)> {
+        let (surface, frame_info) = self.pending_surfaces.pop_front()?;
+        let latency = frame_info.swap_time.elapsed();
+        self.stats.frames_taken += 1;
+        self.stats.total_latency += latency;
+        self.stats.max_latency = self.stats.max_latency.max(latency);
+        Some((surface, frame_info))
+    }
+
+    // Take the current front buffer, or register `waker` to be woken by the next swap.
+    // Called by a consumer.
+    fn poll_pending_surface(&mut self, waker: &Waker) -> Option<Device::Surface> {
+        let frame = self.take_pending_surface();
+        if frame.is_none() && !self.frame_wakers.iter().any(|w| w.will_wake(waker)) {
+            self.frame_wakers.push(waker.clone());
+        }
+        frame.map(|(surface, _)| surface)
     }
 
     // Recycle the current front buffer.
@@ -456,10 +650,9 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
     // Returns an error if `context` is not the producer context for this swap chain.
     fn destroy(&mut self, device: &mut Device, context: &mut Device::Context) -> Result<(), Error> {
         self.validate_context(device, context)?;
-        let surfaces = self
-            .pending_surface
-            .take()
+        let surfaces = mem::take(&mut self.pending_surfaces)
             .into_iter()
+            .map(|(surface, _)| surface)
             .chain(self.back_buffer.take_surface(device, context).into_iter())
             .chain(self.recycled_surfaces.drain(..));
         for mut surface in surfaces {
@@ -470,7 +663,7 @@ impl<Device: DeviceAPI> SwapChainData<Device> {
 }
 
 /// A thread-safe swap chain.
-pub struct SwapChain<Device: DeviceAPI>(Arc<Mutex<SwapChainData<Device>>>);
+pub struct SwapChain<Device: DeviceAPI>(Arc<SwapChainShared<Device>>);
 
 // We can't derive Clone unfortunately
 impl<Device: DeviceAPI> Clone for SwapChain<Device> {
@@ -480,12 +673,29 @@ impl<Device: DeviceAPI> Clone for SwapChain<Device> {
 }
 
 impl<Device: DeviceAPI> SwapChain<Device> {
+    fn new(data: SwapChainData<Device>) -> SwapChain<Device> {
+        SwapChain(Arc::new(SwapChainShared {
+            data: Mutex::new(data),
+            frame_swapped: Condvar::new(),
+            frame_taken: Condvar::new(),
+        }))
+    }
+
     // Guarantee unique access to the swap chain data
     fn lock(&self) -> MutexGuard<SwapChainData<Device>> {
-        self.0.lock().unwrap_or_else(|err| err.into_inner())
+        self.0.data.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    // Let a producer blocked on a full queue know that a front buffer may have been taken.
+    fn notify_taken<T>(&self, taken: Option<T>) -> Option<T> {
+        if taken.is_some() {
+            self.0.frame_taken.notify_all();
+        }
+        taken
     }
 
     /// Swap the back and front buffers.
+    /// Wakes any consumers waiting for a frame, then calls the swap callback, if there is one.
     /// Called by the producer.
     /// Returns an error if `context` is not the producer context for this swap chain.
     pub fn swap_buffers(
@@ -494,7 +704,46 @@ impl<Device: DeviceAPI> SwapChain<Device> {
         context: &mut Device::Context,
         preserve_buffer: PreserveBuffer<'_>,
     ) -> Result<(), Error> {
-        self.lock().swap_buffers(device, context, preserve_buffer)
+        self.swap_buffers_with_user_data(device, context, preserve_buffer, None)
+    }
+
+    /// Swap the back and front buffers, attaching `user_data` to the new front buffer's `FrameInfo`.
+    /// Called by the producer.
+    /// Returns an error if `context` is not the producer context for this swap chain.
+    pub fn swap_buffers_with_user_data(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        preserve_buffer: PreserveBuffer<'_>,
+        user_data: Option<FrameUserData>,
+    ) -> Result<(), Error> {
+        let mut data = self.lock();
+        if let QueueingMode::Fifo {
+            depth,
+            when_full: QueueFull::Block,
+        } = data.queueing_mode
+        {
+            data.validate_context(device, context)?;
+            data = self
+                .0
+                .frame_taken
+                .wait_while(data, |data| data.is_full(depth))
+                .unwrap_or_else(|err| err.into_inner());
+        }
+        data.swap_buffers(device, context, preserve_buffer, user_data)?;
+        let wakers = mem::take(&mut data.frame_wakers);
+        let swap_callback = data.swap_callback.clone();
+        drop(data);
+
+        // Notify consumers without holding the lock, so that they can take the frame at once.
+        self.0.frame_swapped.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+        if let Some(swap_callback) = swap_callback {
+            swap_callback();
+        }
+        Ok(())
     }
 
     /// Swap the attached swap chain.
@@ -522,7 +771,21 @@ impl<Device: DeviceAPI> SwapChain<Device> {
         context: &mut Device::Context,
         size: Size2D<i32>,
     ) -> Result<(), Error> {
-        self.lock().resize(device, context, size)
+        self.resize_preserving_buffer(device, context, size, PreserveBuffer::No)
+    }
+
+    /// Resize the swap chain, copying the region the old and new back buffers have in common
+    /// as `preserve_buffer` says.
+    /// Called by the producer.
+    /// Returns an error if `context` is not the producer context for this swap chain.
+    pub fn resize_preserving_buffer(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        size: Size2D<i32>,
+        preserve_buffer: PreserveBuffer<'_>,
+    ) -> Result<(), Error> {
+        self.lock().resize(device, context, size, preserve_buffer)
     }
 
     /// Get the current size.
@@ -557,7 +820,63 @@ impl<Device: DeviceAPI> SwapChain<Device> {
     /// Returns `None` if there is no current front buffer.
     /// Called by a consumer.
     pub fn take_pending_surface(&self) -> Option<Device::Surface> {
-        self.lock().take_pending_surface()
+        self.take_pending_frame().map(|(surface, _)| surface)
+    }
+
+    /// Take the current front buffer, along with the information recorded when it was swapped.
+    /// With FIFO queueing, this is the oldest front buffer in the queue.
+    /// Returns `None` if there is no current front buffer.
+    /// Called by a consumer.
+    pub fn take_pending_frame(&self) -> Option<(Device::Surface, FrameInfo)> {
+        let frame = self.lock().take_pending_surface();
+        self.notify_taken(frame)
+    }
+
+    /// Get how this swap chain queues front buffers for its consumer.
+    pub fn queueing_mode(&self) -> QueueingMode {
+        self.lock().queueing_mode
+    }
+
+    /// Get the id the next swapped frame will have.
+    /// The latest frame has id `next_frame_id() - 1`, so consumers can tell if theirs is stale.
+    pub fn next_frame_id(&self) -> u64 {
+        self.lock().next_frame_id
+    }
+
+    /// Get statistics about the frames and surfaces that have passed through the swap chain.
+    pub fn stats(&self) -> SwapChainStats {
+        self.lock().stats
+    }
+
+    /// Take the current front buffer, blocking until the producer swaps if there isn't one.
+    /// Returns `None` if no front buffer arrives within `timeout`.
+    /// Called by a consumer.
+    pub fn wait_for_frame(&self, timeout: Duration) -> Option<Device::Surface> {
+        let data = self.lock();
+        let (mut data, _) = self
+            .0
+            .frame_swapped
+            .wait_timeout_while(data, timeout, |data| data.pending_surfaces.is_empty())
+            .unwrap_or_else(|err| err.into_inner());
+        let frame = data.take_pending_surface();
+        drop(data);
+        self.notify_taken(frame).map(|(surface, _)| surface)
+    }
+
+    /// Returns a future that resolves to the current front buffer,
+    /// or to the next one if there isn't a current front buffer.
+    /// Called by a consumer.
+    pub fn next_frame(&self) -> NextFrame<Device> {
+        NextFrame {
+            swap_chain: self.clone(),
+        }
+    }
+
+    /// Set a callback to be called after every swap, or `None` to remove it.
+    /// The callback is called on the producer's thread, after the swap chain has been unlocked,
+    /// so it may take the new front buffer itself.
+    pub fn set_swap_callback(&self, swap_callback: Option<Box<dyn Fn() + Send + Sync>>) {
+        self.lock().swap_callback = swap_callback.map(Arc::from);
     }
 
     /// Clear the current back buffer.
@@ -585,21 +904,91 @@ impl<Device: DeviceAPI> SwapChain<Device> {
         self.lock().destroy(device, context)
     }
 
+    /// Split the swap chain into its producer and consumer halves.
+    /// Other clones of the swap chain keep working, so for the split to mean anything,
+    /// this should be the only one.
+    pub fn split(self) -> (SwapChainProducer<Device>, SwapChainConsumer<Device>) {
+        let context_id = self.lock().context_id;
+        let consumer = SwapChainConsumer(self.clone());
+        let producer = SwapChainProducer {
+            swap_chain: self,
+            context_id,
+        };
+        (producer, consumer)
+    }
+
+    /// Get the attributes of the surfaces this swap chain creates.
+    /// Returns `None` if they follow the attributes of the producer context.
+    pub fn surface_attributes(&self) -> Option<SurfaceAttributes> {
+        self.lock().surface_attributes
+    }
+
     /// Create a new attached swap chain
     pub fn create_attached(
         device: &mut Device,
         context: &mut Device::Context,
         surface_access: SurfaceAccess,
     ) -> Result<SwapChain<Device>, Error> {
+        SwapChain::create_attached_internal(
+            device,
+            context,
+            surface_access,
+            None,
+            QueueingMode::Mailbox,
+        )
+    }
+
+    /// Create a new attached swap chain that queues front buffers as `queueing_mode` says.
+    /// Returns an error if a FIFO queue depth is 0.
+    pub fn create_attached_with_queueing_mode(
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+        queueing_mode: QueueingMode,
+    ) -> Result<SwapChain<Device>, Error> {
+        SwapChain::create_attached_internal(device, context, surface_access, None, queueing_mode)
+    }
+
+    /// Create a new attached swap chain whose new back buffers have the given attributes.
+    /// The first back buffer is the surface currently bound to the context.
+    pub fn create_attached_with_attributes(
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+        surface_attributes: &SurfaceAttributes,
+    ) -> Result<SwapChain<Device>, Error> {
+        SwapChain::create_attached_internal(
+            device,
+            context,
+            surface_access,
+            Some(*surface_attributes),
+            QueueingMode::Mailbox,
+        )
+    }
+
+    fn create_attached_internal(
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+        surface_attributes: Option<SurfaceAttributes>,
+        queueing_mode: QueueingMode,
+    ) -> Result<SwapChain<Device>, Error> {
+        validate_queueing_mode(queueing_mode)?;
         let size = device.context_surface_info(context).unwrap().unwrap().size;
-        Ok(SwapChain(Arc::new(Mutex::new(SwapChainData {
+        Ok(SwapChain::new(SwapChainData {
             size,
             context_id: device.context_id(context),
             surface_access,
+            surface_attributes,
             back_buffer: BackBuffer::Attached,
-            pending_surface: None,
+            pending_surfaces: VecDeque::new(),
+            queueing_mode,
+            next_frame_id: 0,
+            stats: SwapChainStats::default(),
             recycled_surfaces: Vec::new(),
-        }))))
+            frame_wakers: Vec::new(),
+            swap_callback: None,
+        }))
     }
 
     /// Create a new detached swap chain
@@ -609,16 +998,154 @@ impl<Device: DeviceAPI> SwapChain<Device> {
         surface_access: SurfaceAccess,
         size: Size2D<i32>,
     ) -> Result<SwapChain<Device>, Error> {
-        let surface_type = SurfaceType::Generic { size };
-        let surface = device.create_surface(context, surface_access, surface_type)?;
-        Ok(SwapChain(Arc::new(Mutex::new(SwapChainData {
+        SwapChain::create_detached_internal(
+            device,
+            context,
+            surface_access,
+            size,
+            None,
+            QueueingMode::Mailbox,
+        )
+    }
+
+    /// Create a new detached swap chain that queues front buffers as `queueing_mode` says.
+    /// Returns an error if a FIFO queue depth is 0.
+    pub fn create_detached_with_queueing_mode(
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+        size: Size2D<i32>,
+        queueing_mode: QueueingMode,
+    ) -> Result<SwapChain<Device>, Error> {
+        SwapChain::create_detached_internal(
+            device,
+            context,
+            surface_access,
+            size,
+            None,
+            queueing_mode,
+        )
+    }
+
+    /// Create a new detached swap chain whose surfaces have the given attributes
+    pub fn create_detached_with_attributes(
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+        size: Size2D<i32>,
+        surface_attributes: &SurfaceAttributes,
+    ) -> Result<SwapChain<Device>, Error> {
+        SwapChain::create_detached_internal(
+            device,
+            context,
+            surface_access,
+            size,
+            Some(*surface_attributes),
+            QueueingMode::Mailbox,
+        )
+    }
+
+    fn create_detached_internal(
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+        size: Size2D<i32>,
+        surface_attributes: Option<SurfaceAttributes>,
+        queueing_mode: QueueingMode,
+    ) -> Result<SwapChain<Device>, Error> {
+        validate_queueing_mode(queueing_mode)?;
+        let surface = create_surface(
+            device,
+            context,
+            surface_access,
+            size,
+            surface_attributes.as_ref(),
+        )?;
+        Ok(SwapChain::new(SwapChainData {
             size,
             context_id: device.context_id(context),
             surface_access,
+            surface_attributes,
             back_buffer: BackBuffer::Detached(surface),
-            pending_surface: None,
+            pending_surfaces: VecDeque::new(),
+            queueing_mode,
+            next_frame_id: 0,
+            stats: SwapChainStats {
+                surfaces_allocated: 1,
+                ..SwapChainStats::default()
+            },
             recycled_surfaces: Vec::new(),
-        }))))
+            frame_wakers: Vec::new(),
+            swap_callback: None,
+        }))
+    }
+}
+
+// Returns an error if a FIFO queue can never hold a front buffer.
+fn validate_queueing_mode(queueing_mode: QueueingMode) -> Result<(), Error> {
+    match queueing_mode {
+        QueueingMode::Fifo { depth: 0, .. } => Err(Error::Failed),
+        _ => Ok(()),
+    }
+}
+
+// Copy the contents of one surface into another, as far as they overlap.
+fn preserve_contents(
+    gl: &Gl,
+    src_info: &SurfaceInfo,
+    dest_info: &SurfaceInfo,
+    options: &PreserveOptions,
+) {
+    let overlap = Rect::new(Point2D::origin(), src_info.size.min(dest_info.size));
+    let region = match options.damage {
+        Some(damage) => match overlap.intersection(&damage) {
+            Some(region) => region,
+            None => return,
+        },
+        None => overlap,
+    };
+    let mask = if options.depth_stencil {
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT
+    } else {
+        gl::COLOR_BUFFER_BIT
+    };
+    unsafe {
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, src_info.framebuffer_object);
+        debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
+        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, dest_info.framebuffer_object);
+        debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
+        gl.blit_framebuffer(
+            region.min_x(),
+            region.min_y(),
+            region.max_x(),
+            region.max_y(),
+            region.min_x(),
+            region.min_y(),
+            region.max_x(),
+            region.max_y(),
+            mask,
+            gl::NEAREST,
+        );
+        debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
+    }
+}
+
+// Create a generic surface, with the given attributes if there are any.
+fn create_surface<Device: DeviceAPI>(
+    device: &mut Device,
+    context: &Device::Context,
+    surface_access: SurfaceAccess,
+    size: Size2D<i32>,
+    surface_attributes: Option<&SurfaceAttributes>,
+) -> Result<Device::Surface, Error> {
+    match surface_attributes {
+        Some(surface_attributes) => device.create_generic_surface_with_attributes(
+            context,
+            surface_access,
+            &size,
+            surface_attributes,
+        ),
+        None => device.create_surface(context, surface_access, SurfaceType::Generic { size }),
     }
 }
 
@@ -633,7 +1160,15 @@ where
     /// Returns the most recent recycled surface if there is no current front buffer.
     /// Called by a consumer.
     fn take_surface(&self) -> Option<Device::Surface> {
-        self.lock().take_surface()
+        let surface = self.lock().take_surface();
+        self.notify_taken(surface)
+    }
+
+    /// Take the current front buffer, along with the information recorded when it was swapped.
+    /// Returns `None` if there is no current front buffer.
+    /// Called by a consumer.
+    fn take_frame(&self) -> Option<(Device::Surface, FrameInfo)> {
+        self.take_pending_frame()
     }
 
     /// Recycle the current front buffer.
@@ -643,150 +1178,901 @@ where
     }
 }
 
-/// A thread-safe collection of swap chains.
-#[derive(Default)]
-pub struct SwapChains<SwapChainID: Eq + Hash, Device: DeviceAPI> {
-    // The swap chain ids, indexed by context id
-    ids: Arc<Mutex<FnvHashMap<ContextID, FnvHashSet<SwapChainID>>>>,
-    // The swap chains, indexed by swap chain id
-    table: Arc<RwLock<FnvHashMap<SwapChainID, SwapChain<Device>>>>,
+/// The producer half of a swap chain, bound to the producer context.
+///
+/// There is only ever one producer, so it can't be cloned.
+/// Created by `SwapChain::split()`.
+pub struct SwapChainProducer<Device: DeviceAPI> {
+    swap_chain: SwapChain<Device>,
+    context_id: ContextID,
 }
 
-// We can't derive Clone unfortunately
-impl<SwapChainID: Eq + Hash, Device: DeviceAPI> Clone for SwapChains<SwapChainID, Device> {
-    fn clone(&self) -> Self {
-        SwapChains {
-            ids: self.ids.clone(),
-            table: self.table.clone(),
-        }
+impl<Device: DeviceAPI> SwapChainProducer<Device> {
+    /// Get the id of the producer context.
+    pub fn context_id(&self) -> ContextID {
+        self.context_id
     }
-}
 
-impl<SwapChainID, Device> SwapChains<SwapChainID, Device>
-where
-    SwapChainID: Clone + Eq + Hash + Debug,
-    Device: DeviceAPI,
-{
-    /// Create a new collection.
-    pub fn new() -> SwapChains<SwapChainID, Device> {
-        SwapChains {
-            ids: Arc::new(Mutex::new(FnvHashMap::default())),
-            table: Arc::new(RwLock::new(FnvHashMap::default())),
+    // Check the producer context before taking the swap chain lock.
+    fn validate_context(&self, device: &Device, context: &Device::Context) -> Result<(), Error> {
+        if self.context_id == device.context_id(context) {
+            Ok(())
+        } else {
+            Err(Error::IncompatibleContext)
         }
     }
 
-    // Lock the ids
-    fn ids(&self) -> MutexGuard<FnvHashMap<ContextID, FnvHashSet<SwapChainID>>> {
-        self.ids.lock().unwrap_or_else(|err| err.into_inner())
-    }
-
-    // Lock the lookup table
-    fn table(&self) -> RwLockReadGuard<FnvHashMap<SwapChainID, SwapChain<Device>>> {
-        self.table.read().unwrap_or_else(|err| err.into_inner())
-    }
-
-    // Lock the lookup table for writing
-    fn table_mut(&self) -> RwLockWriteGuard<FnvHashMap<SwapChainID, SwapChain<Device>>> {
-        self.table.write().unwrap_or_else(|err| err.into_inner())
+    /// Create another consumer of this swap chain.
+    pub fn consumer(&self) -> SwapChainConsumer<Device> {
+        SwapChainConsumer(self.swap_chain.clone())
     }
 
-    /// Create a new attached swap chain and insert it in the table.
-    /// Returns an error if the `id` is already in the table.
-    pub fn create_attached_swap_chain(
+    /// Swap the back and front buffers.
+    /// Returns an error if `context` is not the producer context.
+    pub fn swap_buffers(
         &self,
-        id: SwapChainID,
         device: &mut Device,
         context: &mut Device::Context,
-        surface_access: SurfaceAccess,
+        preserve_buffer: PreserveBuffer<'_>,
     ) -> Result<(), Error> {
-        match self.table_mut().entry(id.clone()) {
-            Entry::Occupied(_) => Err(Error::Failed)?,
-            Entry::Vacant(entry) => {
-                entry.insert(SwapChain::create_attached(device, context, surface_access)?)
-            }
-        };
-        self.ids()
-            .entry(device.context_id(context))
-            .or_insert_with(Default::default)
-            .insert(id);
-        Ok(())
+        self.validate_context(device, context)?;
+        self.swap_chain
+            .swap_buffers(device, context, preserve_buffer)
     }
 
-    /// Create a new dettached swap chain and insert it in the table.
-    /// Returns an error if the `id` is already in the table.
-    pub fn create_detached_swap_chain(
+    /// Swap the back and front buffers, attaching `user_data` to the new front buffer's `FrameInfo`.
+    /// Returns an error if `context` is not the producer context.
+    pub fn swap_buffers_with_user_data(
         &self,
-        id: SwapChainID,
-        size: Size2D<i32>,
         device: &mut Device,
         context: &mut Device::Context,
-        surface_access: SurfaceAccess,
+        preserve_buffer: PreserveBuffer<'_>,
+        user_data: Option<FrameUserData>,
     ) -> Result<(), Error> {
-        match self.table_mut().entry(id.clone()) {
-            Entry::Occupied(_) => Err(Error::Failed)?,
-            Entry::Vacant(entry) => entry.insert(SwapChain::create_detached(
-                device,
-                context,
-                surface_access,
-                size,
-            )?),
-        };
-        self.ids()
-            .entry(device.context_id(context))
-            .or_insert_with(Default::default)
-            .insert(id);
-        Ok(())
+        self.validate_context(device, context)?;
+        self.swap_chain
+            .swap_buffers_with_user_data(device, context, preserve_buffer, user_data)
     }
 
-    /// Destroy a swap chain.
-    /// Called by the producer.
-    /// Returns an error if `context` is not the producer context for the swap chain.
-    pub fn destroy(
+    /// Swap the attached swap chain.
+    /// Returns an error if `context` is not the producer context for both swap chains.
+    /// Returns an error if this swap chain is attached, or the other swap chain is detached.
+    pub fn take_attachment_from(
         &self,
-        id: SwapChainID,
         device: &mut Device,
         context: &mut Device::Context,
+        other: &SwapChainProducer<Device>,
     ) -> Result<(), Error> {
-        if let Some(swap_chain) = self.table_mut().remove(&id) {
-            swap_chain.destroy(device, context)?;
-        }
-        if let Some(ids) = self.ids().get_mut(&device.context_id(context)) {
-            ids.remove(&id);
-        }
-        Ok(())
+        self.validate_context(device, context)?;
+        other.validate_context(device, context)?;
+        self.swap_chain
+            .take_attachment_from(device, context, &other.swap_chain)
     }
 
-    /// Destroy all the swap chains for a particular producer context.
-    /// Called by the producer.
-    pub fn destroy_all(
+    /// Resize the swap chain.
+    /// This creates a new back buffer of the appropriate size,
+    /// and destroys the old one.
+    /// Returns an error if `context` is not the producer context.
+    pub fn resize(
         &self,
         device: &mut Device,
         context: &mut Device::Context,
+        size: Size2D<i32>,
+    ) -> Result<(), Error> {
+        self.validate_context(device, context)?;
+        self.swap_chain.resize(device, context, size)
+    }
+
+    /// Resize the swap chain, copying the region the old and new back buffers have in common
+    /// as `preserve_buffer` says.
+    /// Returns an error if `context` is not the producer context.
+    pub fn resize_preserving_buffer(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        size: Size2D<i32>,
+        preserve_buffer: PreserveBuffer<'_>,
+    ) -> Result<(), Error> {
+        self.validate_context(device, context)?;
+        self.swap_chain
+            .resize_preserving_buffer(device, context, size, preserve_buffer)
+    }
+
+    /// Get the current size.
+    pub fn size(&self) -> Size2D<i32> {
+        self.swap_chain.size()
+    }
+
+    /// Take the current back buffer.
+    /// Returns an error if `context` is not the producer context.
+    pub fn take_surface_texture(
+        &self,
+        device: &Device,
+        context: &mut Device::Context,
+    ) -> Result<Device::SurfaceTexture, Error> {
+        self.validate_context(device, context)?;
+        self.swap_chain.take_surface_texture(device, context)
+    }
+
+    /// Recycle the current back buffer.
+    /// Returns an error if `context` is not the producer context.
+    pub fn recycle_surface_texture(
+        &self,
+        device: &Device,
+        context: &mut Device::Context,
+        surface_texture: Device::SurfaceTexture,
     ) -> Result<(), Error> {
-        if let Some(mut ids) = self.ids().remove(&device.context_id(context)) {
-            for id in ids.drain() {
-                if let Some(swap_chain) = self.table_mut().remove(&id) {
-                    swap_chain.destroy(device, context)?;
+        self.validate_context(device, context)?;
+        self.swap_chain
+            .recycle_surface_texture(device, context, surface_texture)
+    }
+
+    /// Clear the current back buffer.
+    /// Returns an error if `context` is not the producer context.
+    pub fn clear_surface(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        gl: &Gl,
+        color: [f32; 4],
+    ) -> Result<(), Error> {
+        self.validate_context(device, context)?;
+        self.swap_chain.clear_surface(device, context, gl, color)
+    }
+
+    /// Is this the attached swap chain?
+    pub fn is_attached(&self) -> bool {
+        self.swap_chain.is_attached()
+    }
+
+    /// Get statistics about the frames and surfaces that have passed through the swap chain.
+    pub fn stats(&self) -> SwapChainStats {
+        self.swap_chain.stats()
+    }
+
+    /// Destroy the swap chain.
+    /// Returns an error if `context` is not the producer context.
+    pub fn destroy(&self, device: &mut Device, context: &mut Device::Context) -> Result<(), Error> {
+        self.validate_context(device, context)?;
+        self.swap_chain.destroy(device, context)
+    }
+}
+
+/// A consumer half of a swap chain. Consumers can be cloned and sent to other threads.
+/// Created by `SwapChain::split()` or `SwapChainProducer::consumer()`.
+pub struct SwapChainConsumer<Device: DeviceAPI>(SwapChain<Device>);
+
+// We can't derive Clone unfortunately
+impl<Device: DeviceAPI> Clone for SwapChainConsumer<Device> {
+    fn clone(&self) -> Self {
+        SwapChainConsumer(self.0.clone())
+    }
+}
+
+impl<Device: DeviceAPI> SwapChainConsumer<Device> {
+    /// Get the current size.
+    pub fn size(&self) -> Size2D<i32> {
+        self.0.size()
+    }
+
+    /// Take the current front buffer.
+    /// Returns `None` if there is no current front buffer.
+    pub fn take_pending_surface(&self) -> Option<Device::Surface> {
+        self.0.take_pending_surface()
+    }
+
+    /// Take the current front buffer, along with the information recorded when it was swapped.
+    /// Returns `None` if there is no current front buffer.
+    pub fn take_pending_frame(&self) -> Option<(Device::Surface, FrameInfo)> {
+        self.0.take_pending_frame()
+    }
+
+    /// Take the current front buffer, blocking until the producer swaps if there isn't one.
+    /// Returns `None` if no front buffer arrives within `timeout`.
+    pub fn wait_for_frame(&self, timeout: Duration) -> Option<Device::Surface> {
+        self.0.wait_for_frame(timeout)
+    }
+
+    /// Returns a future that resolves to the current front buffer,
+    /// or to the next one if there isn't a current front buffer.
+    pub fn next_frame(&self) -> NextFrame<Device> {
+        self.0.next_frame()
+    }
+
+    /// Set a callback to be called after every swap, or `None` to remove it.
+    pub fn set_swap_callback(&self, swap_callback: Option<Box<dyn Fn() + Send + Sync>>) {
+        self.0.set_swap_callback(swap_callback)
+    }
+
+    /// Get the id the next swapped frame will have.
+    pub fn next_frame_id(&self) -> u64 {
+        self.0.next_frame_id()
+    }
+
+    /// Get statistics about the frames and surfaces that have passed through the swap chain.
+    pub fn stats(&self) -> SwapChainStats {
+        self.0.stats()
+    }
+}
+
+impl<Device> SwapChainAPI for SwapChainConsumer<Device>
+where
+    Device: 'static + DeviceAPI,
+    Device::Surface: Send,
+{
+    type Surface = Device::Surface;
+
+    /// Take the current front buffer.
+    /// Returns the most recent recycled surface if there is no current front buffer.
+    fn take_surface(&self) -> Option<Device::Surface> {
+        self.0.take_surface()
+    }
+
+    /// Take the current front buffer, along with the information recorded when it was swapped.
+    /// Returns `None` if there is no current front buffer.
+    fn take_frame(&self) -> Option<(Device::Surface, FrameInfo)> {
+        self.0.take_pending_frame()
+    }
+
+    /// Recycle the current front buffer.
+    fn recycle_surface(&self, surface: Device::Surface) {
+        self.0.recycle_surface(surface)
+    }
+}
+
+/// A future that resolves to the front buffer of a swap chain,
+/// returned by `SwapChain::next_frame()`.
+pub struct NextFrame<Device: DeviceAPI> {
+    swap_chain: SwapChain<Device>,
+}
+
+impl<Device: DeviceAPI> Future for NextFrame<Device> {
+    type Output = Device::Surface;
+
+    fn poll(self: Pin<&mut Self>, task_context: &mut TaskContext) -> Poll<Device::Surface> {
+        let surface = self
+            .swap_chain
+            .lock()
+            .poll_pending_surface(task_context.waker());
+        match self.swap_chain.notify_taken(surface) {
+            Some(surface) => Poll::Ready(surface),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Which frames a consumer of a broadcast swap chain receives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameDelivery {
+    /// The consumer receives every frame, in order. Swapping fails with `Error::SwapChainFull`
+    /// while `depth` frames are already waiting for the consumer.
+    EveryFrame {
+        /// The most frames that can be waiting for the consumer at once. Must be at least 1.
+        depth: usize,
+    },
+    /// The consumer only receives the latest frame. Frames it hasn't taken yet are skipped when a
+    /// new one arrives.
+    LatestFrame,
+}
+
+/// Identifies a consumer registered with a broadcast swap chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConsumerID(usize);
+
+// The data stored for each broadcast swap chain.
+struct BroadcastSwapChainData<Device: DeviceAPI> {
+    // The size of the back buffer
+    size: Size2D<i32>,
+    // The id of the producer context
+    context_id: ContextID,
+    // The surface access mode for the context.
+    surface_access: SurfaceAccess,
+    // The back buffer of the swap chain.
+    back_buffer: BackBuffer<Device>,
+    // The registered consumers.
+    consumers: FnvHashMap<ConsumerID, BroadcastConsumer<Device>>,
+    // The id of the next consumer to be registered.
+    next_consumer_id: usize,
+    // Front buffers that some consumers may not have returned yet.
+    published_surfaces: Vec<PublishedSurface<Device>>,
+    // Front buffers that every consumer has returned, ready to be recycled.
+    recycled_surfaces: Vec<Device::Surface>,
+}
+
+struct BroadcastConsumer<Device: DeviceAPI> {
+    delivery: FrameDelivery,
+    // The frames that the consumer hasn't taken yet, oldest first.
+    frames: VecDeque<PublishedSurface<Device>>,
+}
+
+impl<Device: DeviceAPI> BroadcastConsumer<Device> {
+    // Are there already as many frames waiting as the consumer accepts?
+    fn is_full(&self) -> bool {
+        match self.delivery {
+            FrameDelivery::EveryFrame { depth } => self.frames.len() >= depth,
+            FrameDelivery::LatestFrame => false,
+        }
+    }
+}
+
+impl<Device: DeviceAPI> BroadcastSwapChainData<Device> {
+    // Returns `Ok` if `context` is the producer context for this swap chain.
+    fn validate_context(&self, device: &Device, context: &Device::Context) -> Result<(), Error> {
+        if self.context_id == device.context_id(context) {
+            Ok(())
+        } else {
+            Err(Error::IncompatibleContext)
+        }
+    }
+
+    // Take back the front buffers that every consumer has finished with.
+    fn reclaim_surfaces(&mut self) {
+        for published_surface in mem::take(&mut self.published_surfaces) {
+            match published_surface.reclaim() {
+                Ok(surface) => self.recycled_surfaces.push(surface),
+                Err(published_surface) => self.published_surfaces.push(published_surface),
+            }
+        }
+    }
+
+    // Swap the back and front buffers, and send the new front buffer to every consumer.
+    // Called by the producer.
+    // Returns an error if `context` is not the producer context for this swap chain.
+    fn swap_buffers(
+        &mut self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        preserve_buffer: PreserveBuffer<'_>,
+    ) -> Result<(), Error> {
+        debug!("Broadcast swap buffers on context {:?}", self.context_id);
+        self.validate_context(device, context)?;
+        if self.consumers.values().any(BroadcastConsumer::is_full) {
+            return Err(Error::SwapChainFull);
+        }
+        self.reclaim_surfaces();
+
+        // Fetch a new back buffer, recycling returned buffers if possible.
+        let new_back_buffer = match self
+            .recycled_surfaces
+            .iter()
+            .position(|surface| device.surface_info(surface).size == self.size)
+        {
+            Some(index) => self.recycled_surfaces.swap_remove(index),
+            None => create_surface(device, context, self.surface_access, self.size, None)?,
+        };
+        let back_info = device.surface_info(&new_back_buffer);
+
+        // Swap the buffers
+        let new_front_buffer = self.back_buffer.take_surface(device, context)?;
+        self.back_buffer
+            .replace_surface(device, context, new_back_buffer)?;
+
+        if let Some((gl, options)) = preserve_buffer.options() {
+            let front_info = device.surface_info(&new_front_buffer);
+            preserve_contents(gl, &front_info, &back_info, &options);
+        }
+
+        // Broadcast the front buffer. If nobody is listening, it can be reused straight away.
+        if self.consumers.is_empty() {
+            self.recycled_surfaces.push(new_front_buffer);
+        } else {
+            let published_surface = PublishedSurface::new(new_front_buffer);
+            for consumer in self.consumers.values_mut() {
+                if consumer.delivery == FrameDelivery::LatestFrame {
+                    consumer.frames.clear();
                 }
+                consumer.frames.push_back(published_surface.clone());
+            }
+            self.published_surfaces.push(published_surface);
+        }
+
+        // Keep one spare surface, and destroy the rest.
+        let spare_surfaces = self.recycled_surfaces.len().min(1);
+        for mut surface in self.recycled_surfaces.drain(spare_surfaces..) {
+            debug!("Destroying a surface for context {:?}", self.context_id);
+            device.destroy_surface(context, &mut surface)?;
+        }
+
+        Ok(())
+    }
+
+    // Resize the swap chain.
+    // Called by the producer.
+    // Returns an error if `context` is not the producer context for this swap chain.
+    // Returns an error if `size` is smaller than (1, 1).
+    fn resize(
+        &mut self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        size: Size2D<i32>,
+    ) -> Result<(), Error> {
+        self.validate_context(device, context)?;
+        if (size.width < 1) || (size.height < 1) {
+            return Err(Error::Failed);
+        }
+        let new_back_buffer = create_surface(device, context, self.surface_access, size, None)?;
+        let mut old_back_buffer = self.back_buffer.take_surface(device, context)?;
+        self.back_buffer
+            .replace_surface(device, context, new_back_buffer)?;
+        device.destroy_surface(context, &mut old_back_buffer)?;
+        self.size = size;
+        Ok(())
+    }
+
+    // Register a new consumer.
+    // Called by a consumer.
+    // Returns an error if an `EveryFrame` queue depth is 0.
+    fn register_consumer(&mut self, delivery: FrameDelivery) -> Result<ConsumerID, Error> {
+        if let FrameDelivery::EveryFrame { depth: 0 } = delivery {
+            return Err(Error::Failed);
+        }
+        let id = ConsumerID(self.next_consumer_id);
+        self.next_consumer_id += 1;
+        self.consumers.insert(
+            id,
+            BroadcastConsumer {
+                delivery,
+                frames: VecDeque::new(),
+            },
+        );
+        Ok(id)
+    }
+
+    // Take the oldest frame that the consumer hasn't taken yet.
+    // Returns `None` if there is no new frame, or the consumer isn't registered.
+    // Called by a consumer.
+    fn take_frame(&mut self, consumer_id: ConsumerID) -> Option<PublishedSurface<Device>> {
+        self.consumers.get_mut(&consumer_id)?.frames.pop_front()
+    }
+
+    // Is this the attached swap chain?
+    fn is_attached(&self) -> bool {
+        match self.back_buffer {
+            BackBuffer::Attached | BackBuffer::TakenAttached => true,
+            BackBuffer::Detached(_) | BackBuffer::TakenDetached => false,
+        }
+    }
+
+    // Has a consumer taken a frame without returning it yet?
+    // Frames still waiting in the consumer queues don't count, since those are ours to drop.
+    fn has_outstanding_frames(&self) -> bool {
+        self.published_surfaces.iter().any(|published_surface| {
+            let queued_count = self
+                .consumers
+                .values()
+                .flat_map(|consumer| consumer.frames.iter())
+                .filter(|frame| frame.ptr_eq(published_surface))
+                .count();
+            published_surface.handle_count() > queued_count + 1
+        })
+    }
+
+    // Destroy the swap chain.
+    // Called by the producer.
+    // Returns an error if `context` is not the producer context for this swap chain, or if a
+    // consumer still holds a frame, in which case the swap chain is left untouched.
+    fn destroy(&mut self, device: &mut Device, context: &mut Device::Context) -> Result<(), Error> {
+        self.validate_context(device, context)?;
+        // Consumers can only take new handles through us, so once no frame is outstanding, every
+        // published surface can be reclaimed.
+        if self.has_outstanding_frames() {
+            return Err(Error::Failed);
+        }
+        self.consumers.clear();
+        self.reclaim_surfaces();
+        debug_assert!(self.published_surfaces.is_empty());
+        let surfaces = self
+            .back_buffer
+            .take_surface(device, context)
+            .into_iter()
+            .chain(self.recycled_surfaces.drain(..));
+        for mut surface in surfaces {
+            device.destroy_surface(context, &mut surface)?;
+        }
+        Ok(())
+    }
+}
+
+/// A thread-safe swap chain that sends every front buffer to all of its consumers.
+///
+/// Consumers register with `register_consumer()`, then repeatedly take frames with
+/// `take_frame()`, sample them through `PublishedSurface::create_surface_texture()`, and return
+/// them with `recycle_frame()` once their surface textures have been destroyed.
+pub struct BroadcastSwapChain<Device: DeviceAPI>(Arc<Mutex<BroadcastSwapChainData<Device>>>);
+
+// We can't derive Clone unfortunately
+impl<Device: DeviceAPI> Clone for BroadcastSwapChain<Device> {
+    fn clone(&self) -> Self {
+        BroadcastSwapChain(self.0.clone())
+    }
+}
+
+impl<Device: DeviceAPI> BroadcastSwapChain<Device> {
+    // Guarantee unique access to the swap chain data
+    fn lock(&self) -> MutexGuard<BroadcastSwapChainData<Device>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Swap the back and front buffers, and send the new front buffer to every consumer.
+    /// Called by the producer.
+    /// Returns an error if `context` is not the producer context for this swap chain.
+    /// Returns `Error::SwapChainFull`, leaving the back buffer untouched, if an `EveryFrame`
+    /// consumer already has `depth` frames waiting.
+    pub fn swap_buffers(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        preserve_buffer: PreserveBuffer<'_>,
+    ) -> Result<(), Error> {
+        self.lock().swap_buffers(device, context, preserve_buffer)
+    }
+
+    /// Resize the swap chain.
+    /// This creates a new back buffer of the appropriate size,
+    /// and destroys the old one.
+    /// Called by the producer.
+    /// Returns an error if `context` is not the producer context for this swap chain.
+    pub fn resize(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        size: Size2D<i32>,
+    ) -> Result<(), Error> {
+        self.lock().resize(device, context, size)
+    }
+
+    /// Get the current size.
+    /// Called by a consumer.
+    pub fn size(&self) -> Size2D<i32> {
+        self.lock().size
+    }
+
+    /// Register a new consumer, which receives the frames swapped from now on.
+    /// Called by a consumer.
+    /// Returns an error if an `EveryFrame` queue depth is 0.
+    pub fn register_consumer(&self, delivery: FrameDelivery) -> Result<ConsumerID, Error> {
+        self.lock().register_consumer(delivery)
+    }
+
+    /// Unregister a consumer, dropping any frames it hasn't taken yet.
+    /// Frames it has already taken must still be recycled.
+    /// Called by a consumer.
+    pub fn unregister_consumer(&self, consumer_id: ConsumerID) {
+        self.lock().consumers.remove(&consumer_id);
+    }
+
+    /// Take the oldest frame that the consumer hasn't taken yet.
+    /// Returns `None` if there is no new frame, or the consumer isn't registered.
+    /// Called by a consumer.
+    pub fn take_frame(&self, consumer_id: ConsumerID) -> Option<PublishedSurface<Device>> {
+        self.lock().take_frame(consumer_id)
+    }
+
+    /// Return a frame to the producer.
+    /// Every surface texture created from the frame must have been destroyed,
+    /// or the producer will never be able to reuse it.
+    /// Called by a consumer.
+    pub fn recycle_frame(&self, frame: PublishedSurface<Device>) {
+        // The producer reclaims the surface once the last handle to it is gone.
+        drop(frame)
+    }
+
+    /// Is this the attached swap chain?
+    pub fn is_attached(&self) -> bool {
+        self.lock().is_attached()
+    }
+
+    /// Destroy the swap chain.
+    /// Called by the producer.
+    /// Returns an error if `context` is not the producer context for this swap chain,
+    /// or if a consumer still holds a frame. The swap chain is then left untouched,
+    /// and can be destroyed once every frame has been recycled.
+    pub fn destroy(&self, device: &mut Device, context: &mut Device::Context) -> Result<(), Error> {
+        self.lock().destroy(device, context)
+    }
+
+    /// Create a new attached broadcast swap chain
+    pub fn create_attached(
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+    ) -> Result<BroadcastSwapChain<Device>, Error> {
+        let size = device.context_surface_info(context).unwrap().unwrap().size;
+        Ok(BroadcastSwapChain::new(
+            device,
+            context,
+            surface_access,
+            size,
+            BackBuffer::Attached,
+        ))
+    }
+
+    /// Create a new detached broadcast swap chain
+    pub fn create_detached(
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+        size: Size2D<i32>,
+    ) -> Result<BroadcastSwapChain<Device>, Error> {
+        let surface = create_surface(device, context, surface_access, size, None)?;
+        Ok(BroadcastSwapChain::new(
+            device,
+            context,
+            surface_access,
+            size,
+            BackBuffer::Detached(surface),
+        ))
+    }
+
+    fn new(
+        device: &Device,
+        context: &Device::Context,
+        surface_access: SurfaceAccess,
+        size: Size2D<i32>,
+        back_buffer: BackBuffer<Device>,
+    ) -> BroadcastSwapChain<Device> {
+        BroadcastSwapChain(Arc::new(Mutex::new(BroadcastSwapChainData {
+            size,
+            context_id: device.context_id(context),
+            surface_access,
+            back_buffer,
+            consumers: FnvHashMap::default(),
+            next_consumer_id: 0,
+            published_surfaces: Vec::new(),
+            recycled_surfaces: Vec::new(),
+        })))
+    }
+}
+
+// The number of shards the swap chain table is split into. Lookups only lock the shard holding
+// the swap chain, so creating or destroying a swap chain doesn't stall lookups of the others.
+const SHARD_COUNT: usize = 16;
+
+// A shard of the swap chain table, indexed by swap chain id
+type Shard<SwapChainID, Device> = RwLock<FnvHashMap<SwapChainID, SwapChain<Device>>>;
+
+// The swap chains of a context. Snapshots are never modified, only replaced, so iterating over
+// one doesn't need to hold any lock.
+type Snapshot<SwapChainID, Device> = Arc<Vec<(SwapChainID, SwapChain<Device>)>>;
+
+/// A thread-safe collection of swap chains.
+///
+/// Lookups only lock the shard of the table that holds the swap chain, and iteration works on an
+/// immutable snapshot of each context's swap chains, so many threads can use the collection at
+/// once without contending.
+pub struct SwapChains<SwapChainID: Eq + Hash, Device: DeviceAPI> {
+    // Snapshots of the swap chains, indexed by context id
+    ids: Arc<Mutex<FnvHashMap<ContextID, Snapshot<SwapChainID, Device>>>>,
+    // The swap chains, split into shards by the hash of their id
+    table: Arc<[Shard<SwapChainID, Device>]>,
+}
+
+// We can't derive Clone unfortunately
+impl<SwapChainID: Eq + Hash, Device: DeviceAPI> Clone for SwapChains<SwapChainID, Device> {
+    fn clone(&self) -> Self {
+        SwapChains {
+            ids: self.ids.clone(),
+            table: self.table.clone(),
+        }
+    }
+}
+
+// We can't derive Default either, since the table needs all of its shards
+impl<SwapChainID: Eq + Hash, Device: DeviceAPI> Default for SwapChains<SwapChainID, Device> {
+    fn default() -> Self {
+        SwapChains {
+            ids: Arc::new(Mutex::new(FnvHashMap::default())),
+            table: (0..SHARD_COUNT)
+                .map(|_| RwLock::new(FnvHashMap::default()))
+                .collect(),
+        }
+    }
+}
+
+impl<SwapChainID, Device> SwapChains<SwapChainID, Device>
+where
+    SwapChainID: Clone + Eq + Hash + Debug,
+    Device: DeviceAPI,
+{
+    /// Create a new collection.
+    pub fn new() -> SwapChains<SwapChainID, Device> {
+        SwapChains::default()
+    }
+
+    /// Get the consumer half of a swap chain.
+    /// Unlike `SwapChainsAPI::get()`, this can't be used to swap, resize or destroy the swap chain.
+    pub fn get_consumer(&self, id: SwapChainID) -> Option<SwapChainConsumer<Device>> {
+        debug!("Getting swap chain consumer {:?}", id);
+        self.table(&id).get(&id).cloned().map(SwapChainConsumer)
+    }
+
+    // Lock the ids
+    fn ids(&self) -> MutexGuard<FnvHashMap<ContextID, Snapshot<SwapChainID, Device>>> {
+        self.ids.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    // The shard of the lookup table that holds `id`
+    fn shard(&self, id: &SwapChainID) -> &Shard<SwapChainID, Device> {
+        let mut hasher = FnvHasher::default();
+        id.hash(&mut hasher);
+        &self.table[hasher.finish() as usize % self.table.len()]
+    }
+
+    // Lock the shard of the lookup table that holds `id`
+    fn table(
+        &self,
+        id: &SwapChainID,
+    ) -> RwLockReadGuard<FnvHashMap<SwapChainID, SwapChain<Device>>> {
+        self.shard(id).read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    // Lock the shard of the lookup table that holds `id` for writing
+    fn table_mut(
+        &self,
+        id: &SwapChainID,
+    ) -> RwLockWriteGuard<FnvHashMap<SwapChainID, SwapChain<Device>>> {
+        self.shard(id)
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    // Check that `id` isn't in the table before creating a swap chain for it.
+    fn check_vacant(&self, id: &SwapChainID) -> Result<(), Error> {
+        if self.table(id).contains_key(id) {
+            Err(Error::Failed)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Insert a newly created swap chain in the table, and add it to its context's snapshot.
+    // The swap chain is created before this is called, so the table isn't locked while its
+    // surfaces are allocated. If another swap chain was inserted with the same `id` in the
+    // meantime, the new one is destroyed and this returns an error.
+    fn insert(
+        &self,
+        id: SwapChainID,
+        device: &mut Device,
+        context: &mut Device::Context,
+        swap_chain: SwapChain<Device>,
+    ) -> Result<(), Error> {
+        let inserted = match self.table_mut(&id).entry(id.clone()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(swap_chain.clone());
+                true
+            }
+        };
+        if !inserted {
+            swap_chain.destroy(device, context)?;
+            return Err(Error::Failed);
+        }
+        let mut ids = self.ids();
+        let snapshot = ids.entry(device.context_id(context)).or_default();
+        let mut swap_chains = Vec::with_capacity(snapshot.len() + 1);
+        swap_chains.extend(snapshot.iter().cloned());
+        swap_chains.push((id, swap_chain));
+        *snapshot = Arc::new(swap_chains);
+        Ok(())
+    }
+
+    // Replace the snapshot of a context's swap chains with one that doesn't include `id`.
+    fn remove_id(&self, context_id: ContextID, id: &SwapChainID) {
+        let mut ids = self.ids();
+        if let Some(snapshot) = ids.get_mut(&context_id) {
+            if snapshot.iter().any(|(other_id, _)| other_id == id) {
+                *snapshot = Arc::new(
+                    snapshot
+                        .iter()
+                        .filter(|(other_id, _)| other_id != id)
+                        .cloned()
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    /// Create a new attached swap chain and insert it in the table.
+    /// Returns an error if the `id` is already in the table.
+    pub fn create_attached_swap_chain(
+        &self,
+        id: SwapChainID,
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+    ) -> Result<(), Error> {
+        self.check_vacant(&id)?;
+        let swap_chain = SwapChain::create_attached(device, context, surface_access)?;
+        self.insert(id, device, context, swap_chain)
+    }
+
+    /// Create a new dettached swap chain and insert it in the table.
+    /// Returns an error if the `id` is already in the table.
+    pub fn create_detached_swap_chain(
+        &self,
+        id: SwapChainID,
+        size: Size2D<i32>,
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+    ) -> Result<(), Error> {
+        self.check_vacant(&id)?;
+        let swap_chain = SwapChain::create_detached(device, context, surface_access, size)?;
+        self.insert(id, device, context, swap_chain)
+    }
+
+    /// Create a new detached swap chain whose surfaces have the given attributes,
+    /// and insert it in the table.
+    /// Returns an error if the `id` is already in the table.
+    pub fn create_detached_swap_chain_with_attributes(
+        &self,
+        id: SwapChainID,
+        size: Size2D<i32>,
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+        surface_attributes: &SurfaceAttributes,
+    ) -> Result<(), Error> {
+        self.check_vacant(&id)?;
+        let swap_chain = SwapChain::create_detached_with_attributes(
+            device,
+            context,
+            surface_access,
+            size,
+            surface_attributes,
+        )?;
+        self.insert(id, device, context, swap_chain)
+    }
+
+    /// Destroy a swap chain.
+    /// Called by the producer.
+    /// Returns an error if `context` is not the producer context for the swap chain.
+    pub fn destroy(
+        &self,
+        id: SwapChainID,
+        device: &mut Device,
+        context: &mut Device::Context,
+    ) -> Result<(), Error> {
+        let swap_chain = self.table_mut(&id).remove(&id);
+        if let Some(swap_chain) = swap_chain {
+            swap_chain.destroy(device, context)?;
+        }
+        self.remove_id(device.context_id(context), &id);
+        Ok(())
+    }
+
+    /// Destroy all the swap chains for a particular producer context.
+    /// Called by the producer.
+    pub fn destroy_all(
+        &self,
+        device: &mut Device,
+        context: &mut Device::Context,
+    ) -> Result<(), Error> {
+        let snapshot = self.ids().remove(&device.context_id(context));
+        for (id, _) in snapshot.iter().flat_map(|snapshot| snapshot.iter()) {
+            let swap_chain = self.table_mut(id).remove(id);
+            if let Some(swap_chain) = swap_chain {
+                swap_chain.destroy(device, context)?;
             }
         }
         Ok(())
     }
 
     /// Iterate over all the swap chains for a particular producer context.
+    /// This iterates over a snapshot, which swap chains created or destroyed
+    /// in the meantime don't affect.
     /// Called by the producer.
     pub fn iter(
         &self,
         device: &mut Device,
         context: &mut Device::Context,
     ) -> impl Iterator<Item = (SwapChainID, SwapChain<Device>)> {
-        self.ids()
+        let snapshot = self
+            .ids()
             .get(&device.context_id(context))
-            .iter()
-            .flat_map(|ids| ids.iter())
-            .filter_map(|id| Some((id.clone(), self.table().get(id)?.clone())))
-            .collect::<Vec<_>>()
-            .into_iter()
+            .cloned()
+            .unwrap_or_default();
+        (0..snapshot.len()).map(move |index| snapshot[index].clone())
     }
 }
 
@@ -802,7 +2088,7 @@ where
     /// Get a swap chain
     fn get(&self, id: SwapChainID) -> Option<SwapChain<Device>> {
         debug!("Getting swap chain {:?}", id);
-        self.table().get(&id).cloned()
+        self.table(&id).get(&id).cloned()
     }
 }
 
@@ -813,6 +2099,15 @@ pub trait SwapChainAPI: 'static + Clone + Send {
     /// Take the current front buffer.
     fn take_surface(&self) -> Option<Self::Surface>;
 
+    /// Take the current front buffer, along with the information recorded when it was swapped.
+    /// Returns `None` if there is no current front buffer.
+    ///
+    /// The default implementation always returns `None`, for swap chains that don't record frame
+    /// information; use `take_surface()` with those.
+    fn take_frame(&self) -> Option<(Self::Surface, FrameInfo)> {
+        None
+    }
+
     /// Recycle the current front buffer.
     fn recycle_surface(&self, surface: Self::Surface);
 }
@@ -25,12 +25,34 @@ pub trait Connection: Sized {
     /// Connects to the default display.
     fn new() -> Result<Self, Error>;
 
+    /// Connects to the default display using the backend with the given name.
+    ///
+    /// The name must be one of those returned by `backend_names()`; otherwise, an
+    /// `UnknownBackend` error is returned.
+    fn new_with_backend(backend: &str) -> Result<Self, Error>;
+
+    /// Returns the names of the backends that this connection type can use, in the order that
+    /// `new()` tries them.
+    ///
+    /// Connection types that wrap a single windowing system return one name, such as `"x11"`.
+    fn backend_names() -> Vec<&'static str>;
+
+    /// Returns the name of the backend that this connection is using.
+    fn backend_name(&self) -> &'static str;
+
     /// Returns the native connection corresponding to this connection.
     fn native_connection(&self) -> Self::NativeConnection;
 
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
     fn gl_api(&self) -> GLApi;
 
+    /// Returns this connection, switched to the given OpenGL API flavor.
+    ///
+    /// Devices subsequently created from the returned connection create contexts of that flavor.
+    /// If the backend can't provide the requested flavor, an `UnsupportedGLType` error is
+    /// returned.
+    fn with_gl_api(self, gl_api: GLApi) -> Result<Self, Error>;
+
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
@@ -4,9 +4,10 @@
 
 #![allow(unused_imports)]
 
+use crate::device::Device as DeviceInterface;
 use crate::gl;
 use crate::info::GLVersion;
-use crate::Gl;
+use crate::{Error, Gl};
 
 use std::ffi::CStr;
 use std::os::raw::c_char;
@@ -43,6 +44,47 @@ bitflags! {
         /// The OpenGL compatibility profile will be used. If this is not present, the core profile
         /// is used.
         const COMPATIBILITY_PROFILE = 0x08;
+        /// Requests a context that doesn't generate OpenGL errors (`KHR_no_error`). Erroneous
+        /// commands have undefined behavior in such a context, so this is mainly useful for
+        /// release builds.
+        ///
+        /// This is a hint: where it's unsupported, an ordinary context is created instead.
+        const NO_ERROR              = 0x10;
+        /// Requests a lower scheduling priority than other contexts, which suits background work
+        /// such as texture uploads (`EGL_IMG_context_priority`).
+        ///
+        /// This is a hint: where it's unsupported, the context gets the default priority.
+        const LOW_PRIORITY          = 0x20;
+        /// Requests a higher scheduling priority than other contexts (`EGL_IMG_context_priority`).
+        /// If `LOW_PRIORITY` or `MEDIUM_PRIORITY` is also set, this one wins.
+        ///
+        /// This is a hint: where it's unsupported, the context gets the default priority.
+        const HIGH_PRIORITY         = 0x40;
+        /// Requests the medium scheduling priority (`EGL_IMG_context_priority`). If
+        /// `LOW_PRIORITY` is also set, this one wins.
+        ///
+        /// Medium is the default priority, so `Device::context_descriptor()` never reports this
+        /// flag.
+        const MEDIUM_PRIORITY       = 0x80;
+    }
+}
+
+impl ContextAttributeFlags {
+    // The flags that are only requests and may not take effect. To find out which ones did, look
+    // at the attributes of `Device::context_descriptor()` for the created context.
+    #[cfg(any(
+        android_platform,
+        angle,
+        free_unix,
+        ohos_platform,
+        test,
+        feature = "sm-test"
+    ))]
+    pub(crate) fn hints(self) -> ContextAttributeFlags {
+        self & (ContextAttributeFlags::NO_ERROR
+            | ContextAttributeFlags::LOW_PRIORITY
+            | ContextAttributeFlags::MEDIUM_PRIORITY
+            | ContextAttributeFlags::HIGH_PRIORITY)
     }
 }
 
@@ -70,6 +112,44 @@ impl ContextAttributes {
     }
 }
 
+// Tries each candidate in order by creating a throwaway context, and returns the descriptor of the
+// first context that could be created. That descriptor records the version that the driver
+// actually provided, which may be newer than the one requested. The caller is responsible for
+// restoring whatever context was current beforehand.
+pub(crate) fn negotiate_context_descriptor<D>(
+    device: &mut D,
+    candidates: &[ContextAttributes],
+) -> Result<D::ContextDescriptor, Error>
+where
+    D: DeviceInterface,
+{
+    let mut last_error = Error::UnsupportedGLVersion;
+    for attributes in candidates {
+        let descriptor = match device.create_context_descriptor(attributes) {
+            Ok(descriptor) => descriptor,
+            Err(err) => {
+                last_error = err;
+                continue;
+            }
+        };
+        let mut context = match device.create_context(&descriptor, None) {
+            Ok(context) => context,
+            Err(err) => {
+                last_error = err;
+                continue;
+            }
+        };
+
+        // Newly-created contexts are current, which `context_descriptor()` needs on some
+        // backends in order to query the version.
+        let actual_descriptor = device.context_descriptor(&context);
+        device.destroy_context(&mut context)?;
+        return Ok(actual_descriptor);
+    }
+
+    Err(last_error)
+}
+
 #[cfg(any(target_os = "android", target_env = "ohos"))]
 pub(crate) fn current_context_uses_compatibility_profile(_gl: &Gl) -> bool {
     false
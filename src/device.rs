@@ -3,7 +3,10 @@
 //! The abstract interface that all devices conform to.
 
 use super::connection::Connection as ConnectionInterface;
-use crate::{ContextAttributes, ContextID, Error, GLApi, SurfaceAccess, SurfaceInfo, SurfaceType};
+use crate::{
+    AdapterInfo, Capabilities, ContextAttributes, ContextID, Error, GLApi, Gl, SurfaceAccess,
+    SurfaceAttributes, SurfaceInfo, SurfaceType,
+};
 use euclid::default::Size2D;
 use glow::Texture;
 
@@ -28,6 +31,8 @@ where
     type Surface;
     /// The surface texture type associated with this device.
     type SurfaceTexture;
+    /// The guard type that restores the previously-current context when dropped.
+    type CurrentContextGuard;
 
     // device.rs
 
@@ -43,6 +48,19 @@ where
     /// Returns the OpenGL API flavor that this device supports (OpenGL or OpenGL ES).
     fn gl_api(&self) -> GLApi;
 
+    /// Returns a description of what this device supports.
+    ///
+    /// This creates and destroys a temporary context in order to query OpenGL. Whatever context
+    /// was current on this thread beforehand remains current afterward.
+    fn capabilities(&mut self) -> Result<Capabilities, Error>;
+
+    /// Returns the vendor, renderer, and driver version of the GPU that this device renders with,
+    /// and whether it is a software renderer.
+    ///
+    /// This creates and destroys a temporary context in order to query OpenGL. Whatever context
+    /// was current on this thread beforehand remains current afterward.
+    fn adapter_info(&mut self) -> Result<AdapterInfo, Error>;
+
     // context.rs
 
     /// Creates a context descriptor with the given attributes.
@@ -53,6 +71,21 @@ where
         attributes: &ContextAttributes,
     ) -> Result<Self::ContextDescriptor, Error>;
 
+    /// Creates a context descriptor for the first of the given candidate attributes that the
+    /// driver can create a context with.
+    ///
+    /// Candidates are tried in order, so list the most preferred (typically the newest version)
+    /// first. Each candidate is checked by creating and destroying a temporary context; whatever
+    /// context was current on this thread beforehand remains current afterward.
+    ///
+    /// `context_descriptor_attributes()` on the returned descriptor reports the version that the
+    /// driver actually provided, which may be newer than the candidate that succeeded. If no
+    /// candidate works, the error from the last one is returned.
+    fn create_context_descriptor_with_fallbacks(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<Self::ContextDescriptor, Error>;
+
     /// Creates a new OpenGL context and makes it current.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -90,6 +123,25 @@ where
     /// made current.
     fn make_no_context_current(&self) -> Result<(), Error>;
 
+    /// Makes the context current for the duration of the given closure, then restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    ///
+    /// The previous state is captured from the native API, so contexts that were not created by
+    /// `surfman` (for example, those owned by an embedding application) are restored as well. If
+    /// no context was current, none will be current afterward.
+    fn with_context_current<F, R>(&self, context: &Self::Context, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&Gl) -> R;
+
+    /// Makes the context current and returns a guard that, when dropped, restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    ///
+    /// Unlike `with_context_current()`, this doesn't borrow the context while the guard is alive.
+    fn temporarily_make_context_current(
+        &self,
+        context: &Self::Context,
+    ) -> Result<Self::CurrentContextGuard, Error>;
+
     /// Returns the attributes that the context descriptor was created with.
     fn context_descriptor_attributes(
         &self,
@@ -157,6 +209,21 @@ where
         surface_type: SurfaceType<<Self::Connection as ConnectionInterface>::NativeWidget>,
     ) -> Result<Self::Surface, Error>;
 
+    /// Creates a generic surface whose color format and depth/stencil buffers are given by
+    /// `surface_attributes` rather than by the attributes of the context.
+    ///
+    /// `create_surface()` is equivalent to calling this with
+    /// `SurfaceAttributes::for_context()` of the context's attributes. Where generic surfaces are
+    /// pbuffers (ANGLE, or EGL without `EGL_KHR_image_base`), the depth and stencil buffers come
+    /// from the context's config instead.
+    fn create_generic_surface_with_attributes(
+        &mut self,
+        context: &Self::Context,
+        surface_access: SurfaceAccess,
+        size: &Size2D<i32>,
+        surface_attributes: &SurfaceAttributes,
+    ) -> Result<Self::Surface, Error>;
+
     /// Creates a surface texture from an existing generic surface for use with the given context.
     ///
     /// The surface texture is local to the supplied context and takes ownership of the surface.
@@ -165,8 +232,13 @@ where
     /// *The supplied context does not have to be the same context that the surface is associated
     /// with.* This allows you to render to a surface in one context and sample from that surface
     /// in another context.
+    /// Surfaces with more than one layer are the exception: see `SurfaceAttributes::layers`.
     ///
     /// Calling this method on a widget surface returns a `WidgetAttached` error.
+    ///
+    /// Implementations must only read from the surface, and `destroy_surface_texture()` must hand
+    /// it back unchanged. `PublishedSurface` relies on this to share one surface among several
+    /// surface textures.
     fn create_surface_texture(
         &self,
         context: &mut Self::Context,
@@ -201,7 +273,9 @@ where
 
     /// Returns the OpenGL texture target needed to read from this surface texture.
     ///
-    /// This will be `GL_TEXTURE_2D` or `GL_TEXTURE_RECTANGLE`, depending on platform.
+    /// This will be `GL_TEXTURE_2D` or `GL_TEXTURE_RECTANGLE`, depending on platform. Some surface
+    /// textures need a different target; `surface_texture_target()` reports the right one for
+    /// each.
     fn surface_gl_texture_target(&self) -> u32;
 
     /// Displays the contents of a widget surface on screen.
@@ -237,4 +311,12 @@ where
     ///
     /// It is only legal to read from, not write to, this texture object.
     fn surface_texture_object(&self, surface_texture: &Self::SurfaceTexture) -> Option<Texture>;
+
+    /// Returns the OpenGL texture target that the texture object of this surface texture must be
+    /// bound to.
+    ///
+    /// This is `surface_gl_texture_target()`, except for surface textures of layered surfaces,
+    /// which are `GL_TEXTURE_2D_ARRAY`, and of imported EGL images, which use the target that they
+    /// were imported with.
+    fn surface_texture_target(&self, surface_texture: &Self::SurfaceTexture) -> u32;
 }
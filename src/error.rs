@@ -94,6 +94,24 @@ pub enum Error {
     IncompatibleNativeContext,
     /// The native device does not match the supplied connection.
     IncompatibleNativeDevice,
+    /// No backend with the requested name is available.
+    UnknownBackend,
+    /// Every backend failed to open a connection. The failures are listed in the order that the
+    /// backends were tried.
+    NoBackendAvailable(Vec<BackendFailure>),
+    /// The device can't render to surfaces of the requested color format.
+    UnsupportedSurfaceFormat,
+    /// The swap chain's queue of frames waiting for a consumer is full.
+    SwapChainFull,
+}
+
+/// Describes why a particular backend couldn't be used.
+#[derive(Debug)]
+pub struct BackendFailure {
+    /// The name of the backend, such as `"wayland"`.
+    pub backend: String,
+    /// The error that the backend produced.
+    pub error: Error,
 }
 
 /// Abstraction of the errors that EGL, CGL, GLX, CGL, etc. return.
@@ -5,7 +5,7 @@
 use glow::{HasContext, NativeFramebuffer};
 
 use crate::gl;
-use crate::Gl;
+use crate::{Gl, SurfaceFormat};
 
 #[allow(dead_code)]
 pub(crate) fn create_and_bind_framebuffer(
@@ -48,3 +48,93 @@ pub(crate) fn destroy_framebuffer(gl: &Gl, framebuffer_object: NativeFramebuffer
         gl.delete_framebuffer(framebuffer_object);
     }
 }
+
+// The arguments to `glTexImage2D` that allocate a texture of some surface format.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TextureFormat {
+    pub(crate) internal_format: u32,
+    pub(crate) format: u32,
+    pub(crate) ty: u32,
+}
+
+impl TextureFormat {
+    // The internal format to give renderbuffers of this format, which must be sized.
+    #[cfg(any(android_platform, angle, free_unix, ohos_platform))]
+    pub(crate) fn sized_internal_format(&self) -> u32 {
+        // From `GL_EXT_texture_format_BGRA8888`.
+        const BGRA8_EXT: u32 = 0x93a1;
+
+        match self.internal_format {
+            gl::RGBA => gl::RGBA8,
+            gl::RED => gl::R8,
+            gl::BGRA => BGRA8_EXT,
+            internal_format => internal_format,
+        }
+    }
+}
+
+// Returns how to allocate a texture of the given surface format in the current context, or `None`
+// if the context can't render to that format.
+#[allow(dead_code)]
+pub(crate) fn texture_format(gl: &Gl, surface_format: SurfaceFormat) -> Option<TextureFormat> {
+    let version = gl.version();
+    let extensions = gl.supported_extensions();
+    let gl3 = version.major >= 3;
+    // OpenGL ES 2.0 only has unsized internal formats, named by the pixel transfer format.
+    let sized = |internal_format, format| {
+        if version.is_embedded && !gl3 {
+            format
+        } else {
+            internal_format
+        }
+    };
+    let texture_format = |internal_format, format, ty| {
+        Some(TextureFormat {
+            internal_format,
+            format,
+            ty,
+        })
+    };
+
+    match surface_format {
+        SurfaceFormat::RGBA8 => texture_format(gl::RGBA, gl::RGBA, gl::UNSIGNED_BYTE),
+        SurfaceFormat::RGBA16F => {
+            let renderable = if version.is_embedded {
+                extensions.contains("GL_EXT_color_buffer_half_float")
+                    || (gl3 && extensions.contains("GL_EXT_color_buffer_float"))
+            } else {
+                gl3 || extensions.contains("GL_ARB_color_buffer_float")
+            };
+            if !renderable {
+                return None;
+            }
+            let ty = if version.is_embedded && !gl3 {
+                gl::HALF_FLOAT_OES
+            } else {
+                gl::HALF_FLOAT
+            };
+            texture_format(sized(gl::RGBA16F, gl::RGBA), gl::RGBA, ty)
+        }
+        SurfaceFormat::RGB10A2 if gl3 => {
+            texture_format(gl::RGB10_A2, gl::RGBA, gl::UNSIGNED_INT_2_10_10_10_REV)
+        }
+        SurfaceFormat::RGB10A2 => None,
+        SurfaceFormat::R8
+            if gl3
+                || extensions.contains("GL_ARB_texture_rg")
+                || extensions.contains("GL_EXT_texture_rg") =>
+        {
+            texture_format(sized(gl::R8, gl::RED), gl::RED, gl::UNSIGNED_BYTE)
+        }
+        SurfaceFormat::R8 => None,
+        // Desktop OpenGL can upload BGRA data into any texture, but OpenGL ES needs a BGRA texture.
+        SurfaceFormat::BGRA8 if !version.is_embedded => {
+            texture_format(gl::RGBA8, gl::BGRA, gl::UNSIGNED_BYTE)
+        }
+        SurfaceFormat::BGRA8 if extensions.contains("GL_EXT_texture_format_BGRA8888") => {
+            texture_format(gl::BGRA, gl::BGRA, gl::UNSIGNED_BYTE)
+        }
+        SurfaceFormat::BGRA8 => None,
+    }
+}
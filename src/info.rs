@@ -18,7 +18,7 @@ pub enum GLApi {
 ///
 /// Since OpenGL and OpenGL ES have different version numbering schemes, the valid values here
 /// depend on the value of `Device::gl_api()`.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct GLVersion {
     /// The major OpenGL version (e.g. 4 in 4.2).
     pub major: u8,
@@ -29,7 +29,7 @@ pub struct GLVersion {
 impl GLVersion {
     /// Creates a GL version structure with the given major and minor version numbers.
     #[inline]
-    pub fn new(major: u8, minor: u8) -> GLVersion {
+    pub const fn new(major: u8, minor: u8) -> GLVersion {
         GLVersion { major, minor }
     }
 
@@ -42,3 +42,51 @@ impl GLVersion {
         }
     }
 }
+
+/// Identifies the GPU, or software rasterizer, that a device renders with.
+///
+/// Call `Device::adapter_info()` to fetch this.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdapterInfo {
+    /// The company responsible for the driver (`GL_VENDOR`).
+    pub vendor: String,
+    /// The name of the renderer (`GL_RENDERER`), which typically identifies the GPU.
+    pub renderer: String,
+    /// The driver version, as reported in the OpenGL version string (`GL_VERSION`).
+    pub driver_version: String,
+    /// True if rendering happens on the CPU (for example, llvmpipe or SwiftShader).
+    pub is_software: bool,
+}
+
+impl AdapterInfo {
+    pub(crate) fn new(
+        vendor: String,
+        renderer: String,
+        driver_version: String,
+        is_software: bool,
+    ) -> AdapterInfo {
+        let is_software = is_software || renderer_is_software(&renderer);
+        AdapterInfo {
+            vendor,
+            renderer,
+            driver_version,
+            is_software,
+        }
+    }
+}
+
+fn renderer_is_software(renderer: &str) -> bool {
+    const SOFTWARE_RENDERERS: [&str; 7] = [
+        "llvmpipe",
+        "softpipe",
+        "swrast",
+        "software rasterizer",
+        "swiftshader",
+        "gdi generic",
+        "apple software renderer",
+    ];
+    let renderer = renderer.to_lowercase();
+    SOFTWARE_RENDERERS
+        .iter()
+        .any(|software_renderer| renderer.contains(software_renderer))
+}
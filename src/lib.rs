@@ -34,18 +34,24 @@ pub use platform::system::surface::Surface as SystemSurface;
 pub mod chains;
 pub mod connection;
 pub mod device;
+pub mod owned;
+pub mod published;
 
 pub mod error;
-pub use crate::error::{Error, WindowingApiError};
+pub use crate::error::{BackendFailure, Error, WindowingApiError};
+
+mod capabilities;
+pub use crate::capabilities::{Capabilities, CapabilityFlags};
 
 mod context;
 pub use crate::context::{ContextAttributeFlags, ContextAttributes, ContextID};
 
 mod info;
-pub use crate::info::{GLApi, GLVersion};
+pub use crate::info::{AdapterInfo, GLApi, GLVersion};
 
 mod surface;
-pub use crate::surface::{SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType, SystemSurfaceInfo};
+pub use crate::surface::{SurfaceAccess, SurfaceAttachmentFlags, SurfaceAttributes, SurfaceFormat};
+pub use crate::surface::{SurfaceID, SurfaceInfo, SurfaceType, SystemSurfaceInfo};
 
 pub mod macros;
 pub(crate) use macros::implement_interfaces;
@@ -36,8 +36,8 @@ macro_rules! declare_surfman {
 macro_rules! implement_interfaces {
     () => {
         mod implementation {
-            use super::connection::{Connection, NativeConnection};
-            use super::context::{Context, ContextDescriptor, NativeContext};
+            use super::connection::{Connection, NativeConnection, BACKEND_NAME};
+            use super::context::{Context, ContextDescriptor, CurrentContextGuard, NativeContext};
             use super::device::{Adapter, Device, NativeDevice};
             use super::surface::{NativeWidget, Surface, SurfaceTexture};
             use euclid::default::Size2D;
@@ -46,8 +46,9 @@ macro_rules! implement_interfaces {
             use $crate::connection::Connection as ConnectionInterface;
             use $crate::device::Device as DeviceInterface;
             use $crate::info::GLApi;
-            use $crate::Error;
-            use $crate::{ContextAttributes, ContextID, SurfaceAccess, SurfaceInfo, SurfaceType};
+            use $crate::{AdapterInfo, Capabilities, Error, Gl};
+            use $crate::{ContextAttributes, ContextID, SurfaceAccess, SurfaceAttributes};
+            use $crate::{SurfaceInfo, SurfaceType};
 
             impl ConnectionInterface for Connection {
                 type Adapter = Adapter;
@@ -61,6 +62,25 @@ macro_rules! implement_interfaces {
                     Connection::new()
                 }
 
+                #[inline]
+                fn new_with_backend(backend: &str) -> Result<Connection, Error> {
+                    if backend == BACKEND_NAME {
+                        Connection::new()
+                    } else {
+                        Err(Error::UnknownBackend)
+                    }
+                }
+
+                #[inline]
+                fn backend_names() -> Vec<&'static str> {
+                    vec![BACKEND_NAME]
+                }
+
+                #[inline]
+                fn backend_name(&self) -> &'static str {
+                    BACKEND_NAME
+                }
+
                 #[inline]
                 fn native_connection(&self) -> Self::NativeConnection {
                     Connection::native_connection(self)
@@ -71,6 +91,11 @@ macro_rules! implement_interfaces {
                     Connection::gl_api(self)
                 }
 
+                #[inline]
+                fn with_gl_api(self, gl_api: GLApi) -> Result<Self, Error> {
+                    Connection::with_gl_api(self, gl_api)
+                }
+
                 #[inline]
                 fn create_adapter(&self) -> Result<Self::Adapter, Error> {
                     Connection::create_adapter(self)
@@ -155,6 +180,7 @@ macro_rules! implement_interfaces {
                 type NativeContext = NativeContext;
                 type Surface = Surface;
                 type SurfaceTexture = SurfaceTexture;
+                type CurrentContextGuard = CurrentContextGuard;
 
                 // device.rs
 
@@ -179,6 +205,16 @@ macro_rules! implement_interfaces {
                     Device::gl_api(self)
                 }
 
+                #[inline]
+                fn capabilities(&mut self) -> Result<Capabilities, Error> {
+                    Device::capabilities(self)
+                }
+
+                #[inline]
+                fn adapter_info(&mut self) -> Result<AdapterInfo, Error> {
+                    Device::adapter_info(self)
+                }
+
                 // context.rs
 
                 #[inline]
@@ -189,6 +225,14 @@ macro_rules! implement_interfaces {
                     Device::create_context_descriptor(self, attributes)
                 }
 
+                #[inline]
+                fn create_context_descriptor_with_fallbacks(
+                    &mut self,
+                    candidates: &[ContextAttributes],
+                ) -> Result<Self::ContextDescriptor, Error> {
+                    Device::create_context_descriptor_with_fallbacks(self, candidates)
+                }
+
                 #[inline]
                 fn create_context(
                     &mut self,
@@ -226,6 +270,26 @@ macro_rules! implement_interfaces {
                     Device::make_no_context_current(self)
                 }
 
+                #[inline]
+                fn with_context_current<F, R>(
+                    &self,
+                    context: &Self::Context,
+                    f: F,
+                ) -> Result<R, Error>
+                where
+                    F: FnOnce(&Gl) -> R,
+                {
+                    Device::with_context_current(self, context, f)
+                }
+
+                #[inline]
+                fn temporarily_make_context_current(
+                    &self,
+                    context: &Self::Context,
+                ) -> Result<CurrentContextGuard, Error> {
+                    Device::temporarily_make_context_current(self, context)
+                }
+
                 #[inline]
                 fn context_descriptor_attributes(
                     &self,
@@ -290,6 +354,23 @@ macro_rules! implement_interfaces {
                     Device::create_surface(self, context, surface_access, surface_type)
                 }
 
+                #[inline]
+                fn create_generic_surface_with_attributes(
+                    &mut self,
+                    context: &Self::Context,
+                    surface_access: SurfaceAccess,
+                    size: &Size2D<i32>,
+                    surface_attributes: &SurfaceAttributes,
+                ) -> Result<Self::Surface, Error> {
+                    Device::create_generic_surface_with_attributes(
+                        self,
+                        context,
+                        surface_access,
+                        size,
+                        surface_attributes,
+                    )
+                }
+
                 #[inline]
                 fn create_surface_texture(
                     &self,
@@ -353,6 +434,11 @@ macro_rules! implement_interfaces {
                 ) -> Option<Texture> {
                     Device::surface_texture_object(self, surface_texture)
                 }
+
+                #[inline]
+                fn surface_texture_target(&self, surface_texture: &Self::SurfaceTexture) -> u32 {
+                    Device::surface_texture_target(self, surface_texture)
+                }
             }
         }
     };
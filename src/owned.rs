@@ -0,0 +1,425 @@
+// surfman/surfman/src/owned.rs
+//
+//! Owning wrappers that destroy contexts, surfaces, and surface textures when dropped.
+//!
+//! The core `surfman` API requires every context, surface, and surface texture to be destroyed
+//! explicitly, and panics otherwise. That is awkward when errors are propagated with `?`, so this
+//! module offers optional RAII handles layered on top of any `Device` implementation.
+//!
+//! Each handle keeps a reference-counted pointer to the device it was created on and to the
+//! context that owns it, so a context is only destroyed once every surface and surface texture
+//! created from it has been dropped. Like the raw types they wrap, these handles are local to a
+//! single thread.
+
+use crate::connection::Connection as ConnectionAPI;
+use crate::device::Device as DeviceAPI;
+use crate::{ContextID, Error, SurfaceAccess, SurfaceAttributes, SurfaceInfo, SurfaceType};
+
+use euclid::default::Size2D;
+use glow::Texture;
+use std::cell::{Ref, RefCell, RefMut};
+use std::mem;
+use std::rc::Rc;
+
+/// A device shared among owned handles.
+pub type SharedDevice<Device> = Rc<RefCell<Device>>;
+
+/// A context that is destroyed when the last handle referring to it is dropped.
+pub struct OwnedContext<Device: DeviceAPI> {
+    handle: Rc<ContextHandle<Device>>,
+}
+
+/// A surface that is destroyed, with its owning context current, when dropped.
+pub struct OwnedSurface<Device: DeviceAPI> {
+    owner: Rc<ContextHandle<Device>>,
+    surface: Option<Device::Surface>,
+}
+
+/// A surface texture that is destroyed when dropped, along with the surface it wraps.
+pub struct OwnedSurfaceTexture<Device: DeviceAPI> {
+    consumer: Rc<ContextHandle<Device>>,
+    owner: Rc<ContextHandle<Device>>,
+    surface_texture: Option<Device::SurfaceTexture>,
+}
+
+struct ContextHandle<Device: DeviceAPI> {
+    device: SharedDevice<Device>,
+    context: RefCell<Option<Device::Context>>,
+}
+
+impl<Device: DeviceAPI> Drop for ContextHandle<Device> {
+    fn drop(&mut self) {
+        let mut context = match self.context.get_mut().take() {
+            None => return,
+            Some(context) => context,
+        };
+        match self.device.try_borrow() {
+            Ok(device) => {
+                if let Err(err) = device.destroy_context(&mut context) {
+                    error!("Failed to destroy owned context: {:?}", err);
+                    mem::forget(context);
+                }
+            }
+            Err(_) => {
+                error!("Device was borrowed while dropping an owned context; leaking it");
+                mem::forget(context);
+            }
+        }
+    }
+}
+
+impl<Device: DeviceAPI> ContextHandle<Device> {
+    fn context(&self) -> Ref<Device::Context> {
+        Ref::map(self.context.borrow(), |context| context.as_ref().unwrap())
+    }
+
+    fn context_mut(&self) -> RefMut<Device::Context> {
+        RefMut::map(self.context.borrow_mut(), |context| {
+            context.as_mut().unwrap()
+        })
+    }
+}
+
+impl<Device: DeviceAPI> OwnedContext<Device> {
+    /// Creates a new context on the given device and takes ownership of it.
+    pub fn new(
+        device: &SharedDevice<Device>,
+        descriptor: &Device::ContextDescriptor,
+        share_with: Option<&OwnedContext<Device>>,
+    ) -> Result<OwnedContext<Device>, Error> {
+        let context = {
+            let share_with = share_with.map(|context| context.handle.context());
+            device
+                .borrow_mut()
+                .create_context(descriptor, share_with.as_deref())?
+        };
+        Ok(OwnedContext::from_raw(device.clone(), context))
+    }
+
+    /// Takes ownership of an existing context created on the given device.
+    pub fn from_raw(
+        device: SharedDevice<Device>,
+        context: Device::Context,
+    ) -> OwnedContext<Device> {
+        OwnedContext {
+            handle: Rc::new(ContextHandle {
+                device,
+                context: RefCell::new(Some(context)),
+            }),
+        }
+    }
+
+    /// Releases ownership of the context, returning the raw handle.
+    ///
+    /// This fails, returning the owned context unchanged, if any surface or surface texture
+    /// created with this context is still alive.
+    pub fn into_raw(self) -> Result<Device::Context, OwnedContext<Device>> {
+        match Rc::try_unwrap(self.handle) {
+            Ok(handle) => Ok(handle.context.borrow_mut().take().unwrap()),
+            Err(handle) => Err(OwnedContext { handle }),
+        }
+    }
+
+    /// Returns the device that this context was created on.
+    #[inline]
+    pub fn device(&self) -> &SharedDevice<Device> {
+        &self.handle.device
+    }
+
+    /// Borrows the underlying context.
+    #[inline]
+    pub fn context(&self) -> Ref<Device::Context> {
+        self.handle.context()
+    }
+
+    /// Mutably borrows the underlying context.
+    #[inline]
+    pub fn context_mut(&self) -> RefMut<Device::Context> {
+        self.handle.context_mut()
+    }
+
+    /// Returns the unique ID of this context.
+    pub fn id(&self) -> ContextID {
+        self.handle.device.borrow().context_id(&self.context())
+    }
+
+    /// Makes this context the current OpenGL context for this thread.
+    pub fn make_current(&self) -> Result<(), Error> {
+        self.handle
+            .device
+            .borrow()
+            .make_context_current(&self.context())
+    }
+
+    /// Creates a surface associated with this context.
+    pub fn create_surface(
+        &self,
+        surface_access: SurfaceAccess,
+        surface_type: SurfaceType<<Device::Connection as ConnectionAPI>::NativeWidget>,
+    ) -> Result<OwnedSurface<Device>, Error> {
+        let surface = self.handle.device.borrow_mut().create_surface(
+            &self.context(),
+            surface_access,
+            surface_type,
+        )?;
+        Ok(OwnedSurface {
+            owner: self.handle.clone(),
+            surface: Some(surface),
+        })
+    }
+
+    /// Creates a generic surface associated with this context, with its own color format and
+    /// depth/stencil buffers.
+    pub fn create_generic_surface_with_attributes(
+        &self,
+        surface_access: SurfaceAccess,
+        size: &Size2D<i32>,
+        surface_attributes: &SurfaceAttributes,
+    ) -> Result<OwnedSurface<Device>, Error> {
+        let surface = self
+            .handle
+            .device
+            .borrow_mut()
+            .create_generic_surface_with_attributes(
+                &self.context(),
+                surface_access,
+                size,
+                surface_attributes,
+            )?;
+        Ok(OwnedSurface {
+            owner: self.handle.clone(),
+            surface: Some(surface),
+        })
+    }
+
+    /// Takes ownership of an existing surface that was created with this context.
+    pub fn adopt_surface(&self, surface: Device::Surface) -> OwnedSurface<Device> {
+        OwnedSurface {
+            owner: self.handle.clone(),
+            surface: Some(surface),
+        }
+    }
+
+    /// Attaches a surface to this context for rendering.
+    ///
+    /// The context takes over responsibility for the surface, which will be destroyed along with
+    /// the context unless it is unbound first. If an error occurs, the surface is returned.
+    pub fn bind_surface(
+        &self,
+        mut surface: OwnedSurface<Device>,
+    ) -> Result<(), (Error, OwnedSurface<Device>)> {
+        if !Rc::ptr_eq(&self.handle, &surface.owner) {
+            return Err((Error::IncompatibleSurface, surface));
+        }
+        let raw_surface = surface.surface.take().unwrap();
+        let result = self
+            .handle
+            .device
+            .borrow()
+            .bind_surface_to_context(&mut self.context_mut(), raw_surface);
+        result.map_err(|(err, raw_surface)| {
+            surface.surface = Some(raw_surface);
+            (err, surface)
+        })
+    }
+
+    /// Removes and returns any surface attached to this context.
+    pub fn unbind_surface(&self) -> Result<Option<OwnedSurface<Device>>, Error> {
+        let surface = self
+            .handle
+            .device
+            .borrow()
+            .unbind_surface_from_context(&mut self.context_mut())?;
+        Ok(surface.map(|surface| self.adopt_surface(surface)))
+    }
+
+    /// Returns information about the surface attached to this context, if any.
+    pub fn surface_info(&self) -> Result<Option<SurfaceInfo>, Error> {
+        self.handle
+            .device
+            .borrow()
+            .context_surface_info(&self.context())
+    }
+
+    /// Wraps a surface in a surface texture local to this context.
+    ///
+    /// The surface may belong to any context on the same device; this context is the one that
+    /// will sample from it. If an error occurs, the surface is returned.
+    pub fn create_surface_texture(
+        &self,
+        mut surface: OwnedSurface<Device>,
+    ) -> Result<OwnedSurfaceTexture<Device>, (Error, OwnedSurface<Device>)> {
+        let raw_surface = surface.surface.take().unwrap();
+        let result = self
+            .handle
+            .device
+            .borrow()
+            .create_surface_texture(&mut self.context_mut(), raw_surface);
+        match result {
+            Ok(surface_texture) => Ok(OwnedSurfaceTexture {
+                consumer: self.handle.clone(),
+                owner: surface.owner.clone(),
+                surface_texture: Some(surface_texture),
+            }),
+            Err((err, raw_surface)) => {
+                surface.surface = Some(raw_surface);
+                Err((err, surface))
+            }
+        }
+    }
+}
+
+impl<Device: DeviceAPI> Clone for OwnedContext<Device> {
+    fn clone(&self) -> Self {
+        OwnedContext {
+            handle: self.handle.clone(),
+        }
+    }
+}
+
+impl<Device: DeviceAPI> OwnedSurface<Device> {
+    /// Returns a reference to the underlying surface.
+    #[inline]
+    pub fn surface(&self) -> &Device::Surface {
+        self.surface.as_ref().unwrap()
+    }
+
+    /// Returns a mutable reference to the underlying surface.
+    #[inline]
+    pub fn surface_mut(&mut self) -> &mut Device::Surface {
+        self.surface.as_mut().unwrap()
+    }
+
+    /// Returns the context that owns this surface.
+    pub fn context(&self) -> OwnedContext<Device> {
+        OwnedContext {
+            handle: self.owner.clone(),
+        }
+    }
+
+    /// Returns various information about the surface.
+    pub fn info(&self) -> SurfaceInfo {
+        self.owner.device.borrow().surface_info(self.surface())
+    }
+
+    /// Releases ownership of the surface, returning the raw handle.
+    ///
+    /// The caller becomes responsible for destroying the surface with `destroy_surface()`.
+    pub fn into_raw(mut self) -> Device::Surface {
+        self.surface.take().unwrap()
+    }
+}
+
+impl<Device: DeviceAPI> Drop for OwnedSurface<Device> {
+    fn drop(&mut self) {
+        if let Some(surface) = self.surface.take() {
+            destroy_surface_or_leak(&self.owner, surface);
+        }
+    }
+}
+
+impl<Device: DeviceAPI> OwnedSurfaceTexture<Device> {
+    /// Returns a reference to the underlying surface texture.
+    #[inline]
+    pub fn surface_texture(&self) -> &Device::SurfaceTexture {
+        self.surface_texture.as_ref().unwrap()
+    }
+
+    /// Returns the OpenGL texture object containing the contents of the surface.
+    pub fn texture_object(&self) -> Option<Texture> {
+        self.consumer
+            .device
+            .borrow()
+            .surface_texture_object(self.surface_texture())
+    }
+
+    /// Returns the OpenGL texture target that `texture_object()` must be bound to.
+    pub fn texture_target(&self) -> u32 {
+        self.consumer
+            .device
+            .borrow()
+            .surface_texture_target(self.surface_texture())
+    }
+
+    /// Destroys the surface texture and returns the surface it wrapped.
+    ///
+    /// If an error occurs, the surface texture is returned.
+    pub fn into_surface(
+        mut self,
+    ) -> Result<OwnedSurface<Device>, (Error, OwnedSurfaceTexture<Device>)> {
+        let surface_texture = self.surface_texture.take().unwrap();
+        match destroy_surface_texture(&self.consumer, surface_texture) {
+            Ok(surface) => Ok(OwnedSurface {
+                owner: self.owner.clone(),
+                surface: Some(surface),
+            }),
+            Err((err, surface_texture)) => {
+                self.surface_texture = Some(surface_texture);
+                Err((err, self))
+            }
+        }
+    }
+
+    /// Releases ownership of the surface texture, returning the raw handle.
+    ///
+    /// The caller becomes responsible for destroying the surface texture with
+    /// `destroy_surface_texture()` and then destroying the underlying surface.
+    pub fn into_raw(mut self) -> Device::SurfaceTexture {
+        self.surface_texture.take().unwrap()
+    }
+}
+
+impl<Device: DeviceAPI> Drop for OwnedSurfaceTexture<Device> {
+    fn drop(&mut self) {
+        if let Some(surface_texture) = self.surface_texture.take() {
+            match destroy_surface_texture(&self.consumer, surface_texture) {
+                Ok(surface) => destroy_surface_or_leak(&self.owner, surface),
+                Err((err, surface_texture)) => {
+                    error!("Failed to destroy owned surface texture: {:?}", err);
+                    mem::forget(surface_texture);
+                }
+            }
+        }
+    }
+}
+
+// Destroys a surface with its owning context current, then restores whatever context was current
+// beforehand. Raw surfaces panic if dropped without being destroyed, so on failure the surface is
+// leaked instead.
+fn destroy_surface_or_leak<Device: DeviceAPI>(
+    owner: &ContextHandle<Device>,
+    mut surface: Device::Surface,
+) {
+    let result = match (owner.device.try_borrow(), owner.context.try_borrow_mut()) {
+        (Ok(device), Ok(mut context)) => {
+            let context = context.as_mut().unwrap();
+            device
+                .temporarily_make_context_current(context)
+                .and_then(|_guard| device.destroy_surface(context, &mut surface))
+        }
+        _ => Err(Error::Failed),
+    };
+    if let Err(err) = result {
+        error!("Failed to destroy owned surface: {:?}", err);
+        mem::forget(surface);
+    }
+}
+
+fn destroy_surface_texture<Device: DeviceAPI>(
+    consumer: &ContextHandle<Device>,
+    surface_texture: Device::SurfaceTexture,
+) -> Result<Device::Surface, (Error, Device::SurfaceTexture)> {
+    let (device, mut context) = match (
+        consumer.device.try_borrow(),
+        consumer.context.try_borrow_mut(),
+    ) {
+        (Ok(device), Ok(context)) => (device, context),
+        _ => return Err((Error::Failed, surface_texture)),
+    };
+    let context = context.as_mut().unwrap();
+    let _guard = match device.temporarily_make_context_current(context) {
+        Ok(guard) => guard,
+        Err(err) => return Err((err, surface_texture)),
+    };
+    device.destroy_surface_texture(context, surface_texture)
+}
@@ -3,6 +3,9 @@
 use std::os::raw::c_int;
 
 pub(crate) const AHARDWAREBUFFER_FORMAT_R8G8B8A8_UNORM: u32 = 1;
+pub(crate) const AHARDWAREBUFFER_FORMAT_R16G16B16A16_FLOAT: u32 = 0x16;
+pub(crate) const AHARDWAREBUFFER_FORMAT_R10G10B10A2_UNORM: u32 = 0x2b;
+pub(crate) const AHARDWAREBUFFER_FORMAT_R8_UNORM: u32 = 0x38;
 
 pub(crate) const AHARDWAREBUFFER_USAGE_CPU_READ_NEVER: u64 = 0;
 pub(crate) const AHARDWAREBUFFER_USAGE_CPU_WRITE_NEVER: u64 = 0 << 4;
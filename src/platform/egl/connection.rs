@@ -18,6 +18,8 @@ use euclid::default::Size2D;
 
 use std::os::raw::c_void;
 
+pub(crate) const BACKEND_NAME: &str = "egl";
+
 /// A connection to the display server.
 #[derive(Clone)]
 pub struct Connection;
@@ -51,6 +53,16 @@ impl Connection {
         GLApi::GLES
     }
 
+    /// Returns this connection if `gl_api` is OpenGL ES, the only flavor this backend supports.
+    /// Otherwise, returns an `UnsupportedGLType` error.
+    #[inline]
+    pub fn with_gl_api(self, gl_api: GLApi) -> Result<Connection, Error> {
+        match gl_api {
+            GLApi::GLES => Ok(self),
+            GLApi::GL => Err(Error::UnsupportedGLType),
+        }
+    }
+
     /// Returns the "best" adapter on this system.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
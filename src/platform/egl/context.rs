@@ -9,7 +9,7 @@ use super::surface::{Surface, SurfaceObjects};
 use crate::context::{ContextID, CREATE_CONTEXT_MUTEX};
 use crate::egl;
 use crate::egl::types::{EGLConfig, EGLContext, EGLSurface, EGLint};
-use crate::platform::generic::egl::context::{self, CurrentContextGuard};
+use crate::platform::generic::egl::context;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::error::ToWindowingApiError;
 use crate::platform::generic::egl::surface::ExternalEGLSurfaces;
@@ -20,7 +20,9 @@ use std::mem;
 use std::os::raw::c_void;
 use std::thread;
 
-pub use crate::platform::generic::egl::context::{ContextDescriptor, NativeContext};
+pub use crate::platform::generic::egl::context::{
+    ContextDescriptor, CurrentContextGuard, NativeContext,
+};
 
 /// Represents an OpenGL rendering context.
 ///
@@ -81,6 +83,19 @@ impl Device {
         }
     }
 
+    /// Creates a context descriptor for the first of the given candidate attributes that the
+    /// driver can create a context with.
+    ///
+    /// Each candidate is checked by creating and destroying a temporary context. Whatever context
+    /// was current on this thread beforehand remains current afterward.
+    pub fn create_context_descriptor_with_fallbacks(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor, Error> {
+        let _guard = CurrentContextGuard::new();
+        crate::context::negotiate_context_descriptor(self, candidates)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -222,7 +237,10 @@ impl Device {
                 }) => (egl_surface, egl_surface),
                 Framebuffer::External(ExternalEGLSurfaces { draw, read }) => (draw, read),
                 Framebuffer::Surface(Surface {
-                    objects: SurfaceObjects::HardwareBuffer { .. },
+                    objects:
+                        SurfaceObjects::HardwareBuffer { .. }
+                        | SurfaceObjects::TextureImage { .. }
+                        | SurfaceObjects::ExternalImage { .. },
                     ..
                 }) => (context.pbuffer, context.pbuffer),
                 Framebuffer::None => (context.pbuffer, context.pbuffer),
@@ -266,6 +284,10 @@ impl Device {
         if context.id != new_surface.context_id {
             return Err((Error::IncompatibleSurface, new_surface));
         }
+        // Imported images can't be rendered to.
+        if let SurfaceObjects::ExternalImage { .. } = new_surface.objects {
+            return Err((Error::IncompatibleSurface, new_surface));
+        }
 
         match context.framebuffer {
             Framebuffer::External { .. } => return Err((Error::ExternalRenderTarget, new_surface)),
@@ -338,7 +360,11 @@ impl Device {
         }
     }
 
-    pub(crate) fn temporarily_make_context_current(
+    /// Makes the context current and returns a guard that, when dropped, restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    ///
+    /// This works even if the previously-current context was not created by `surfman`.
+    pub fn temporarily_make_context_current(
         &self,
         context: &Context,
     ) -> Result<CurrentContextGuard, Error> {
@@ -347,6 +373,17 @@ impl Device {
         Ok(guard)
     }
 
+    /// Makes the context current for the duration of the given closure, then restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    #[inline]
+    pub fn with_context_current<F, R>(&self, context: &Context, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&Gl) -> R,
+    {
+        let _guard = self.temporarily_make_context_current(context)?;
+        Ok(f(&context.gl))
+    }
+
     /// Returns a unique ID representing a context.
     ///
     /// This ID is unique to all currently-allocated contexts. If you destroy a context and create
@@ -376,7 +413,10 @@ impl Device {
             }) => (egl_surface, egl_surface),
             Framebuffer::External(ExternalEGLSurfaces { draw, read }) => (draw, read),
             Framebuffer::Surface(Surface {
-                objects: SurfaceObjects::HardwareBuffer { .. },
+                objects:
+                    SurfaceObjects::HardwareBuffer { .. }
+                    | SurfaceObjects::TextureImage { .. }
+                    | SurfaceObjects::ExternalImage { .. },
                 ..
             }) => (context.pbuffer, context.pbuffer),
             Framebuffer::None => (context.pbuffer, context.pbuffer),
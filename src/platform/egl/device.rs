@@ -3,10 +3,13 @@
 //! A thread-local handle to the device.
 
 use super::connection::Connection;
+use super::surface;
+use crate::capabilities;
 use crate::egl;
 use crate::egl::types::EGLDisplay;
-use crate::platform::generic::egl::device::EGL_FUNCTIONS;
-use crate::{Error, GLApi};
+use crate::platform::generic::egl::context::CurrentContextGuard;
+use crate::platform::generic::egl::device::{self, EGL_FUNCTIONS};
+use crate::{AdapterInfo, Capabilities, Error, GLApi};
 
 /// Represents a hardware display adapter that can be used for rendering (including the CPU).
 ///
@@ -32,6 +35,7 @@ impl Drop for Device {
             if !self.display_is_owned {
                 return;
             }
+            device::forget_extensions(self.egl_display);
             let result = egl.Terminate(self.egl_display);
             assert_ne!(result, egl::FALSE);
             self.egl_display = egl::NO_DISPLAY;
@@ -92,4 +96,25 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GLES
     }
+
+    /// Returns what this device supports. See `Device::capabilities()`.
+    pub fn capabilities(&mut self) -> Result<Capabilities, Error> {
+        let _guard = CurrentContextGuard::new();
+        unsafe {
+            let extensions = device::query_extensions(self.egl_display);
+            let flags = device::capability_flags(&extensions);
+            capabilities::probe(self, extensions, flags, |_, format| {
+                surface::native_buffer_format(format).is_some()
+            })
+        }
+    }
+
+    /// Returns the GPU that this device renders with. See `Device::adapter_info()`.
+    pub fn adapter_info(&mut self) -> Result<AdapterInfo, Error> {
+        let _guard = CurrentContextGuard::new();
+        unsafe {
+            let is_software = device::device_is_software(self.egl_display);
+            capabilities::probe_adapter_info(self, is_software)
+        }
+    }
 }
@@ -94,6 +94,8 @@ pub struct OH_NativeBuffer_Format(core::ffi::c_int);
 impl OH_NativeBuffer_Format {
     /// RGBA8888 format
     pub const RGBA_8888: OH_NativeBuffer_Format = OH_NativeBuffer_Format(12);
+    /// BGRA8888 format
+    pub const BGRA_8888: OH_NativeBuffer_Format = OH_NativeBuffer_Format(20);
 }
 
 bitflags! {
@@ -4,6 +4,7 @@
 
 use crate::context::ContextID;
 use crate::platform::generic::egl::ffi::EGLImageKHR;
+use crate::SurfaceFormat;
 
 use euclid::default::Size2D;
 use glow::Texture;
@@ -44,6 +45,7 @@ pub use ohos_surface::*;
 pub struct Surface {
     pub(crate) context_id: ContextID,
     pub(crate) size: Size2D<i32>,
+    pub(crate) format: SurfaceFormat,
     pub(crate) objects: SurfaceObjects,
     pub(crate) destroyed: bool,
 }
@@ -59,6 +61,7 @@ pub struct Surface {
 /// `destroy_surface_texture()` method, or a panic will occur.
 pub struct SurfaceTexture {
     pub(crate) surface: Surface,
+    // This is `EGL_NO_IMAGE_KHR` if the surface's own image is bound instead.
     pub(crate) local_egl_image: EGLImageKHR,
     pub(crate) texture_object: Option<Texture>,
     pub(crate) phantom: PhantomData<*const ()>,
@@ -17,12 +17,14 @@ use crate::gl_utils;
 use crate::platform::egl::ohos_ffi::{eglGetNativeClientBufferANDROID, EGL_NATIVE_BUFFER_OHOS};
 use crate::platform::generic;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::error::ToWindowingApiError;
 use crate::platform::generic::egl::ffi::EGLImageKHR;
 use crate::platform::generic::egl::ffi::EGL_EXTENSION_FUNCTIONS;
 use crate::platform::generic::egl::ffi::EGL_IMAGE_PRESERVED_KHR;
 use crate::platform::generic::egl::ffi::EGL_NO_IMAGE_KHR;
 use crate::renderbuffers::Renderbuffers;
-use crate::{Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType};
+use crate::{Error, SurfaceAccess, SurfaceAttributes, SurfaceFormat, SurfaceID};
+use crate::{SurfaceInfo, SurfaceType, WindowingApiError};
 
 use super::super::context::Context;
 use super::super::device::Device;
@@ -43,9 +45,19 @@ pub(crate) enum SurfaceObjects {
         texture_object: Option<Texture>,
         renderbuffers: Renderbuffers,
     },
+    // A texture that belongs to the caller, shared with surface textures through an EGL image.
+    TextureImage {
+        egl_image: EGLImageKHR,
+        framebuffer_object: Option<glow::Framebuffer>,
+    },
     Window {
         egl_surface: EGLSurface,
     },
+    // An EGL image that belongs to the caller. These surfaces can only be read from.
+    ExternalImage {
+        egl_image: EGLImageKHR,
+        texture_target: u32,
+    },
 }
 
 /// An OHOS native window.
@@ -53,6 +65,42 @@ pub struct NativeWidget {
     pub(crate) native_window: *mut OHNativeWindow,
 }
 
+/// The native EGL and OpenGL objects behind a surface.
+///
+/// These still belong to the surface and become invalid once it is destroyed.
+#[derive(Clone, Copy, Debug)]
+pub enum NativeSurface {
+    /// A generic surface, which renders to a native buffer through an EGL image.
+    HardwareBuffer {
+        /// The native buffer.
+        hardware_buffer: *mut OH_NativeBuffer,
+        /// The EGL image created from the native buffer.
+        egl_image: EGLImageKHR,
+        /// The OpenGL texture in the surface's context that the image is bound to.
+        texture_object: Option<Texture>,
+        /// The OpenGL framebuffer object in the surface's context that renders to the texture.
+        framebuffer_object: Option<glow::Framebuffer>,
+    },
+    /// A generic surface made by `create_surface_from_texture()`, whose texture belongs to the
+    /// caller.
+    TextureImage {
+        /// The EGL image created from the caller's texture.
+        egl_image: EGLImageKHR,
+        /// The OpenGL framebuffer object in the surface's context that renders to the texture.
+        framebuffer_object: Option<glow::Framebuffer>,
+    },
+    /// A widget surface.
+    Window {
+        /// The EGL window surface.
+        egl_surface: EGLSurface,
+    },
+    /// A surface that wraps an EGL image imported with `create_surface_texture_from_egl_image()`.
+    ExternalImage {
+        /// The imported EGL image, which belongs to the caller.
+        egl_image: EGLImageKHR,
+    },
+}
+
 impl Device {
     /// Creates either a generic or a widget surface, depending on the supplied surface type.
     ///
@@ -61,31 +109,56 @@ impl Device {
     pub fn create_surface(
         &mut self,
         context: &Context,
-        _: SurfaceAccess,
+        access: SurfaceAccess,
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
         info!("Device create_surface with Context");
         match surface_type {
-            SurfaceType::Generic { size } => self.create_generic_surface(context, &size),
+            SurfaceType::Generic { size } => {
+                let context_descriptor = self.context_descriptor(context);
+                let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+                let surface_attributes = SurfaceAttributes::for_context(&context_attributes);
+                self.create_generic_surface_with_attributes(
+                    context,
+                    access,
+                    &size,
+                    &surface_attributes,
+                )
+            }
             SurfaceType::Widget { native_widget } => unsafe {
                 self.create_window_surface(context, native_widget)
             },
         }
     }
 
-    fn create_generic_surface(
+    /// Creates a generic surface whose color format and depth/stencil buffers are given by
+    /// `surface_attributes` rather than by the attributes of the context.
+    pub fn create_generic_surface_with_attributes(
         &mut self,
         context: &Context,
+        _: SurfaceAccess,
         size: &Size2D<i32>,
+        surface_attributes: &SurfaceAttributes,
     ) -> Result<Surface, Error> {
+        if surface_attributes.layers != 1 || surface_attributes.samples != 1 {
+            return Err(Error::UnsupportedOnThisPlatform);
+        }
         let _guard = self.temporarily_make_context_current(context)?;
 
+        let format = match native_buffer_format(surface_attributes.format) {
+            Some(format)
+                if gl_utils::texture_format(&context.gl, surface_attributes.format).is_some() =>
+            {
+                format
+            }
+            _ => return Err(Error::UnsupportedSurfaceFormat),
+        };
         let usage = OH_NativeBuffer_Usage::HW_RENDER | OH_NativeBuffer_Usage::HW_TEXTURE;
 
         let config = OH_NativeBuffer_Config {
             width: size.width,
             height: size.height,
-            format: OH_NativeBuffer_Format::RGBA_8888,
+            format,
             usage: usage,
             stride: 10, // used same magic number as android. I have no idea
         };
@@ -99,7 +172,8 @@ impl Device {
             let egl_image = self.create_egl_image(context, hardware_buffer);
 
             // Initialize and bind the image to the texture.
-            let texture_object = generic::egl::surface::bind_egl_image_to_gl_texture(gl, egl_image);
+            let texture_object =
+                generic::egl::surface::bind_egl_image_to_gl_texture(gl, egl_image, gl::TEXTURE_2D);
 
             // Create the framebuffer, and bind the texture to it.
             let framebuffer_object = gl_utils::create_and_bind_framebuffer(
@@ -109,18 +183,28 @@ impl Device {
             );
 
             // Bind renderbuffers as appropriate.
-            let context_descriptor = self.context_descriptor(context);
-            let context_attributes = self.context_descriptor_attributes(&context_descriptor);
-            let renderbuffers = Renderbuffers::new(gl, size, &context_attributes);
+            let mut renderbuffers = Renderbuffers::new(gl, size, surface_attributes.attachments);
             renderbuffers.bind_to_current_framebuffer(gl);
 
-            debug_assert_eq!(
-                gl.check_framebuffer_status(gl::FRAMEBUFFER),
-                gl::FRAMEBUFFER_COMPLETE
-            );
+            if gl.check_framebuffer_status(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                renderbuffers.destroy(gl);
+                gl.delete_framebuffer(framebuffer_object);
+                gl.delete_texture(texture_object);
+                let result = (EGL_EXTENSION_FUNCTIONS
+                    .DestroyImageKHR
+                    .expect("Where's the `EGL_KHR_image_base` extension?"))(
+                    self.egl_display,
+                    egl_image,
+                );
+                assert_ne!(result, egl::FALSE);
+                let res = OH_NativeBuffer_Unreference(hardware_buffer);
+                assert_eq!(res, 0, "OH_NativeBuffer_Unreference failed");
+                return Err(Error::SurfaceCreationFailed(WindowingApiError::Failed));
+            }
 
             Ok(Surface {
                 size: *size,
+                format: surface_attributes.format,
                 context_id: context.id,
                 objects: SurfaceObjects::HardwareBuffer {
                     hardware_buffer,
@@ -164,12 +248,127 @@ impl Device {
             Ok(Surface {
                 context_id: context.id,
                 size: Size2D::new(width, height),
+                format: SurfaceFormat::RGBA8,
                 objects: SurfaceObjects::Window { egl_surface },
                 destroyed: false,
             })
         })
     }
 
+    /// Wraps an existing OpenGL texture in a generic surface, so that it can be rendered to by the
+    /// context, read from other contexts via `create_surface_texture()`, or handed to a swap
+    /// chain.
+    ///
+    /// The surface has no depth or stencil buffer and reports the given format. Where the context
+    /// can inspect the texture, that is, with OpenGL ES 3.1, a texture whose size or internal
+    /// format doesn't match returns a `SurfaceImportFailed(BadMatch)` error.
+    ///
+    /// # Safety
+    ///
+    /// `texture` must be a complete `GL_TEXTURE_2D` texture of the given size and format,
+    /// belonging to `context` or to a context that shares objects with it.
+    ///
+    /// The texture remains owned by the caller: `destroy_surface()` does not delete it, and the
+    /// caller must not delete it until the surface has been destroyed.
+    pub unsafe fn create_surface_from_texture(
+        &mut self,
+        context: &Context,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture: Texture,
+    ) -> Result<Surface, Error> {
+        if !generic::egl::device::has_extension(self.egl_display, "EGL_KHR_gl_texture_2D_image") {
+            return Err(Error::RequiredExtensionUnavailable);
+        }
+        let _guard = self.temporarily_make_context_current(context)?;
+        let gl = &context.gl;
+        generic::egl::surface::check_texture(gl, texture, size, format)?;
+
+        // Keep whatever was already rendered into the texture.
+        let egl_image = generic::egl::surface::create_egl_image(
+            self.egl_display,
+            context.egl_context,
+            Some(texture),
+            true,
+        );
+        if egl_image == EGL_NO_IMAGE_KHR {
+            let err = EGL_FUNCTIONS.with(|egl| egl.GetError().to_windowing_api_error());
+            return Err(Error::SurfaceImportFailed(err));
+        }
+
+        let framebuffer_object =
+            gl_utils::create_and_bind_framebuffer(gl, SURFACE_GL_TEXTURE_TARGET, Some(texture));
+        if gl.check_framebuffer_status(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+            gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+            gl.delete_framebuffer(framebuffer_object);
+            let result = (EGL_EXTENSION_FUNCTIONS
+                .DestroyImageKHR
+                .expect("Where's the `EGL_KHR_image_base` extension?"))(
+                self.egl_display,
+                egl_image,
+            );
+            assert_ne!(result, egl::FALSE);
+            return Err(Error::SurfaceImportFailed(WindowingApiError::BadMatch));
+        }
+
+        Ok(Surface {
+            size: *size,
+            format,
+            context_id: context.id,
+            objects: SurfaceObjects::TextureImage {
+                egl_image,
+                framebuffer_object: Some(framebuffer_object),
+            },
+            destroyed: false,
+        })
+    }
+
+    /// Creates a surface texture that samples from an EGL image made elsewhere, for example by a
+    /// video decoder.
+    ///
+    /// `texture_target` must be `GL_TEXTURE_2D` or `GL_TEXTURE_EXTERNAL_OES`. The surface texture
+    /// is bound to that target, as reported by `surface_texture_target()`.
+    ///
+    /// Destroying the surface texture returns a surface that wraps the image. That surface can't
+    /// be bound to a context, must be destroyed with `destroy_surface()` as usual, and reports the
+    /// given format, since EGL has no way to query the format of an image.
+    ///
+    /// # Safety
+    ///
+    /// `egl_image` must be a valid image on this device's EGL display, of the given size and
+    /// format. It remains owned by the caller, who must keep it alive until the surface created
+    /// from it has been destroyed.
+    pub unsafe fn create_surface_texture_from_egl_image(
+        &self,
+        context: &mut Context,
+        egl_image: EGLImageKHR,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture_target: u32,
+    ) -> Result<SurfaceTexture, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        let gl = &context.gl;
+        generic::egl::surface::check_egl_image_texture_target(gl, texture_target)?;
+
+        let texture_object =
+            generic::egl::surface::bind_egl_image_to_gl_texture(gl, egl_image, texture_target);
+        Ok(SurfaceTexture {
+            surface: Surface {
+                size: *size,
+                format,
+                context_id: context.id,
+                objects: SurfaceObjects::ExternalImage {
+                    egl_image,
+                    texture_target,
+                },
+                destroyed: false,
+            },
+            local_egl_image: EGL_NO_IMAGE_KHR,
+            texture_object: Some(texture_object),
+            phantom: PhantomData,
+        })
+    }
+
     /// Creates a surface texture from an existing generic surface for use with the given context.
     ///
     /// The surface texture is local to the supplied context and takes ownership of the surface.
@@ -198,8 +397,11 @@ impl Device {
                     let gl = &context.gl;
 
                     let local_egl_image = self.create_egl_image(context, hardware_buffer);
-                    let texture_object =
-                        generic::egl::surface::bind_egl_image_to_gl_texture(gl, local_egl_image);
+                    let texture_object = generic::egl::surface::bind_egl_image_to_gl_texture(
+                        gl,
+                        local_egl_image,
+                        gl::TEXTURE_2D,
+                    );
                     Ok(SurfaceTexture {
                         surface,
                         local_egl_image,
@@ -207,6 +409,26 @@ impl Device {
                         phantom: PhantomData,
                     })
                 }
+                SurfaceObjects::TextureImage { egl_image, .. }
+                | SurfaceObjects::ExternalImage { egl_image, .. } => {
+                    let _guard = match self.temporarily_make_context_current(context) {
+                        Ok(guard) => guard,
+                        Err(err) => return Err((err, surface)),
+                    };
+
+                    // The surface's own image is shared, so there is no local image to destroy.
+                    let texture_object = generic::egl::surface::bind_egl_image_to_gl_texture(
+                        &context.gl,
+                        egl_image,
+                        surface.texture_target(),
+                    );
+                    Ok(SurfaceTexture {
+                        surface,
+                        local_egl_image: EGL_NO_IMAGE_KHR,
+                        texture_object: Some(texture_object),
+                        phantom: PhantomData,
+                    })
+                }
             }
         }
     }
@@ -229,7 +451,9 @@ impl Device {
                     egl.SwapBuffers(self.egl_display, egl_surface);
                     Ok(())
                 }
-                SurfaceObjects::HardwareBuffer { .. } => Err(Error::NoWidgetAttached),
+                SurfaceObjects::HardwareBuffer { .. }
+                | SurfaceObjects::TextureImage { .. }
+                | SurfaceObjects::ExternalImage { .. } => Err(Error::NoWidgetAttached),
             }
         })
     }
@@ -261,7 +485,9 @@ impl Device {
             egl::NONE as EGLint,
             0,
         ];
-        let egl_image = (EGL_EXTENSION_FUNCTIONS.CreateImageKHR)(
+        let egl_image = (EGL_EXTENSION_FUNCTIONS
+            .CreateImageKHR
+            .expect("Where's the `EGL_KHR_image_base` extension?"))(
             self.egl_display,
             egl::NO_CONTEXT,
             EGL_NATIVE_BUFFER_OHOS,
@@ -311,7 +537,12 @@ impl Device {
                     }
 
                     let egl_display = self.egl_display;
-                    let result = (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, *egl_image);
+                    let result = (EGL_EXTENSION_FUNCTIONS
+                        .DestroyImageKHR
+                        .expect("Where's the `EGL_KHR_image_base` extension?"))(
+                        egl_display,
+                        *egl_image,
+                    );
                     assert_ne!(result, egl::FALSE);
                     *egl_image = EGL_NO_IMAGE_KHR;
 
@@ -319,6 +550,28 @@ impl Device {
                     assert_eq!(res, 0, "OH_NativeBuffer_Unreference failed");
                     *hardware_buffer = ptr::null_mut();
                 }
+                SurfaceObjects::TextureImage {
+                    ref mut egl_image,
+                    ref mut framebuffer_object,
+                } => {
+                    // The texture belongs to the caller, so it is left alone.
+                    let gl = &context.gl;
+                    gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+                    if let Some(framebuffer) = framebuffer_object.take() {
+                        gl.delete_framebuffer(framebuffer);
+                    }
+
+                    let result = (EGL_EXTENSION_FUNCTIONS
+                        .DestroyImageKHR
+                        .expect("Where's the `EGL_KHR_image_base` extension?"))(
+                        self.egl_display,
+                        *egl_image,
+                    );
+                    assert_ne!(result, egl::FALSE);
+                    *egl_image = EGL_NO_IMAGE_KHR;
+                }
+                // The image belongs to the caller.
+                SurfaceObjects::ExternalImage { .. } => {}
                 SurfaceObjects::Window {
                     ref mut egl_surface,
                 } => EGL_FUNCTIONS.with(|egl| {
@@ -351,13 +604,17 @@ impl Device {
                 gl.delete_texture(texture);
             }
 
-            let egl_display = self.egl_display;
-            let result = (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(
-                egl_display,
-                surface_texture.local_egl_image,
-            );
-            assert_ne!(result, egl::FALSE);
-            surface_texture.local_egl_image = EGL_NO_IMAGE_KHR;
+            if surface_texture.local_egl_image != EGL_NO_IMAGE_KHR {
+                let egl_display = self.egl_display;
+                let result = (EGL_EXTENSION_FUNCTIONS
+                    .DestroyImageKHR
+                    .expect("Where's the `EGL_KHR_image_base` extension?"))(
+                    egl_display,
+                    surface_texture.local_egl_image,
+                );
+                assert_ne!(result, egl::FALSE);
+                surface_texture.local_egl_image = EGL_NO_IMAGE_KHR;
+            }
         }
 
         Ok(surface_texture.surface)
@@ -385,16 +642,23 @@ impl Device {
     /// on the framebuffer object returned by this function. This framebuffer object may or not be
     /// 0, the default framebuffer, depending on platform.
     pub fn surface_info(&self, surface: &Surface) -> SurfaceInfo {
+        let framebuffer_object = match surface.objects {
+            SurfaceObjects::HardwareBuffer {
+                framebuffer_object, ..
+            }
+            | SurfaceObjects::TextureImage {
+                framebuffer_object, ..
+            } => framebuffer_object,
+            SurfaceObjects::Window { .. } | SurfaceObjects::ExternalImage { .. } => None,
+        };
         SurfaceInfo {
             size: surface.size,
             id: surface.id(),
             context_id: surface.context_id,
-            framebuffer_object: match surface.objects {
-                SurfaceObjects::HardwareBuffer {
-                    framebuffer_object, ..
-                } => framebuffer_object,
-                SurfaceObjects::Window { .. } => None,
-            },
+            framebuffer_object,
+            format: surface.format,
+            layers: 1,
+            layer_framebuffer_objects: framebuffer_object.into_iter().collect(),
         }
     }
 
@@ -405,6 +669,46 @@ impl Device {
     pub fn surface_texture_object(&self, surface_texture: &SurfaceTexture) -> Option<Texture> {
         surface_texture.texture_object
     }
+
+    /// Returns the OpenGL texture target that the texture object of this surface texture must be
+    /// bound to, which is `surface_gl_texture_target()` unless the surface texture was created by
+    /// `create_surface_texture_from_egl_image()`.
+    #[inline]
+    pub fn surface_texture_target(&self, surface_texture: &SurfaceTexture) -> u32 {
+        surface_texture.surface.texture_target()
+    }
+
+    /// Returns the native EGL and OpenGL objects behind this surface.
+    ///
+    /// The objects still belong to the surface, so they must not be destroyed by the caller, and
+    /// they become invalid once the surface is destroyed.
+    pub fn native_surface(&self, surface: &Surface) -> NativeSurface {
+        match surface.objects {
+            SurfaceObjects::HardwareBuffer {
+                hardware_buffer,
+                egl_image,
+                framebuffer_object,
+                texture_object,
+                ..
+            } => NativeSurface::HardwareBuffer {
+                hardware_buffer,
+                egl_image,
+                texture_object,
+                framebuffer_object,
+            },
+            SurfaceObjects::TextureImage {
+                egl_image,
+                framebuffer_object,
+            } => NativeSurface::TextureImage {
+                egl_image,
+                framebuffer_object,
+            },
+            SurfaceObjects::Window { egl_surface } => NativeSurface::Window { egl_surface },
+            SurfaceObjects::ExternalImage { egl_image, .. } => {
+                NativeSurface::ExternalImage { egl_image }
+            }
+        }
+    }
 }
 
 impl NativeWidget {
@@ -415,10 +719,28 @@ impl NativeWidget {
     }
 }
 
+// The native buffer format for surfaces of the given format, if OpenHarmony has one.
+pub(crate) fn native_buffer_format(format: SurfaceFormat) -> Option<OH_NativeBuffer_Format> {
+    match format {
+        SurfaceFormat::RGBA8 => Some(OH_NativeBuffer_Format::RGBA_8888),
+        SurfaceFormat::BGRA8 => Some(OH_NativeBuffer_Format::BGRA_8888),
+        SurfaceFormat::RGBA16F | SurfaceFormat::RGB10A2 | SurfaceFormat::R8 => None,
+    }
+}
+
 impl Surface {
+    fn texture_target(&self) -> u32 {
+        match self.objects {
+            SurfaceObjects::ExternalImage { texture_target, .. } => texture_target,
+            _ => SURFACE_GL_TEXTURE_TARGET,
+        }
+    }
+
     pub(super) fn id(&self) -> SurfaceID {
         match self.objects {
-            SurfaceObjects::HardwareBuffer { egl_image, .. } => SurfaceID(egl_image as usize),
+            SurfaceObjects::HardwareBuffer { egl_image, .. }
+            | SurfaceObjects::TextureImage { egl_image, .. }
+            | SurfaceObjects::ExternalImage { egl_image, .. } => SurfaceID(egl_image as usize),
             SurfaceObjects::Window { egl_surface } => SurfaceID(egl_surface as usize),
         }
     }
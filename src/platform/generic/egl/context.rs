@@ -2,14 +2,17 @@
 //
 //! Functionality common to backends using EGL contexts.
 
-use super::device::EGL_FUNCTIONS;
+use super::device::{self, EGL_FUNCTIONS};
 use super::error::ToWindowingApiError;
-use super::ffi::EGL_CONTEXT_OPENGL_PROFILE_MASK;
 use super::ffi::{EGL_CONTEXT_MINOR_VERSION_KHR, EGL_CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT};
-use super::surface::{EGLBackedSurface, ExternalEGLSurfaces};
+use super::ffi::{EGL_CONTEXT_OPENGL_NO_ERROR_KHR, EGL_CONTEXT_OPENGL_PROFILE_MASK};
+use super::ffi::{EGL_CONTEXT_PRIORITY_HIGH_IMG, EGL_CONTEXT_PRIORITY_LEVEL_IMG};
+use super::ffi::{EGL_CONTEXT_PRIORITY_LOW_IMG, EGL_CONTEXT_PRIORITY_MEDIUM_IMG};
+use super::surface::{EGLBackedSurface, EGLSurfaceObjects, ExternalEGLSurfaces};
 use crate::context::{self, CREATE_CONTEXT_MUTEX};
 use crate::egl;
-use crate::egl::types::{EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLint};
+use crate::egl::types::{EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLenum, EGLint};
+use crate::gl;
 use crate::surface::Framebuffer;
 use crate::{ContextAttributeFlags, ContextAttributes, ContextID, Error, GLApi, GLVersion};
 use crate::{Gl, SurfaceInfo};
@@ -28,6 +31,9 @@ const RGB_CHANNEL_BIT_DEPTH: EGLint = 8;
 pub(crate) struct EGLBackedContext {
     pub(crate) egl_context: EGLContext,
     pub(crate) id: ContextID,
+    // The ID of the first context in this context's share group. Objects such as textures can be
+    // used by every context with the same share group.
+    pub(crate) share_group: ContextID,
     pbuffer: EGLSurface,
     framebuffer: Framebuffer<EGLBackedSurface, ExternalEGLSurfaces>,
     context_is_owned: bool,
@@ -52,14 +58,21 @@ pub struct ContextDescriptor {
     pub(crate) egl_config_id: EGLint,
     pub(crate) gl_version: GLVersion,
     pub(crate) compatibility_profile: bool,
+    pub(crate) hints: ContextAttributeFlags,
 }
 
+/// Restores the previously-current EGL context, draw surface, and read surface, along with the
+/// bound client API, when dropped.
+///
+/// The previous state is queried from EGL itself, so contexts that `surfman` did not create are
+/// restored too. If no context was current, none will be current once the guard is dropped.
 #[must_use]
-pub(crate) struct CurrentContextGuard {
+pub struct CurrentContextGuard {
     egl_display: EGLDisplay,
     old_egl_draw_surface: EGLSurface,
     old_egl_read_surface: EGLSurface,
     old_egl_context: EGLContext,
+    old_egl_api: EGLenum,
 }
 
 impl Drop for EGLBackedContext {
@@ -74,6 +87,20 @@ impl Drop for EGLBackedContext {
 impl Drop for CurrentContextGuard {
     fn drop(&mut self) {
         EGL_FUNCTIONS.with(|egl| unsafe {
+            // The current context is per client API, so switch back to the old API first.
+            if egl.QueryAPI() != self.old_egl_api {
+                let egl_display = egl.GetCurrentDisplay();
+                if egl_display != egl::NO_DISPLAY {
+                    egl.MakeCurrent(
+                        egl_display,
+                        egl::NO_SURFACE,
+                        egl::NO_SURFACE,
+                        egl::NO_CONTEXT,
+                    );
+                }
+                egl.BindAPI(self.old_egl_api);
+            }
+
             if self.egl_display != egl::NO_DISPLAY {
                 egl.MakeCurrent(
                     self.egl_display,
@@ -81,6 +108,18 @@ impl Drop for CurrentContextGuard {
                     self.old_egl_read_surface,
                     self.old_egl_context,
                 );
+                return;
+            }
+
+            // Nothing was current before, so release whatever the guarded scope made current.
+            let egl_display = egl.GetCurrentDisplay();
+            if egl_display != egl::NO_DISPLAY {
+                egl.MakeCurrent(
+                    egl_display,
+                    egl::NO_SURFACE,
+                    egl::NO_SURFACE,
+                    egl::NO_CONTEXT,
+                );
             }
         })
     }
@@ -110,6 +149,7 @@ impl EGLBackedContext {
         let context = EGLBackedContext {
             egl_context,
             id: *next_context_id,
+            share_group: share_with.map_or(*next_context_id, |ctx| ctx.share_group),
             framebuffer: Framebuffer::None,
             context_is_owned: true,
             pbuffer,
@@ -123,6 +163,8 @@ impl EGLBackedContext {
         let context = EGLBackedContext {
             egl_context: native_context.egl_context,
             id: *next_context_id,
+            // Whatever the native context shares with is unknown, so assume nothing.
+            share_group: *next_context_id,
             framebuffer: Framebuffer::External(ExternalEGLSurfaces {
                 draw: native_context.egl_draw_surface,
                 read: native_context.egl_read_surface,
@@ -210,6 +252,10 @@ impl EGLBackedContext {
         if self.id != surface.context_id {
             return Err((Error::IncompatibleSurface, surface));
         }
+        // Imported images can't be rendered to.
+        if let EGLSurfaceObjects::ExternalImage { .. } = surface.objects {
+            return Err((Error::IncompatibleSurface, surface));
+        }
 
         match self.framebuffer {
             Framebuffer::None => self.framebuffer = Framebuffer::Surface(surface),
@@ -397,6 +443,7 @@ impl ContextDescriptor {
                 egl_config_id,
                 gl_version,
                 compatibility_profile,
+                hints: flags.hints(),
             })
         })
     }
@@ -410,10 +457,42 @@ impl ContextDescriptor {
         let gl_version = GLVersion::current(&gl);
         let compatibility_profile = context::current_context_uses_compatibility_profile(&gl);
 
+        // Report the hints that actually took effect, not the ones that were requested.
+        let mut hints = ContextAttributeFlags::empty();
+        let version = gl.version();
+        let has_context_flags = if version.is_embedded {
+            (version.major, version.minor) >= (3, 2)
+        } else {
+            version.major >= 3
+        };
+        if has_context_flags && gl.supported_extensions().contains("GL_KHR_no_error") {
+            let context_flags = gl.get_parameter_i32(gl::CONTEXT_FLAGS) as u32;
+            hints.set(
+                ContextAttributeFlags::NO_ERROR,
+                context_flags & gl::CONTEXT_FLAG_NO_ERROR_BIT != 0,
+            );
+        }
+        if device::has_extension(egl_display, "EGL_IMG_context_priority") {
+            let priority = get_context_attr(
+                egl_display,
+                egl_context,
+                EGL_CONTEXT_PRIORITY_LEVEL_IMG as EGLint,
+            );
+            hints.set(
+                ContextAttributeFlags::HIGH_PRIORITY,
+                priority == EGL_CONTEXT_PRIORITY_HIGH_IMG as EGLint,
+            );
+            hints.set(
+                ContextAttributeFlags::LOW_PRIORITY,
+                priority == EGL_CONTEXT_PRIORITY_LOW_IMG as EGLint,
+            );
+        }
+
         ContextDescriptor {
             egl_config_id,
             gl_version,
             compatibility_profile,
+            hints,
         }
     }
 
@@ -460,6 +539,7 @@ impl ContextDescriptor {
             ContextAttributeFlags::COMPATIBILITY_PROFILE,
             self.compatibility_profile,
         );
+        attribute_flags.insert(self.hints);
 
         // Create appropriate context attributes.
         ContextAttributes {
@@ -477,6 +557,7 @@ impl CurrentContextGuard {
                 old_egl_draw_surface: egl.GetCurrentSurface(egl::DRAW as EGLint),
                 old_egl_read_surface: egl.GetCurrentSurface(egl::READ as EGLint),
                 old_egl_context: egl.GetCurrentContext(),
+                old_egl_api: egl.QueryAPI(),
             }
         })
     }
@@ -514,6 +595,52 @@ pub(crate) unsafe fn create_context(
         ]);
     }
 
+    // Hints are dropped if the implementation lacks the extension or rejects them.
+    let mut hint_attributes: Vec<EGLint> = vec![];
+    let hints = descriptor.hints;
+    if hints.contains(ContextAttributeFlags::NO_ERROR)
+        && device::has_extension(egl_display, "EGL_KHR_create_context_no_error")
+    {
+        hint_attributes.extend(&[
+            EGL_CONTEXT_OPENGL_NO_ERROR_KHR as EGLint,
+            egl::TRUE as EGLint,
+        ]);
+    }
+    let priorities = ContextAttributeFlags::LOW_PRIORITY
+        | ContextAttributeFlags::MEDIUM_PRIORITY
+        | ContextAttributeFlags::HIGH_PRIORITY;
+    if hints.intersects(priorities)
+        && device::has_extension(egl_display, "EGL_IMG_context_priority")
+    {
+        let priority = if hints.contains(ContextAttributeFlags::HIGH_PRIORITY) {
+            EGL_CONTEXT_PRIORITY_HIGH_IMG
+        } else if hints.contains(ContextAttributeFlags::MEDIUM_PRIORITY) {
+            EGL_CONTEXT_PRIORITY_MEDIUM_IMG
+        } else {
+            EGL_CONTEXT_PRIORITY_LOW_IMG
+        };
+        hint_attributes.extend(&[EGL_CONTEXT_PRIORITY_LEVEL_IMG as EGLint, priority as EGLint]);
+    }
+
+    if !hint_attributes.is_empty() {
+        let mut hinted_attributes = egl_context_attributes.clone();
+        hinted_attributes.extend(hint_attributes);
+        if let Ok(egl_context) =
+            create_context_with_attributes(egl_display, egl_config, share_with, hinted_attributes)
+        {
+            return Ok(egl_context);
+        }
+    }
+
+    create_context_with_attributes(egl_display, egl_config, share_with, egl_context_attributes)
+}
+
+unsafe fn create_context_with_attributes(
+    egl_display: EGLDisplay,
+    egl_config: EGLConfig,
+    share_with: EGLContext,
+    mut egl_context_attributes: Vec<EGLint>,
+) -> Result<EGLContext, Error> {
     // Include some extra zeroes to work around broken implementations.
     //
     // FIXME(pcwalton): Which implementations are those? (This is copied from Gecko.)
@@ -536,6 +663,60 @@ pub(crate) unsafe fn create_context(
     })
 }
 
+/// Returns the `EGL_RENDERABLE_TYPE` bit that configs must have in order to back contexts of the
+/// given API with the given attributes.
+///
+/// Fails with `UnsupportedGLProfile` if a compatibility profile is requested of OpenGL ES.
+#[cfg(free_unix)]
+pub(crate) fn renderable_type_bit(
+    gl_api: GLApi,
+    attributes: &ContextAttributes,
+) -> Result<EGLint, Error> {
+    match gl_api {
+        GLApi::GL => Ok(egl::OPENGL_BIT as EGLint),
+        // OpenGL ES has no profiles.
+        GLApi::GLES
+            if attributes
+                .flags
+                .contains(ContextAttributeFlags::COMPATIBILITY_PROFILE) =>
+        {
+            Err(Error::UnsupportedGLProfile)
+        }
+        GLApi::GLES if attributes.version.major >= 3 => Ok(egl::OPENGL_ES3_BIT as EGLint),
+        GLApi::GLES => Ok(egl::OPENGL_ES2_BIT as EGLint),
+    }
+}
+
+// Returns true if the display has any configs that can render with the given OpenGL API flavor.
+pub(crate) unsafe fn display_supports_gl_api(egl_display: EGLDisplay, gl_api: GLApi) -> bool {
+    let renderable_type_bits = match gl_api {
+        GLApi::GL => &[egl::OPENGL_BIT][..],
+        GLApi::GLES => &[egl::OPENGL_ES3_BIT, egl::OPENGL_ES2_BIT][..],
+    };
+    EGL_FUNCTIONS.with(|egl| {
+        renderable_type_bits.iter().any(|&renderable_type_bit| {
+            // Any surface type will do, so that displays without window configs qualify.
+            let config_attributes = [
+                egl::SURFACE_TYPE as EGLint,
+                egl::DONT_CARE,
+                egl::RENDERABLE_TYPE as EGLint,
+                renderable_type_bit as EGLint,
+                egl::NONE as EGLint,
+                0,
+            ];
+            let mut config_count = 0;
+            let result = egl.ChooseConfig(
+                egl_display,
+                config_attributes.as_ptr(),
+                ptr::null_mut(),
+                0,
+                &mut config_count,
+            );
+            result != egl::FALSE && config_count > 0
+        })
+    })
+}
+
 pub(crate) unsafe fn make_no_context_current(egl_display: EGLDisplay) -> Result<(), Error> {
     EGL_FUNCTIONS.with(|egl| {
         let result = egl.MakeCurrent(
@@ -2,14 +2,19 @@
 //
 //! Functionality common to backends using EGL displays.
 
+use crate::egl;
+use crate::egl::types::{EGLAttrib, EGLDisplay, EGLint};
 use crate::egl::Egl;
+use crate::platform::generic::egl::ffi::{EGL_DEVICE_EXT, EGL_EXTENSION_FUNCTIONS};
+use crate::CapabilityFlags;
 
 #[cfg(not(target_os = "windows"))]
 use libc::{dlopen, dlsym, RTLD_LAZY};
+use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::mem;
 use std::os::raw::c_void;
-use std::sync::LazyLock;
+use std::sync::{LazyLock, Mutex};
 #[cfg(target_os = "windows")]
 use winapi::shared::minwindef::HMODULE;
 #[cfg(target_os = "windows")]
@@ -19,6 +24,12 @@ thread_local! {
     pub static EGL_FUNCTIONS: Egl = Egl::load_with(get_proc_address);
 }
 
+// The extensions of each display that `has_extension()` has been asked about, keyed by display.
+// Entries are removed by `forget_extensions()` when the display goes away, since the handle may
+// then be reused for another display.
+static DISPLAY_EXTENSIONS: LazyLock<Mutex<HashMap<usize, Vec<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
 #[cfg(target_os = "windows")]
 static EGL_LIBRARY: LazyLock<EGLLibraryWrapper> = LazyLock::new(|| unsafe {
     let module = libloaderapi::LoadLibraryA(c"libEGL.dll".as_ptr());
@@ -73,3 +84,97 @@ fn get_proc_address(symbol_name: &str) -> *const c_void {
 pub(crate) unsafe fn lookup_egl_extension(name: &CStr) -> *mut c_void {
     EGL_FUNCTIONS.with(|egl| mem::transmute(egl.GetProcAddress(name.as_ptr())))
 }
+
+// Returns the client extensions followed by the extensions of the given display.
+pub(crate) unsafe fn query_extensions(egl_display: EGLDisplay) -> Vec<String> {
+    EGL_FUNCTIONS.with(|egl| {
+        let mut extensions: Vec<String> = vec![];
+        for display in [egl::NO_DISPLAY, egl_display] {
+            let extension_string = egl.QueryString(display, egl::EXTENSIONS as EGLint);
+            if extension_string.is_null() {
+                // Client extensions are unsupported before EGL 1.5 without
+                // `EGL_EXT_client_extensions`.
+                continue;
+            }
+            for extension in CStr::from_ptr(extension_string)
+                .to_string_lossy()
+                .split(' ')
+            {
+                if !extension.is_empty() && !extensions.iter().any(|known| known == extension) {
+                    extensions.push(extension.to_owned());
+                }
+            }
+        }
+        extensions
+    })
+}
+
+// Returns true if the given display, which must be initialized, supports the given extension.
+//
+// The extensions of each display are queried only once, since splitting the extension strings is
+// too slow to repeat every time a context or surface is created.
+pub(crate) unsafe fn has_extension(egl_display: EGLDisplay, name: &str) -> bool {
+    let mut display_extensions = DISPLAY_EXTENSIONS.lock().unwrap();
+    display_extensions
+        .entry(egl_display as usize)
+        .or_insert_with(|| query_extensions(egl_display))
+        .iter()
+        .any(|extension| extension == name)
+}
+
+// Forgets the cached extensions of the given display. Call this when the display is terminated,
+// or when its native display is closed, so that a display that reuses the handle is queried anew.
+pub(crate) fn forget_extensions(egl_display: EGLDisplay) {
+    DISPLAY_EXTENSIONS
+        .lock()
+        .unwrap()
+        .remove(&(egl_display as usize));
+}
+
+pub(crate) fn capability_flags(extensions: &[String]) -> CapabilityFlags {
+    let mut flags = CapabilityFlags::empty();
+    for extension in extensions {
+        match &**extension {
+            "EGL_MESA_image_dma_buf_export" => flags.insert(CapabilityFlags::DMABUF_EXPORT),
+            "EGL_KHR_fence_sync" | "EGL_KHR_reusable_sync" => flags.insert(CapabilityFlags::FENCES),
+            "EGL_EXT_create_context_robustness" => flags.insert(CapabilityFlags::ROBUSTNESS),
+            "EGL_KHR_partial_update"
+            | "EGL_KHR_swap_buffers_with_damage"
+            | "EGL_EXT_swap_buffers_with_damage" => flags.insert(CapabilityFlags::DAMAGE),
+            "EGL_EXT_buffer_age" => flags.insert(CapabilityFlags::BUFFER_AGE),
+            _ => {}
+        }
+    }
+    flags
+}
+
+// Returns true if `EGL_EXT_device_query` reports that the display is backed by a software
+// renderer.
+#[allow(non_snake_case)]
+pub(crate) unsafe fn device_is_software(egl_display: EGLDisplay) -> bool {
+    let (eglQueryDisplayAttribEXT, eglQueryDeviceStringEXT) = match (
+        EGL_EXTENSION_FUNCTIONS.QueryDisplayAttribEXT,
+        EGL_EXTENSION_FUNCTIONS.QueryDeviceStringEXT,
+    ) {
+        (Some(query_display_attrib), Some(query_device_string)) => {
+            (query_display_attrib, query_device_string)
+        }
+        _ => return false,
+    };
+
+    let mut device: EGLAttrib = 0;
+    if eglQueryDisplayAttribEXT(egl_display, EGL_DEVICE_EXT as EGLint, &mut device) == egl::FALSE
+        || device == 0
+    {
+        return false;
+    }
+
+    let extensions = eglQueryDeviceStringEXT(device as _, egl::EXTENSIONS as EGLint);
+    if extensions.is_null() {
+        return false;
+    }
+    CStr::from_ptr(extensions)
+        .to_string_lossy()
+        .split(' ')
+        .any(|extension| extension == "EGL_MESA_device_software")
+}
@@ -7,7 +7,7 @@
 use crate::egl::types::{EGLAttrib, EGLBoolean, EGLContext, EGLDeviceEXT, EGLDisplay, EGLSurface};
 use crate::egl::types::{EGLenum, EGLint};
 
-use std::os::raw::c_void;
+use std::os::raw::{c_char, c_void};
 use std::sync::LazyLock;
 
 pub enum EGLClientBufferOpaque {}
@@ -20,8 +20,13 @@ pub const EGL_GL_TEXTURE_2D_KHR: EGLenum = 0x30b1;
 pub const EGL_IMAGE_PRESERVED_KHR: EGLenum = 0x30d2;
 pub const EGL_CONTEXT_MINOR_VERSION_KHR: EGLenum = 0x30fb;
 pub const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLenum = 0x30fd;
+pub const EGL_CONTEXT_PRIORITY_LEVEL_IMG: EGLenum = 0x3100;
+pub const EGL_CONTEXT_PRIORITY_HIGH_IMG: EGLenum = 0x3101;
+pub const EGL_CONTEXT_PRIORITY_MEDIUM_IMG: EGLenum = 0x3102;
+pub const EGL_CONTEXT_PRIORITY_LOW_IMG: EGLenum = 0x3103;
 pub const EGL_PLATFORM_DEVICE_EXT: EGLenum = 0x313f;
 pub const EGL_NATIVE_BUFFER_ANDROID: EGLenum = 0x3140;
+pub const EGL_CONTEXT_OPENGL_NO_ERROR_KHR: EGLenum = 0x31b3;
 pub const EGL_PLATFORM_X11_KHR: EGLenum = 0x31d5;
 pub const EGL_PLATFORM_WAYLAND_KHR: EGLenum = 0x31d8;
 pub const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31dd;
@@ -40,16 +45,25 @@ pub const EGL_CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT: EGLint = 2;
 
 #[allow(non_snake_case)]
 pub(crate) struct EGLExtensionFunctions {
-    // Ubiquitous extensions assumed to be present
-    pub(crate) CreateImageKHR: extern "C" fn(
-        dpy: EGLDisplay,
-        ctx: EGLContext,
-        target: EGLenum,
-        buffer: EGLClientBuffer,
-        attrib_list: *const EGLint,
-    ) -> EGLImageKHR,
-    pub(crate) DestroyImageKHR: extern "C" fn(dpy: EGLDisplay, image: EGLImageKHR) -> EGLBoolean,
-    pub(crate) ImageTargetTexture2DOES: extern "C" fn(target: EGLenum, image: EGLImageKHR),
+    // Image extensions. Nearly every implementation has these, but some minimal or virtualized
+    // EGL stacks don't.
+    pub(crate) CreateImageKHR: Option<
+        extern "C" fn(
+            dpy: EGLDisplay,
+            ctx: EGLContext,
+            target: EGLenum,
+            buffer: EGLClientBuffer,
+            attrib_list: *const EGLint,
+        ) -> EGLImageKHR,
+    >,
+    pub(crate) DestroyImageKHR:
+        Option<extern "C" fn(dpy: EGLDisplay, image: EGLImageKHR) -> EGLBoolean>,
+    pub(crate) ImageTargetTexture2DOES: Option<extern "C" fn(target: EGLenum, image: EGLImageKHR)>,
+
+    // OpenGL functions that `glow` doesn't wrap. These are core in desktop OpenGL and in OpenGL
+    // ES 3.1, so check the version before calling them.
+    pub(crate) GetTexLevelParameteriv:
+        Option<extern "C" fn(target: EGLenum, level: EGLint, pname: EGLenum, params: *mut EGLint)>,
 
     // Optional extensions
     pub(crate) CreateDeviceANGLE: Option<
@@ -64,6 +78,8 @@ pub(crate) struct EGLExtensionFunctions {
     pub(crate) QueryDeviceAttribEXT: Option<
         extern "C" fn(device: EGLDeviceEXT, attribute: EGLint, value: *mut EGLAttrib) -> EGLBoolean,
     >,
+    pub(crate) QueryDeviceStringEXT:
+        Option<extern "C" fn(device: EGLDeviceEXT, name: EGLint) -> *const c_char>,
     pub(crate) QueryDisplayAttribEXT: Option<
         extern "C" fn(dpy: EGLDisplay, attribute: EGLint, value: *mut EGLAttrib) -> EGLBoolean,
     >,
@@ -86,9 +102,18 @@ pub(crate) static EGL_EXTENSION_FUNCTIONS: LazyLock<EGLExtensionFunctions> = Laz
             DestroyImageKHR: cast(get(c"eglDestroyImageKHR")),
             ImageTargetTexture2DOES: cast(get(c"glEGLImageTargetTexture2DOES")),
 
+            GetTexLevelParameteriv: cast::<
+                *mut c_void,
+                Option<extern "C" fn(EGLenum, EGLint, EGLenum, *mut EGLint)>,
+            >(get(c"glGetTexLevelParameteriv")),
+
             CreateDeviceANGLE: cast(get(c"eglCreateDeviceANGLE")),
             GetNativeClientBufferANDROID: cast(get(c"eglGetNativeClientBufferANDROID")),
             QueryDeviceAttribEXT: cast(get(c"eglQueryDeviceAttribEXT")),
+            QueryDeviceStringEXT: cast::<
+                *mut c_void,
+                Option<extern "C" fn(EGLDeviceEXT, EGLint) -> *const c_char>,
+            >(get(c"eglQueryDeviceStringEXT")),
             QueryDisplayAttribEXT: cast(get(c"eglQueryDisplayAttribEXT")),
             QuerySurfacePointerANGLE: cast(get(c"eglQuerySurfacePointerANGLE")),
         }
@@ -2,8 +2,8 @@
 //
 //! Functionality common to backends using EGL surfaces.
 
-use super::context::CurrentContextGuard;
-use super::device::EGL_FUNCTIONS;
+use super::context::{self, CurrentContextGuard};
+use super::device::{self, EGL_FUNCTIONS};
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLint};
 use crate::gl;
@@ -15,17 +15,33 @@ use crate::platform::generic::egl::ffi::EGL_EXTENSION_FUNCTIONS;
 use crate::platform::generic::egl::ffi::EGL_GL_TEXTURE_2D_KHR;
 use crate::platform::generic::egl::ffi::EGL_IMAGE_PRESERVED_KHR;
 use crate::platform::generic::egl::ffi::EGL_NO_IMAGE_KHR;
-use crate::renderbuffers::Renderbuffers;
+use crate::renderbuffers::{self, Renderbuffers};
 use crate::Gl;
-use crate::{ContextAttributes, ContextID, Error, SurfaceID, SurfaceInfo};
+use crate::WindowingApiError;
+use crate::{ContextID, Error, SurfaceAttachmentFlags, SurfaceAttributes, SurfaceFormat};
+use crate::{SurfaceID, SurfaceInfo};
 
 use euclid::default::Size2D;
-use glow::{Framebuffer, HasContext, PixelUnpackData, Texture};
+use glow::{Framebuffer, HasContext, PixelUnpackData, Renderbuffer, Texture};
+use std::env;
 use std::fmt::{self, Debug, Formatter};
 use std::marker::PhantomData;
 use std::mem;
 use std::os::raw::c_void;
 use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::LazyLock;
+
+static FORCE_PBUFFER_SURFACES_ENV_VAR: &str = "SURFMAN_FORCE_PBUFFER_SURFACES";
+
+// Whether generic surfaces are pbuffers even where EGL images are available. The environment is
+// only read once; tests flip this with `ForcePbufferSurfacesGuard` instead.
+static FORCE_PBUFFER_SURFACES: LazyLock<AtomicBool> =
+    LazyLock::new(|| AtomicBool::new(env::var(FORCE_PBUFFER_SURFACES_ENV_VAR).is_ok()));
+
+const TEXTURE_EXTERNAL_OES: u32 = 0x8d65;
+const TEXTURE_BINDING_EXTERNAL_OES: u32 = 0x8d67;
 
 #[allow(dead_code)]
 #[derive(Clone)]
@@ -34,9 +50,64 @@ pub(crate) struct ExternalEGLSurfaces {
     pub(crate) read: EGLSurface,
 }
 
+/// The native EGL and OpenGL objects behind a surface.
+///
+/// These still belong to the surface and become invalid once it is destroyed.
+#[derive(Clone, Debug)]
+pub enum NativeSurface {
+    /// A generic surface with one layer, which renders to a texture image.
+    TextureImage {
+        /// The texture image.
+        image: NativeTextureImage,
+    },
+    /// A generic surface with more than one layer, which renders to a texture array.
+    TextureArray {
+        /// The OpenGL `GL_TEXTURE_2D_ARRAY` texture in the surface's context.
+        ///
+        /// Contexts that share objects with the surface's context use this texture directly.
+        texture_object: Texture,
+        /// The OpenGL framebuffer objects in the surface's context that render to each layer of
+        /// the texture, in layer order.
+        framebuffer_objects: Vec<Framebuffer>,
+    },
+    /// A generic surface that renders to a pbuffer, because EGL images are unavailable.
+    Pbuffer {
+        /// The EGL pbuffer surface.
+        egl_surface: EGLSurface,
+    },
+    /// A widget surface.
+    Window {
+        /// The EGL window surface.
+        egl_surface: EGLSurface,
+        /// The native window that the EGL surface was created for.
+        native_window: *const c_void,
+    },
+    /// A surface that wraps an EGL image imported with `create_surface_texture_from_egl_image()`.
+    ExternalImage {
+        /// The imported EGL image, which belongs to the caller.
+        egl_image: EGLImageKHR,
+    },
+}
+
+/// A texture that a generic surface renders to, which is shared with other contexts through an
+/// EGL image.
+#[derive(Clone, Copy, Debug)]
+pub struct NativeTextureImage {
+    /// The EGL image.
+    pub egl_image: EGLImageKHR,
+    /// The OpenGL texture in the surface's context that the image was created from.
+    ///
+    /// This is `None` if the texture belongs to the caller, as with surfaces made by
+    /// `create_surface_from_texture()`.
+    pub texture_object: Option<Texture>,
+    /// The OpenGL framebuffer object in the surface's context that renders to the texture.
+    pub framebuffer_object: Option<Framebuffer>,
+}
+
 pub struct EGLBackedSurface {
     pub(crate) context_id: ContextID,
     pub(crate) size: Size2D<i32>,
+    pub(crate) format: SurfaceFormat,
     pub(crate) objects: EGLSurfaceObjects,
     pub(crate) destroyed: bool,
 }
@@ -52,20 +123,57 @@ unsafe impl Send for EGLBackedSurface {}
 #[allow(dead_code)]
 pub(crate) enum EGLSurfaceObjects {
     TextureImage {
-        egl_image: EGLImageKHR,
-        framebuffer_object: Option<Framebuffer>,
-        texture_object: Option<Texture>,
-        renderbuffers: Renderbuffers,
+        image: EGLTextureImage,
+    },
+    // A texture array with a framebuffer object per layer. EGL images can only alias 2D textures,
+    // so the texture is shared with the other contexts in the surface's share group instead.
+    TextureArray {
+        texture_object: Texture,
+        share_group: ContextID,
+        layers: Vec<TextureFramebuffer>,
     },
     Window {
         native_window: *const c_void,
         egl_surface: EGLSurface,
     },
+    Pbuffer {
+        egl_surface: EGLSurface,
+    },
+    // An EGL image that belongs to the caller. These surfaces can only be read from.
+    ExternalImage {
+        egl_image: EGLImageKHR,
+        texture_target: u32,
+    },
+}
+
+// A texture that is rendered to through a framebuffer object and shared with other contexts
+// through an EGL image.
+pub(crate) struct EGLTextureImage {
+    egl_image: EGLImageKHR,
+    // This is `None` if the texture belongs to the caller.
+    texture_object: Option<Texture>,
+    framebuffer: TextureFramebuffer,
+}
+
+// A framebuffer object that renders to a texture, or to one layer of a texture array.
+pub(crate) struct TextureFramebuffer {
+    framebuffer_object: Option<Framebuffer>,
+    renderbuffers: Renderbuffers,
+    // If present, this is rendered to instead and resolved into the texture on unbind.
+    multisample: Option<MultisampleFramebuffer>,
+}
+
+// A multisampled framebuffer that stands in for the texture of a multisampled surface.
+struct MultisampleFramebuffer {
+    framebuffer_object: Framebuffer,
+    color_renderbuffer: Renderbuffer,
+    renderbuffers: Renderbuffers,
 }
 
 pub(crate) struct EGLSurfaceTexture {
     pub(crate) surface: EGLBackedSurface,
     pub(crate) texture_object: Option<Texture>,
+    pub(crate) texture_target: u32,
     pub(crate) phantom: PhantomData<*const ()>,
 }
 
@@ -81,81 +189,219 @@ impl EGLBackedSurface {
         egl_display: EGLDisplay,
         egl_context: EGLContext,
         context_id: ContextID,
-        context_attributes: &ContextAttributes,
+        share_group: ContextID,
+        surface_attributes: &SurfaceAttributes,
         size: &Size2D<i32>,
-    ) -> EGLBackedSurface {
-        let egl_image_attribs = [
-            EGL_IMAGE_PRESERVED_KHR as EGLint,
-            egl::FALSE as EGLint,
-            egl::NONE as EGLint,
-            0,
-        ];
+    ) -> Result<EGLBackedSurface, Error> {
+        if surface_attributes.layers == 0 || surface_attributes.samples == 0 {
+            return Err(Error::SurfaceCreationFailed(WindowingApiError::BadValue));
+        }
 
         unsafe {
-            // Create our texture.
-            let texture_object = gl.create_texture().ok();
-            // Save the current texture binding
-            let old_texture_object = gl.get_parameter_texture(gl::TEXTURE_BINDING_2D);
-            gl.bind_texture(gl::TEXTURE_2D, texture_object);
-            // Unbind PIXEL_UNPACK_BUFFER, because if it is bound,
-            // it can cause errors in glTexImage2D.
-            // TODO: should this be inside a check for GL 2.0?
-            let unpack_buffer = gl.get_parameter_buffer(gl::PIXEL_UNPACK_BUFFER_BINDING);
-            if unpack_buffer.is_some() {
-                gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, None);
-            }
-            gl.tex_image_2d(
-                gl::TEXTURE_2D,
-                0,
-                gl::RGBA as i32,
-                size.width,
-                size.height,
-                0,
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
-                PixelUnpackData::Slice(None),
-            );
-            // Restore the old bindings
-            gl.bind_texture(gl::TEXTURE_2D, old_texture_object);
-            if unpack_buffer.is_some() {
-                gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, unpack_buffer);
+            if !egl_images_supported(gl, egl_display) {
+                // Pbuffers have the format of the context's config, and only ever one layer.
+                if surface_attributes.layers != 1 || surface_attributes.samples != 1 {
+                    return Err(Error::UnsupportedOnThisPlatform);
+                }
+                if surface_attributes.format != SurfaceFormat::RGBA8 {
+                    return Err(Error::UnsupportedSurfaceFormat);
+                }
+                return EGLBackedSurface::new_pbuffer(egl_display, egl_context, context_id, size);
             }
+        }
 
-            // Create our image.
-            let egl_client_buffer =
-                texture_object.map_or(0, |tex| tex.0.get()) as usize as EGLClientBuffer;
-            let egl_image = (EGL_EXTENSION_FUNCTIONS.CreateImageKHR)(
-                egl_display,
-                egl_context,
-                EGL_GL_TEXTURE_2D_KHR,
-                egl_client_buffer,
-                egl_image_attribs.as_ptr(),
-            );
-
-            // Create the framebuffer, and bind the texture to it.
-            let framebuffer_object =
-                gl_utils::create_and_bind_framebuffer(gl, gl::TEXTURE_2D, texture_object);
+        // Layered surfaces are texture arrays, and multisampled surfaces are resolved with
+        // `glBlitFramebuffer()`.
+        if (surface_attributes.layers > 1 || surface_attributes.samples > 1)
+            && gl.version().major < 3
+        {
+            return Err(Error::UnsupportedGLVersion);
+        }
+        if surface_attributes.samples > 1 {
+            let max_samples = unsafe { gl.get_parameter_i32(gl::MAX_SAMPLES) };
+            if surface_attributes.samples > max_samples as u32 {
+                return Err(Error::SurfaceCreationFailed(WindowingApiError::BadValue));
+            }
+        }
 
-            // Bind renderbuffers as appropriate.
-            let renderbuffers = Renderbuffers::new(gl, size, context_attributes);
-            renderbuffers.bind_to_current_framebuffer(gl);
+        let texture_format = gl_utils::texture_format(gl, surface_attributes.format)
+            .ok_or(Error::UnsupportedSurfaceFormat)?;
 
-            debug_assert_eq!(
-                gl.check_framebuffer_status(gl::FRAMEBUFFER),
-                gl::FRAMEBUFFER_COMPLETE
-            );
+        unsafe {
+            let (objects, complete) = if surface_attributes.layers == 1 {
+                let image = EGLTextureImage::new(
+                    gl,
+                    egl_display,
+                    egl_context,
+                    &texture_format,
+                    surface_attributes,
+                    size,
+                );
+                let complete =
+                    gl.check_framebuffer_status(gl::FRAMEBUFFER) == gl::FRAMEBUFFER_COMPLETE;
+                (EGLSurfaceObjects::TextureImage { image }, complete)
+            } else {
+                let texture_object =
+                    create_texture_array(gl, &texture_format, surface_attributes.layers, size);
+                let mut layers = Vec::with_capacity(surface_attributes.layers as usize);
+                let mut complete = true;
+                while complete && layers.len() < surface_attributes.layers as usize {
+                    layers.push(TextureFramebuffer::new_for_layer(
+                        gl,
+                        texture_object,
+                        layers.len() as i32,
+                        &texture_format,
+                        surface_attributes,
+                        size,
+                    ));
+                    complete =
+                        gl.check_framebuffer_status(gl::FRAMEBUFFER) == gl::FRAMEBUFFER_COMPLETE;
+                }
+                let objects = EGLSurfaceObjects::TextureArray {
+                    texture_object,
+                    share_group,
+                    layers,
+                };
+                (objects, complete)
+            };
 
-            EGLBackedSurface {
+            let mut surface = EGLBackedSurface {
                 context_id,
                 size: *size,
-                objects: EGLSurfaceObjects::TextureImage {
-                    egl_image,
-                    framebuffer_object: Some(framebuffer_object),
-                    texture_object,
-                    renderbuffers,
-                },
+                format: surface_attributes.format,
+                objects,
                 destroyed: false,
+            };
+
+            // Don't hand out a surface that can't be rendered to, e.g. because the driver rejected
+            // the depth/stencil formats.
+            if !complete {
+                surface.destroy(gl, egl_display, context_id)?;
+                return Err(Error::SurfaceCreationFailed(WindowingApiError::Failed));
             }
+
+            Ok(surface)
+        }
+    }
+
+    // The texture stays owned by the caller, so it isn't recorded in the surface and destroying
+    // the surface leaves it alone.
+    pub(crate) unsafe fn new_from_texture(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        egl_context: EGLContext,
+        context_id: ContextID,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture_object: Texture,
+    ) -> Result<EGLBackedSurface, Error> {
+        if !egl_images_supported(gl, egl_display) {
+            return Err(Error::RequiredExtensionUnavailable);
+        }
+        check_texture(gl, texture_object, size, format)?;
+
+        // Keep whatever was already rendered into the texture.
+        let egl_image = create_egl_image(egl_display, egl_context, Some(texture_object), true);
+        if egl_image == EGL_NO_IMAGE_KHR {
+            let err = EGL_FUNCTIONS.with(|egl| egl.GetError().to_windowing_api_error());
+            return Err(Error::SurfaceImportFailed(err));
+        }
+
+        let framebuffer_object =
+            gl_utils::create_and_bind_framebuffer(gl, gl::TEXTURE_2D, Some(texture_object));
+        let framebuffer_status = gl.check_framebuffer_status(gl::FRAMEBUFFER);
+
+        let mut surface = EGLBackedSurface {
+            context_id,
+            size: *size,
+            format,
+            objects: EGLSurfaceObjects::TextureImage {
+                image: EGLTextureImage {
+                    egl_image,
+                    texture_object: None,
+                    framebuffer: TextureFramebuffer {
+                        framebuffer_object: Some(framebuffer_object),
+                        renderbuffers: Renderbuffers::new(
+                            gl,
+                            size,
+                            SurfaceAttachmentFlags::empty(),
+                        ),
+                        multisample: None,
+                    },
+                },
+            },
+            destroyed: false,
+        };
+
+        if framebuffer_status != gl::FRAMEBUFFER_COMPLETE {
+            surface.destroy(gl, egl_display, context_id)?;
+            return Err(Error::SurfaceImportFailed(WindowingApiError::BadMatch));
+        }
+
+        Ok(surface)
+    }
+
+    pub(crate) unsafe fn new_from_egl_image(
+        gl: &Gl,
+        context_id: ContextID,
+        egl_image: EGLImageKHR,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture_target: u32,
+    ) -> Result<EGLBackedSurface, Error> {
+        check_egl_image_texture_target(gl, texture_target)?;
+
+        Ok(EGLBackedSurface {
+            context_id,
+            size: *size,
+            format,
+            objects: EGLSurfaceObjects::ExternalImage {
+                egl_image,
+                texture_target,
+            },
+            destroyed: false,
+        })
+    }
+
+    // Rendering goes to the default framebuffer of the pbuffer, and surface textures receive a
+    // copy of its contents.
+    fn new_pbuffer(
+        egl_display: EGLDisplay,
+        egl_context: EGLContext,
+        context_id: ContextID,
+        size: &Size2D<i32>,
+    ) -> Result<EGLBackedSurface, Error> {
+        unsafe {
+            let egl_config_id =
+                context::get_context_attr(egl_display, egl_context, egl::CONFIG_ID as EGLint);
+            let egl_config = context::egl_config_from_id(egl_display, egl_config_id);
+            let pbuffer_attributes = [
+                egl::WIDTH as EGLint,
+                size.width as EGLint,
+                egl::HEIGHT as EGLint,
+                size.height as EGLint,
+                egl::NONE as EGLint,
+                0,
+                0,
+                0,
+            ];
+
+            EGL_FUNCTIONS.with(|egl| {
+                let egl_surface =
+                    egl.CreatePbufferSurface(egl_display, egl_config, pbuffer_attributes.as_ptr());
+                if egl_surface == egl::NO_SURFACE {
+                    let err = egl.GetError().to_windowing_api_error();
+                    return Err(Error::SurfaceCreationFailed(err));
+                }
+
+                Ok(EGLBackedSurface {
+                    context_id,
+                    size: *size,
+                    format: SurfaceFormat::RGBA8,
+                    objects: EGLSurfaceObjects::Pbuffer { egl_surface },
+                    destroyed: false,
+                })
+            })
         }
     }
 
@@ -179,6 +425,7 @@ impl EGLBackedSurface {
             EGLBackedSurface {
                 context_id,
                 size: *size,
+                format: SurfaceFormat::RGBA8,
                 objects: EGLSurfaceObjects::Window {
                     native_window,
                     egl_surface,
@@ -188,19 +435,49 @@ impl EGLBackedSurface {
         })
     }
 
+    // `share_group` is the share group of the context that the surface texture is for.
     pub(crate) fn to_surface_texture(
         self,
         gl: &Gl,
+        share_group: ContextID,
     ) -> Result<EGLSurfaceTexture, (Error, EGLBackedSurface)> {
         unsafe {
-            let egl_image = match self.objects {
-                EGLSurfaceObjects::TextureImage { egl_image, .. } => egl_image,
+            let (texture_object, texture_target) = match self.objects {
+                EGLSurfaceObjects::TextureImage { ref image } => {
+                    let texture_object =
+                        bind_egl_image_to_gl_texture(gl, image.egl_image, gl::TEXTURE_2D);
+                    (texture_object, gl::TEXTURE_2D)
+                }
+                EGLSurfaceObjects::TextureArray {
+                    texture_object,
+                    share_group: surface_share_group,
+                    ..
+                } => {
+                    if share_group != surface_share_group {
+                        return Err((Error::IncompatibleSharedContext, self));
+                    }
+                    (texture_object, gl::TEXTURE_2D_ARRAY)
+                }
+                EGLSurfaceObjects::Pbuffer { egl_surface } => {
+                    match copy_pbuffer_to_gl_texture(gl, egl_surface, &self.size) {
+                        Ok(texture_object) => (texture_object, gl::TEXTURE_2D),
+                        Err(err) => return Err((err, self)),
+                    }
+                }
+                EGLSurfaceObjects::ExternalImage {
+                    egl_image,
+                    texture_target,
+                } => {
+                    let texture_object =
+                        bind_egl_image_to_gl_texture(gl, egl_image, texture_target);
+                    (texture_object, texture_target)
+                }
                 EGLSurfaceObjects::Window { .. } => return Err((Error::WidgetAttached, self)),
             };
-            let texture_object = bind_egl_image_to_gl_texture(gl, egl_image);
             Ok(EGLSurfaceTexture {
                 surface: self,
                 texture_object: Some(texture_object),
+                texture_target,
                 phantom: PhantomData,
             })
         }
@@ -218,25 +495,23 @@ impl EGLBackedSurface {
 
         unsafe {
             match self.objects {
-                EGLSurfaceObjects::TextureImage {
-                    ref mut egl_image,
-                    ref mut framebuffer_object,
-                    ref mut texture_object,
-                    ref mut renderbuffers,
-                } => {
+                EGLSurfaceObjects::TextureImage { ref mut image } => {
                     gl.bind_framebuffer(gl::FRAMEBUFFER, None);
-                    if let Some(framebuffer) = framebuffer_object.take() {
-                        gl.delete_framebuffer(framebuffer);
-                    }
-                    renderbuffers.destroy(gl);
-
-                    let result = (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, *egl_image);
-                    assert_ne!(result, egl::FALSE);
-                    *egl_image = EGL_NO_IMAGE_KHR;
+                    image.destroy(gl, egl_display);
 
-                    if let Some(texture) = texture_object.take() {
-                        gl.delete_texture(texture);
+                    self.destroyed = true;
+                    Ok(None)
+                }
+                EGLSurfaceObjects::TextureArray {
+                    texture_object,
+                    ref mut layers,
+                    ..
+                } => {
+                    gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+                    for layer in layers {
+                        layer.destroy(gl);
                     }
+                    gl.delete_texture(texture_object);
 
                     self.destroyed = true;
                     Ok(None)
@@ -253,6 +528,26 @@ impl EGLBackedSurface {
                     self.destroyed = true;
                     Ok(Some(mem::replace(native_window, ptr::null())))
                 }
+                EGLSurfaceObjects::Pbuffer {
+                    ref mut egl_surface,
+                } => {
+                    EGL_FUNCTIONS.with(|egl| {
+                        egl.DestroySurface(egl_display, *egl_surface);
+                        *egl_surface = egl::NO_SURFACE;
+                    });
+
+                    self.destroyed = true;
+                    Ok(None)
+                }
+                EGLSurfaceObjects::ExternalImage {
+                    ref mut egl_image, ..
+                } => {
+                    // The image belongs to the caller.
+                    *egl_image = EGL_NO_IMAGE_KHR;
+
+                    self.destroyed = true;
+                    Ok(None)
+                }
             }
         }
     }
@@ -288,75 +583,392 @@ impl EGLBackedSurface {
                         }
                     })
                 }
-                EGLSurfaceObjects::TextureImage { .. } => Err(Error::NoWidgetAttached),
+                EGLSurfaceObjects::TextureImage { .. }
+                | EGLSurfaceObjects::TextureArray { .. }
+                | EGLSurfaceObjects::Pbuffer { .. }
+                | EGLSurfaceObjects::ExternalImage { .. } => Err(Error::NoWidgetAttached),
             }
         }
     }
 
     pub(crate) fn info(&self) -> SurfaceInfo {
+        let (layers, layer_framebuffer_objects) = match self.objects {
+            EGLSurfaceObjects::TextureImage { ref image } => (
+                1,
+                image
+                    .framebuffer
+                    .render_framebuffer_object()
+                    .into_iter()
+                    .collect(),
+            ),
+            EGLSurfaceObjects::TextureArray { ref layers, .. } => (
+                layers.len() as u32,
+                layers
+                    .iter()
+                    .filter_map(|layer| layer.render_framebuffer_object())
+                    .collect(),
+            ),
+            EGLSurfaceObjects::Window { .. }
+            | EGLSurfaceObjects::Pbuffer { .. }
+            | EGLSurfaceObjects::ExternalImage { .. } => (1, vec![]),
+        };
         SurfaceInfo {
             size: self.size,
             id: self.id(),
             context_id: self.context_id,
-            framebuffer_object: match self.objects {
-                EGLSurfaceObjects::TextureImage {
-                    framebuffer_object, ..
-                } => framebuffer_object,
-                EGLSurfaceObjects::Window { .. } => None,
-            },
+            framebuffer_object: layer_framebuffer_objects.first().copied(),
+            format: self.format,
+            layers,
+            layer_framebuffer_objects,
         }
     }
 
     pub(crate) fn id(&self) -> SurfaceID {
         match self.objects {
-            EGLSurfaceObjects::TextureImage { egl_image, .. } => SurfaceID(egl_image as usize),
-            EGLSurfaceObjects::Window { egl_surface, .. } => SurfaceID(egl_surface as usize),
+            EGLSurfaceObjects::TextureImage { ref image } => SurfaceID(image.egl_image as usize),
+            EGLSurfaceObjects::TextureArray { texture_object, .. } => {
+                SurfaceID(texture_object.0.get() as usize)
+            }
+            EGLSurfaceObjects::Window { egl_surface, .. }
+            | EGLSurfaceObjects::Pbuffer { egl_surface } => SurfaceID(egl_surface as usize),
+            EGLSurfaceObjects::ExternalImage { egl_image, .. } => SurfaceID(egl_image as usize),
+        }
+    }
+
+    pub(crate) fn native_surface(&self) -> NativeSurface {
+        match self.objects {
+            EGLSurfaceObjects::TextureImage { ref image } => NativeSurface::TextureImage {
+                image: NativeTextureImage {
+                    egl_image: image.egl_image,
+                    texture_object: image.texture_object,
+                    framebuffer_object: image.framebuffer.render_framebuffer_object(),
+                },
+            },
+            EGLSurfaceObjects::TextureArray {
+                texture_object,
+                ref layers,
+                ..
+            } => NativeSurface::TextureArray {
+                texture_object,
+                framebuffer_objects: layers
+                    .iter()
+                    .filter_map(|layer| layer.render_framebuffer_object())
+                    .collect(),
+            },
+            EGLSurfaceObjects::Pbuffer { egl_surface } => NativeSurface::Pbuffer { egl_surface },
+            EGLSurfaceObjects::Window {
+                egl_surface,
+                native_window,
+            } => NativeSurface::Window {
+                egl_surface,
+                native_window,
+            },
+            EGLSurfaceObjects::ExternalImage { egl_image, .. } => {
+                NativeSurface::ExternalImage { egl_image }
+            }
         }
     }
 
     pub(crate) fn native_window(&self) -> Result<*const c_void, Error> {
         match self.objects {
-            EGLSurfaceObjects::TextureImage { .. } => Err(Error::NoWidgetAttached),
+            EGLSurfaceObjects::TextureImage { .. }
+            | EGLSurfaceObjects::TextureArray { .. }
+            | EGLSurfaceObjects::Pbuffer { .. }
+            | EGLSurfaceObjects::ExternalImage { .. } => Err(Error::NoWidgetAttached),
             EGLSurfaceObjects::Window { native_window, .. } => Ok(native_window),
         }
     }
 
     pub(crate) fn unbind(&self, gl: &Gl, egl_display: EGLDisplay, egl_context: EGLContext) {
-        // If we're current, we stay current, but with no surface attached.
+        // If we're current, we stay current, but with no surface attached. Multisampled surfaces
+        // have to be resolved regardless, and texture arrays are read by other contexts directly,
+        // which only see what has been flushed. So the context is made current just long enough
+        // to do that if it isn't already.
+        let multisampled = self
+            .texture_framebuffers()
+            .iter()
+            .any(|framebuffer| framebuffer.multisample.is_some());
+        let needs_flush =
+            multisampled || matches!(self.objects, EGLSurfaceObjects::TextureArray { .. });
         unsafe {
             EGL_FUNCTIONS.with(|egl| {
-                if egl.GetCurrentContext() != egl_context {
+                let _guard = if egl.GetCurrentContext() == egl_context {
+                    None
+                } else if needs_flush {
+                    Some(CurrentContextGuard::new())
+                } else {
                     return;
-                }
+                };
 
                 egl.MakeCurrent(egl_display, egl::NO_SURFACE, egl::NO_SURFACE, egl_context);
 
-                match self.objects {
-                    EGLSurfaceObjects::TextureImage {
-                        framebuffer_object: Some(framebuffer_object),
-                        ..
-                    } => {
+                for framebuffer in self.texture_framebuffers() {
+                    framebuffer.resolve(gl, &self.size);
+                    if let Some(framebuffer_object) = framebuffer.render_framebuffer_object() {
                         gl_utils::unbind_framebuffer_if_necessary(gl, framebuffer_object);
                     }
-                    EGLSurfaceObjects::TextureImage { .. } | EGLSurfaceObjects::Window { .. } => {}
+                }
+                if needs_flush {
+                    gl.flush();
                 }
             })
         }
     }
 
+    // The framebuffer objects that render to the surface's texture or to each of its layers.
+    fn texture_framebuffers(&self) -> &[TextureFramebuffer] {
+        match self.objects {
+            EGLSurfaceObjects::TextureImage { ref image } => slice::from_ref(&image.framebuffer),
+            EGLSurfaceObjects::TextureArray { ref layers, .. } => layers,
+            EGLSurfaceObjects::Window { .. }
+            | EGLSurfaceObjects::Pbuffer { .. }
+            | EGLSurfaceObjects::ExternalImage { .. } => &[],
+        }
+    }
+
     pub(crate) fn egl_surfaces(&self) -> ExternalEGLSurfaces {
         match self.objects {
-            EGLSurfaceObjects::Window { egl_surface, .. } => ExternalEGLSurfaces {
+            EGLSurfaceObjects::Window { egl_surface, .. }
+            | EGLSurfaceObjects::Pbuffer { egl_surface } => ExternalEGLSurfaces {
                 draw: egl_surface,
                 read: egl_surface,
             },
-            EGLSurfaceObjects::TextureImage { .. } => ExternalEGLSurfaces::default(),
+            EGLSurfaceObjects::TextureImage { .. }
+            | EGLSurfaceObjects::TextureArray { .. }
+            | EGLSurfaceObjects::ExternalImage { .. } => ExternalEGLSurfaces::default(),
+        }
+    }
+}
+
+impl EGLTextureImage {
+    // Leaves the new framebuffer object bound.
+    unsafe fn new(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        egl_context: EGLContext,
+        texture_format: &gl_utils::TextureFormat,
+        surface_attributes: &SurfaceAttributes,
+        size: &Size2D<i32>,
+    ) -> EGLTextureImage {
+        // Create our texture.
+        let texture_object = gl.create_texture().ok();
+        // Save the current texture binding
+        let old_texture_object = gl.get_parameter_texture(gl::TEXTURE_BINDING_2D);
+        gl.bind_texture(gl::TEXTURE_2D, texture_object);
+        // Unbind PIXEL_UNPACK_BUFFER, because if it is bound,
+        // it can cause errors in glTexImage2D.
+        // TODO: should this be inside a check for GL 2.0?
+        let unpack_buffer = gl.get_parameter_buffer(gl::PIXEL_UNPACK_BUFFER_BINDING);
+        if unpack_buffer.is_some() {
+            gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, None);
+        }
+        gl.tex_image_2d(
+            gl::TEXTURE_2D,
+            0,
+            texture_format.internal_format as i32,
+            size.width,
+            size.height,
+            0,
+            texture_format.format,
+            texture_format.ty,
+            PixelUnpackData::Slice(None),
+        );
+        // Restore the old bindings
+        gl.bind_texture(gl::TEXTURE_2D, old_texture_object);
+        if unpack_buffer.is_some() {
+            gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, unpack_buffer);
+        }
+
+        // Create our image.
+        let egl_image = create_egl_image(egl_display, egl_context, texture_object, false);
+
+        // Create the framebuffer, and bind the texture to it.
+        let framebuffer_object =
+            gl_utils::create_and_bind_framebuffer(gl, gl::TEXTURE_2D, texture_object);
+        let framebuffer = TextureFramebuffer::new(
+            gl,
+            framebuffer_object,
+            texture_format,
+            surface_attributes,
+            size,
+        );
+
+        EGLTextureImage {
+            egl_image,
+            texture_object,
+            framebuffer,
+        }
+    }
+
+    unsafe fn destroy(&mut self, gl: &Gl, egl_display: EGLDisplay) {
+        self.framebuffer.destroy(gl);
+
+        let result = (EGL_EXTENSION_FUNCTIONS
+            .DestroyImageKHR
+            .expect("Where's the `EGL_KHR_image_base` extension?"))(
+            egl_display, self.egl_image
+        );
+        assert_ne!(result, egl::FALSE);
+        self.egl_image = EGL_NO_IMAGE_KHR;
+
+        if let Some(texture) = self.texture_object.take() {
+            gl.delete_texture(texture);
         }
     }
 }
 
+impl TextureFramebuffer {
+    // Takes a framebuffer object that is bound with the texture attached, and adds the buffers
+    // that the surface attributes ask for. Leaves the framebuffer object that is rendered to bound.
+    unsafe fn new(
+        gl: &Gl,
+        framebuffer_object: Framebuffer,
+        texture_format: &gl_utils::TextureFormat,
+        surface_attributes: &SurfaceAttributes,
+        size: &Size2D<i32>,
+    ) -> TextureFramebuffer {
+        // Bind renderbuffers as appropriate. Multisampled surfaces render to a framebuffer of
+        // their own, so the texture's framebuffer is only a resolve target and needs none.
+        if surface_attributes.samples > 1 {
+            let renderbuffers = Renderbuffers::new(gl, size, SurfaceAttachmentFlags::empty());
+            let multisample =
+                MultisampleFramebuffer::new(gl, texture_format, surface_attributes, size);
+            return TextureFramebuffer {
+                framebuffer_object: Some(framebuffer_object),
+                renderbuffers,
+                multisample: Some(multisample),
+            };
+        }
+        let renderbuffers = Renderbuffers::new(gl, size, surface_attributes.attachments);
+        renderbuffers.bind_to_current_framebuffer(gl);
+
+        TextureFramebuffer {
+            framebuffer_object: Some(framebuffer_object),
+            renderbuffers,
+            multisample: None,
+        }
+    }
+
+    // Renders to one layer of a texture array. Leaves the framebuffer object that is rendered to
+    // bound.
+    unsafe fn new_for_layer(
+        gl: &Gl,
+        texture_object: Texture,
+        layer: i32,
+        texture_format: &gl_utils::TextureFormat,
+        surface_attributes: &SurfaceAttributes,
+        size: &Size2D<i32>,
+    ) -> TextureFramebuffer {
+        let framebuffer_object = gl.create_framebuffer().unwrap();
+        gl.bind_framebuffer(gl::FRAMEBUFFER, Some(framebuffer_object));
+        gl.framebuffer_texture_layer(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            Some(texture_object),
+            0,
+            layer,
+        );
+        TextureFramebuffer::new(
+            gl,
+            framebuffer_object,
+            texture_format,
+            surface_attributes,
+            size,
+        )
+    }
+
+    // The framebuffer object that the surface is rendered to through.
+    fn render_framebuffer_object(&self) -> Option<Framebuffer> {
+        match self.multisample {
+            Some(ref multisample) => Some(multisample.framebuffer_object),
+            None => self.framebuffer_object,
+        }
+    }
+
+    // Copies the multisampled framebuffer, if any, into the texture. The context must be current.
+    unsafe fn resolve(&self, gl: &Gl, size: &Size2D<i32>) {
+        let multisample = match self.multisample {
+            None => return,
+            Some(ref multisample) => multisample,
+        };
+        let old_read_framebuffer = gl.get_parameter_framebuffer(gl::READ_FRAMEBUFFER_BINDING);
+        let old_draw_framebuffer = gl.get_parameter_framebuffer(gl::DRAW_FRAMEBUFFER_BINDING);
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, Some(multisample.framebuffer_object));
+        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, self.framebuffer_object);
+        gl.blit_framebuffer(
+            0,
+            0,
+            size.width,
+            size.height,
+            0,
+            0,
+            size.width,
+            size.height,
+            gl::COLOR_BUFFER_BIT,
+            gl::NEAREST,
+        );
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, old_read_framebuffer);
+        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, old_draw_framebuffer);
+    }
+
+    unsafe fn destroy(&mut self, gl: &Gl) {
+        if let Some(mut multisample) = self.multisample.take() {
+            multisample.destroy(gl);
+        }
+        if let Some(framebuffer) = self.framebuffer_object.take() {
+            gl.delete_framebuffer(framebuffer);
+        }
+        self.renderbuffers.destroy(gl);
+    }
+}
+
+impl MultisampleFramebuffer {
+    // Leaves the new framebuffer object bound.
+    unsafe fn new(
+        gl: &Gl,
+        texture_format: &gl_utils::TextureFormat,
+        surface_attributes: &SurfaceAttributes,
+        size: &Size2D<i32>,
+    ) -> MultisampleFramebuffer {
+        let samples = surface_attributes.samples;
+        let color_renderbuffer = gl.create_renderbuffer().unwrap();
+        gl.bind_renderbuffer(gl::RENDERBUFFER, Some(color_renderbuffer));
+        renderbuffers::allocate_storage(gl, texture_format.sized_internal_format(), size, samples);
+        gl.bind_renderbuffer(gl::RENDERBUFFER, None);
+
+        let framebuffer_object = gl.create_framebuffer().unwrap();
+        gl.bind_framebuffer(gl::FRAMEBUFFER, Some(framebuffer_object));
+        gl.framebuffer_renderbuffer(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            gl::RENDERBUFFER,
+            Some(color_renderbuffer),
+        );
+        let renderbuffers =
+            Renderbuffers::new_multisample(gl, size, surface_attributes.attachments, samples);
+        renderbuffers.bind_to_current_framebuffer(gl);
+
+        MultisampleFramebuffer {
+            framebuffer_object,
+            color_renderbuffer,
+            renderbuffers,
+        }
+    }
+
+    unsafe fn destroy(&mut self, gl: &Gl) {
+        gl_utils::unbind_framebuffer_if_necessary(gl, self.framebuffer_object);
+        gl.delete_framebuffer(self.framebuffer_object);
+        self.renderbuffers.destroy(gl);
+        gl.delete_renderbuffer(self.color_renderbuffer);
+    }
+}
+
 impl EGLSurfaceTexture {
     pub(crate) fn destroy(mut self, gl: &Gl) -> EGLBackedSurface {
+        // Texture arrays are the surface's own texture, which the surface still needs.
+        if let EGLSurfaceObjects::TextureArray { .. } = self.surface.objects {
+            self.texture_object = None;
+        }
         if let Some(texture) = self.texture_object.take() {
             unsafe {
                 gl.delete_texture(texture);
@@ -404,21 +1016,259 @@ pub(crate) unsafe fn create_pbuffer_surface(
     })
 }
 
+pub(crate) unsafe fn create_egl_image(
+    egl_display: EGLDisplay,
+    egl_context: EGLContext,
+    texture_object: Option<Texture>,
+    preserved: bool,
+) -> EGLImageKHR {
+    let egl_image_attribs = [
+        EGL_IMAGE_PRESERVED_KHR as EGLint,
+        if preserved { egl::TRUE } else { egl::FALSE } as EGLint,
+        egl::NONE as EGLint,
+        0,
+    ];
+
+    let egl_client_buffer = texture_object.map_or(0, |tex| tex.0.get()) as usize as EGLClientBuffer;
+    (EGL_EXTENSION_FUNCTIONS
+        .CreateImageKHR
+        .expect("Where's the `EGL_KHR_image_base` extension?"))(
+        egl_display,
+        egl_context,
+        EGL_GL_TEXTURE_2D_KHR,
+        egl_client_buffer,
+        egl_image_attribs.as_ptr(),
+    )
+}
+
 #[allow(dead_code)]
-pub(crate) unsafe fn bind_egl_image_to_gl_texture(gl: &Gl, egl_image: EGLImageKHR) -> Texture {
+pub(crate) unsafe fn bind_egl_image_to_gl_texture(
+    gl: &Gl,
+    egl_image: EGLImageKHR,
+    texture_target: u32,
+) -> Texture {
     let texture = gl.create_texture().unwrap();
 
+    let texture_binding = gl.get_parameter_texture(match texture_target {
+        TEXTURE_EXTERNAL_OES => TEXTURE_BINDING_EXTERNAL_OES,
+        _ => gl::TEXTURE_BINDING_2D,
+    });
+
+    gl.bind_texture(texture_target, Some(texture));
+    (EGL_EXTENSION_FUNCTIONS
+        .ImageTargetTexture2DOES
+        .expect("Where's the `GL_OES_EGL_image` extension?"))(texture_target, egl_image);
+    gl.tex_parameter_i32(texture_target, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
+    gl.tex_parameter_i32(texture_target, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
+    gl.tex_parameter_i32(texture_target, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as _);
+    gl.tex_parameter_i32(texture_target, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as _);
+    gl.bind_texture(texture_target, texture_binding);
+
+    debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
+    texture
+}
+
+// Imported EGL images can be bound to `GL_TEXTURE_2D` or, with the external image extension, to
+// `GL_TEXTURE_EXTERNAL_OES`.
+pub(crate) fn check_egl_image_texture_target(gl: &Gl, texture_target: u32) -> Result<(), Error> {
+    let extension = match texture_target {
+        gl::TEXTURE_2D => "GL_OES_EGL_image",
+        TEXTURE_EXTERNAL_OES => "GL_OES_EGL_image_external",
+        _ => return Err(Error::UnsupportedOnThisPlatform),
+    };
+    if EGL_EXTENSION_FUNCTIONS.ImageTargetTexture2DOES.is_none()
+        || !gl.supported_extensions().contains(extension)
+    {
+        return Err(Error::RequiredExtensionUnavailable);
+    }
+    Ok(())
+}
+
+// Checks that a caller's `GL_TEXTURE_2D` texture has the size and format that it's said to have.
+// The texture can only be inspected with desktop OpenGL or OpenGL ES 3.1, so elsewhere only the
+// format is checked for being one the context can render to.
+pub(crate) unsafe fn check_texture(
+    gl: &Gl,
+    texture_object: Texture,
+    size: &Size2D<i32>,
+    format: SurfaceFormat,
+) -> Result<(), Error> {
+    let texture_format =
+        gl_utils::texture_format(gl, format).ok_or(Error::UnsupportedSurfaceFormat)?;
+
+    let version = gl.version();
+    let get_tex_level_parameter = match EGL_EXTENSION_FUNCTIONS.GetTexLevelParameteriv {
+        Some(get_tex_level_parameter)
+            if !version.is_embedded || (version.major, version.minor) >= (3, 1) =>
+        {
+            get_tex_level_parameter
+        }
+        _ => return Ok(()),
+    };
+
     let texture_binding = gl.get_parameter_texture(gl::TEXTURE_BINDING_2D);
+    gl.bind_texture(gl::TEXTURE_2D, Some(texture_object));
+    // This fails if the texture was already bound to another target.
+    let bound = gl.get_error() == gl::NO_ERROR;
+    let get = |pname| {
+        let mut value = 0;
+        get_tex_level_parameter(gl::TEXTURE_2D, 0, pname, &mut value);
+        value
+    };
+    let (width, height, internal_format) = (
+        get(gl::TEXTURE_WIDTH),
+        get(gl::TEXTURE_HEIGHT),
+        get(gl::TEXTURE_INTERNAL_FORMAT) as u32,
+    );
+    gl.bind_texture(gl::TEXTURE_2D, texture_binding);
 
-    // FIXME(pcwalton): Should this be `GL_TEXTURE_EXTERNAL_OES`?
+    // Drivers may report unsized internal formats either as given or as their sized equivalent.
+    if !bound
+        || width != size.width
+        || height != size.height
+        || (internal_format != texture_format.internal_format
+            && internal_format != texture_format.sized_internal_format())
+    {
+        return Err(Error::SurfaceImportFailed(WindowingApiError::BadMatch));
+    }
+    Ok(())
+}
+
+// Generic surfaces are backed by EGL images when possible, so that surface textures can share
+// them without copying. Minimal EGL stacks that lack the image extensions get pbuffers instead.
+pub(crate) unsafe fn egl_images_supported(gl: &Gl, egl_display: EGLDisplay) -> bool {
+    !FORCE_PBUFFER_SURFACES.load(Ordering::Relaxed)
+        && EGL_EXTENSION_FUNCTIONS.CreateImageKHR.is_some()
+        && EGL_EXTENSION_FUNCTIONS.DestroyImageKHR.is_some()
+        && EGL_EXTENSION_FUNCTIONS.ImageTargetTexture2DOES.is_some()
+        && gl.supported_extensions().contains("GL_OES_EGL_image")
+        && device::has_extension(egl_display, "EGL_KHR_gl_texture_2D_image")
+}
+
+/// Makes generic surfaces use the pbuffer fallback until dropped, as though
+/// `SURFMAN_FORCE_PBUFFER_SURFACES` were set. Dropping it restores the previous setting.
+#[cfg(any(test, feature = "sm-test"))]
+pub(crate) struct ForcePbufferSurfacesGuard {
+    was_forced: bool,
+}
+
+#[cfg(any(test, feature = "sm-test"))]
+impl ForcePbufferSurfacesGuard {
+    pub(crate) fn new() -> ForcePbufferSurfacesGuard {
+        ForcePbufferSurfacesGuard {
+            was_forced: FORCE_PBUFFER_SURFACES.swap(true, Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "sm-test"))]
+impl Drop for ForcePbufferSurfacesGuard {
+    fn drop(&mut self) {
+        FORCE_PBUFFER_SURFACES.store(self.was_forced, Ordering::Relaxed);
+    }
+}
+
+// Copies the contents of a pbuffer into a new texture in the current context. The pbuffer is made
+// current with that context just long enough to read from it.
+unsafe fn copy_pbuffer_to_gl_texture(
+    gl: &Gl,
+    egl_surface: EGLSurface,
+    size: &Size2D<i32>,
+) -> Result<Texture, Error> {
+    let _guard = CurrentContextGuard::new();
+    let (egl_display, egl_context) = EGL_FUNCTIONS.with(|egl| {
+        let egl_display = egl.GetCurrentDisplay();
+        let egl_context = egl.GetCurrentContext();
+        let result = egl.MakeCurrent(egl_display, egl_surface, egl_surface, egl_context);
+        if result == egl::FALSE {
+            let err = egl.GetError().to_windowing_api_error();
+            return Err(Error::MakeCurrentFailed(err));
+        }
+        Ok((egl_display, egl_context))
+    })?;
+
+    // OpenGL ES refuses to copy into a format with channels that the framebuffer lacks.
+    let egl_config_id =
+        context::get_context_attr(egl_display, egl_context, egl::CONFIG_ID as EGLint);
+    let egl_config = context::egl_config_from_id(egl_display, egl_config_id);
+    let format = if context::get_config_attr(egl_display, egl_config, egl::ALPHA_SIZE as EGLint) > 0
+    {
+        gl::RGBA
+    } else {
+        gl::RGB
+    };
+
+    let framebuffer_binding = gl.get_parameter_framebuffer(gl::FRAMEBUFFER_BINDING);
+    let texture_binding = gl.get_parameter_texture(gl::TEXTURE_BINDING_2D);
+
+    let texture = gl.create_texture().unwrap();
+    gl.bind_framebuffer(gl::FRAMEBUFFER, None);
     gl.bind_texture(gl::TEXTURE_2D, Some(texture));
-    (EGL_EXTENSION_FUNCTIONS.ImageTargetTexture2DOES)(gl::TEXTURE_2D, egl_image);
+    gl.copy_tex_image_2d(gl::TEXTURE_2D, 0, format, 0, 0, size.width, size.height, 0);
     gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as _);
     gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
     gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as _);
     gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as _);
     gl.bind_texture(gl::TEXTURE_2D, texture_binding);
+    gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer_binding);
 
     debug_assert_eq!(gl.get_error(), gl::NO_ERROR);
+    Ok(texture)
+}
+
+// Allocates a texture array with a layer per surface layer in the current context.
+unsafe fn create_texture_array(
+    gl: &Gl,
+    texture_format: &gl_utils::TextureFormat,
+    layers: u32,
+    size: &Size2D<i32>,
+) -> Texture {
+    let texture = gl.create_texture().unwrap();
+
+    let texture_binding = gl.get_parameter_texture(gl::TEXTURE_BINDING_2D_ARRAY);
+    let unpack_buffer = gl.get_parameter_buffer(gl::PIXEL_UNPACK_BUFFER_BINDING);
+    if unpack_buffer.is_some() {
+        gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, None);
+    }
+
+    gl.bind_texture(gl::TEXTURE_2D_ARRAY, Some(texture));
+    gl.tex_image_3d(
+        gl::TEXTURE_2D_ARRAY,
+        0,
+        texture_format.internal_format as i32,
+        size.width,
+        size.height,
+        layers as i32,
+        0,
+        texture_format.format,
+        texture_format.ty,
+        PixelUnpackData::Slice(None),
+    );
+    gl.tex_parameter_i32(
+        gl::TEXTURE_2D_ARRAY,
+        gl::TEXTURE_MAG_FILTER,
+        gl::LINEAR as _,
+    );
+    gl.tex_parameter_i32(
+        gl::TEXTURE_2D_ARRAY,
+        gl::TEXTURE_MIN_FILTER,
+        gl::LINEAR as _,
+    );
+    gl.tex_parameter_i32(
+        gl::TEXTURE_2D_ARRAY,
+        gl::TEXTURE_WRAP_S,
+        gl::CLAMP_TO_EDGE as _,
+    );
+    gl.tex_parameter_i32(
+        gl::TEXTURE_2D_ARRAY,
+        gl::TEXTURE_WRAP_T,
+        gl::CLAMP_TO_EDGE as _,
+    );
+
+    gl.bind_texture(gl::TEXTURE_2D_ARRAY, texture_binding);
+    if unpack_buffer.is_some() {
+        gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, unpack_buffer);
+    }
+
     texture
 }
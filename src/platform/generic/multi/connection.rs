@@ -6,11 +6,12 @@ use super::device::{Adapter, Device, NativeDevice};
 use super::surface::NativeWidget;
 use crate::connection::Connection as ConnectionInterface;
 use crate::device::Device as DeviceInterface;
-use crate::Error;
 use crate::GLApi;
+use crate::{BackendFailure, Error};
 
 use euclid::default::Size2D;
 
+use std::env;
 use std::os::raw::c_void;
 
 /// A connection to the display server.
@@ -64,11 +65,74 @@ where
     Alt::Connection: ConnectionInterface<Device = Alt>,
 {
     /// Connects to the default display.
-    #[inline]
+    ///
+    /// If the `SURFMAN_BACKEND` environment variable is set, only the backend it names is tried.
+    /// Otherwise, each backend is tried in turn, and if they all fail, a `NoBackendAvailable`
+    /// error listing every failure is returned.
     pub fn new() -> Result<Connection<Def, Alt>, Error> {
-        match <Def::Connection>::new() {
-            Ok(connection) => Ok(Connection::Default(connection)),
-            Err(_) => <Alt::Connection>::new().map(Connection::Alternate),
+        Connection::connect(<Def::Connection>::new, <Alt::Connection>::new)
+    }
+
+    // Connects with `connect_default`, falling back to `connect_alternate`. If the
+    // `SURFMAN_BACKEND` environment variable is set, only the side that has the backend it names
+    // is tried, and an `UnknownBackend` error is returned if neither does.
+    fn connect<D, A>(
+        connect_default: D,
+        connect_alternate: A,
+    ) -> Result<Connection<Def, Alt>, Error>
+    where
+        D: FnOnce() -> Result<Def::Connection, Error>,
+        A: FnOnce() -> Result<Alt::Connection, Error>,
+    {
+        if let Ok(backend) = env::var("SURFMAN_BACKEND") {
+            return if <Def::Connection>::backend_names().contains(&&*backend) {
+                connect_default().map(Connection::Default)
+            } else if <Alt::Connection>::backend_names().contains(&&*backend) {
+                connect_alternate().map(Connection::Alternate)
+            } else {
+                Err(Error::UnknownBackend)
+            };
+        }
+
+        let mut failures = vec![];
+        match connect_default() {
+            Ok(connection) => return Ok(Connection::Default(connection)),
+            Err(err) => push_failure::<Def::Connection>(&mut failures, err),
+        }
+        match connect_alternate() {
+            Ok(connection) => return Ok(Connection::Alternate(connection)),
+            Err(err) => push_failure::<Alt::Connection>(&mut failures, err),
+        }
+        Err(Error::NoBackendAvailable(failures))
+    }
+
+    /// Connects to the default display using the backend with the given name.
+    ///
+    /// The name must be one of those returned by `backend_names()`, such as `"wayland"`, `"x11"`,
+    /// or `"surfaceless"`; otherwise, an `UnknownBackend` error is returned.
+    pub fn new_with_backend(backend: &str) -> Result<Connection<Def, Alt>, Error> {
+        if <Def::Connection>::backend_names().contains(&backend) {
+            <Def::Connection>::new_with_backend(backend).map(Connection::Default)
+        } else if <Alt::Connection>::backend_names().contains(&backend) {
+            <Alt::Connection>::new_with_backend(backend).map(Connection::Alternate)
+        } else {
+            Err(Error::UnknownBackend)
+        }
+    }
+
+    /// Returns the names of the backends that this connection type can use, in the order that
+    /// `new()` tries them.
+    pub fn backend_names() -> Vec<&'static str> {
+        let mut backend_names = <Def::Connection>::backend_names();
+        backend_names.extend(<Alt::Connection>::backend_names());
+        backend_names
+    }
+
+    /// Returns the name of the backend that this connection is using.
+    pub fn backend_name(&self) -> &'static str {
+        match *self {
+            Connection::Default(ref connection) => connection.backend_name(),
+            Connection::Alternate(ref connection) => connection.backend_name(),
         }
     }
 
@@ -92,6 +156,18 @@ where
         }
     }
 
+    /// Returns this connection, switched to the given OpenGL API flavor.
+    pub fn with_gl_api(self, gl_api: GLApi) -> Result<Connection<Def, Alt>, Error> {
+        match self {
+            Connection::Default(connection) => {
+                connection.with_gl_api(gl_api).map(Connection::Default)
+            }
+            Connection::Alternate(connection) => {
+                connection.with_gl_api(gl_api).map(Connection::Alternate)
+            }
+        }
+    }
+
     /// Returns the "best" adapter on this system.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
@@ -180,27 +256,29 @@ where
     }
 
     /// Opens the display connection corresponding to the given `RawDisplayHandle.
+    ///
+    /// Like `new()`, this honors the `SURFMAN_BACKEND` environment variable.
     #[cfg(feature = "sm-raw-window-handle-05")]
     pub fn from_raw_display_handle(
         raw_handle: rwh_05::RawDisplayHandle,
     ) -> Result<Connection<Def, Alt>, Error> {
-        match <Def::Connection>::from_raw_display_handle(raw_handle) {
-            Ok(connection) => Ok(Connection::Default(connection)),
-            Err(_) => {
-                <Alt::Connection>::from_raw_display_handle(raw_handle).map(Connection::Alternate)
-            }
-        }
+        Connection::connect(
+            || <Def::Connection>::from_raw_display_handle(raw_handle),
+            || <Alt::Connection>::from_raw_display_handle(raw_handle),
+        )
     }
 
     /// Opens the display connection corresponding to the given `DisplayHandle`.
+    ///
+    /// Like `new()`, this honors the `SURFMAN_BACKEND` environment variable.
     #[cfg(feature = "sm-raw-window-handle-06")]
     pub fn from_display_handle(
         handle: rwh_06::DisplayHandle,
     ) -> Result<Connection<Def, Alt>, Error> {
-        match <Def::Connection>::from_display_handle(handle) {
-            Ok(connection) => Ok(Connection::Default(connection)),
-            Err(_) => <Alt::Connection>::from_display_handle(handle).map(Connection::Alternate),
-        }
+        Connection::connect(
+            || <Def::Connection>::from_display_handle(handle),
+            || <Alt::Connection>::from_display_handle(handle),
+        )
     }
 
     /// Create a native widget from a raw pointer
@@ -272,6 +350,21 @@ where
         Connection::new()
     }
 
+    #[inline]
+    fn new_with_backend(backend: &str) -> Result<Connection<Def, Alt>, Error> {
+        Connection::new_with_backend(backend)
+    }
+
+    #[inline]
+    fn backend_names() -> Vec<&'static str> {
+        Connection::<Def, Alt>::backend_names()
+    }
+
+    #[inline]
+    fn backend_name(&self) -> &'static str {
+        Connection::backend_name(self)
+    }
+
     #[inline]
     fn native_connection(&self) -> NativeConnection<Def, Alt> {
         Connection::native_connection(self)
@@ -282,6 +375,11 @@ where
         Connection::gl_api(self)
     }
 
+    #[inline]
+    fn with_gl_api(self, gl_api: GLApi) -> Result<Self, Error> {
+        Connection::with_gl_api(self, gl_api)
+    }
+
     #[inline]
     fn create_adapter(&self) -> Result<Adapter<Def, Alt>, Error> {
         Connection::create_adapter(self)
@@ -354,3 +452,18 @@ where
         Connection::create_native_widget_from_window_handle(self, handle, size)
     }
 }
+
+// Records why a backend failed, flattening the failures of nested multi-backend connections so
+// that each individual backend is listed.
+fn push_failure<C>(failures: &mut Vec<BackendFailure>, error: Error)
+where
+    C: ConnectionInterface,
+{
+    match error {
+        Error::NoBackendAvailable(nested_failures) => failures.extend(nested_failures),
+        error => failures.push(BackendFailure {
+            backend: C::backend_names().join("/"),
+            error,
+        }),
+    }
+}
@@ -5,7 +5,7 @@
 use super::device::Device;
 use super::surface::Surface;
 use crate::device::Device as DeviceInterface;
-use crate::{ContextAttributes, ContextID, Error, SurfaceInfo};
+use crate::{ContextAttributes, ContextID, Error, Gl, SurfaceInfo};
 
 use std::os::raw::c_void;
 
@@ -63,6 +63,18 @@ where
     Alternate(Alt::NativeContext),
 }
 
+/// Restores the previously-current context when dropped.
+pub enum CurrentContextGuard<Def, Alt>
+where
+    Def: DeviceInterface,
+    Alt: DeviceInterface,
+{
+    /// The default guard type.
+    Default(Def::CurrentContextGuard),
+    /// The alternate guard type.
+    Alternate(Alt::CurrentContextGuard),
+}
+
 impl<Def, Alt> Device<Def, Alt>
 where
     Def: DeviceInterface,
@@ -85,6 +97,22 @@ where
         }
     }
 
+    /// Creates a context descriptor for the first of the given candidate attributes that the
+    /// driver can create a context with.
+    pub fn create_context_descriptor_with_fallbacks(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor<Def, Alt>, Error> {
+        match *self {
+            Device::Default(ref mut device) => device
+                .create_context_descriptor_with_fallbacks(candidates)
+                .map(ContextDescriptor::Default),
+            Device::Alternate(ref mut device) => device
+                .create_context_descriptor_with_fallbacks(candidates)
+                .map(ContextDescriptor::Alternate),
+        }
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -211,6 +239,40 @@ where
         }
     }
 
+    /// Makes the context current and returns a guard that, when dropped, restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    pub fn temporarily_make_context_current(
+        &self,
+        context: &Context<Def, Alt>,
+    ) -> Result<CurrentContextGuard<Def, Alt>, Error> {
+        match (self, context) {
+            (Device::Default(device), Context::Default(context)) => device
+                .temporarily_make_context_current(context)
+                .map(CurrentContextGuard::Default),
+            (Device::Alternate(device), Context::Alternate(context)) => device
+                .temporarily_make_context_current(context)
+                .map(CurrentContextGuard::Alternate),
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
+    /// Makes the context current for the duration of the given closure, then restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    pub fn with_context_current<F, R>(&self, context: &Context<Def, Alt>, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&Gl) -> R,
+    {
+        match (self, context) {
+            (Device::Default(device), Context::Default(context)) => {
+                device.with_context_current(context, f)
+            }
+            (Device::Alternate(device), Context::Alternate(context)) => {
+                device.with_context_current(context, f)
+            }
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
     /// Attaches a surface to a context for rendering.
     ///
     /// This function takes ownership of the surface. The surface must have been created with this
@@ -3,12 +3,19 @@
 //! A device abstraction that allows the choice of backends dynamically.
 
 use super::connection::Connection;
-use super::context::{Context, ContextDescriptor, NativeContext};
+use super::context::{Context, ContextDescriptor, CurrentContextGuard, NativeContext};
+#[cfg(free_unix)]
+use super::surface::NativeSurface;
 use super::surface::{NativeWidget, Surface, SurfaceTexture};
 use crate::connection::Connection as ConnectionInterface;
 use crate::context::ContextAttributes;
 use crate::device::Device as DeviceInterface;
-use crate::{ContextID, Error, GLApi, SurfaceAccess, SurfaceInfo, SurfaceType};
+#[cfg(free_unix)]
+use crate::platform::generic::egl::ffi::EGLImageKHR;
+use crate::{
+    AdapterInfo, Capabilities, ContextID, Error, GLApi, Gl, SurfaceAccess, SurfaceAttributes,
+    SurfaceFormat, SurfaceInfo, SurfaceType,
+};
 use euclid::default::Size2D;
 use glow::Texture;
 
@@ -107,6 +114,23 @@ where
             Device::Alternate(ref device) => device.gl_api(),
         }
     }
+
+    /// Returns a description of what this device supports.
+    pub fn capabilities(&mut self) -> Result<Capabilities, Error> {
+        match *self {
+            Device::Default(ref mut device) => device.capabilities(),
+            Device::Alternate(ref mut device) => device.capabilities(),
+        }
+    }
+
+    /// Returns the vendor, renderer, and driver version of the GPU that this device renders
+    /// with, and whether it is a software renderer.
+    pub fn adapter_info(&mut self) -> Result<AdapterInfo, Error> {
+        match *self {
+            Device::Default(ref mut device) => device.adapter_info(),
+            Device::Alternate(ref mut device) => device.adapter_info(),
+        }
+    }
 }
 
 impl<Def, Alt> DeviceInterface for Device<Def, Alt>
@@ -122,6 +146,7 @@ where
     type NativeContext = NativeContext<Def, Alt>;
     type Surface = Surface<Def, Alt>;
     type SurfaceTexture = SurfaceTexture<Def, Alt>;
+    type CurrentContextGuard = CurrentContextGuard<Def, Alt>;
 
     // device.rs
 
@@ -145,6 +170,16 @@ where
         Device::gl_api(self)
     }
 
+    #[inline]
+    fn capabilities(&mut self) -> Result<Capabilities, Error> {
+        Device::capabilities(self)
+    }
+
+    #[inline]
+    fn adapter_info(&mut self) -> Result<AdapterInfo, Error> {
+        Device::adapter_info(self)
+    }
+
     // context.rs
 
     #[inline]
@@ -155,6 +190,14 @@ where
         Device::create_context_descriptor(self, attributes)
     }
 
+    #[inline]
+    fn create_context_descriptor_with_fallbacks(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<Self::ContextDescriptor, Error> {
+        Device::create_context_descriptor_with_fallbacks(self, candidates)
+    }
+
     #[inline]
     fn create_context(
         &mut self,
@@ -197,6 +240,22 @@ where
         Device::make_no_context_current(self)
     }
 
+    #[inline]
+    fn with_context_current<F, R>(&self, context: &Context<Def, Alt>, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&Gl) -> R,
+    {
+        Device::with_context_current(self, context, f)
+    }
+
+    #[inline]
+    fn temporarily_make_context_current(
+        &self,
+        context: &Context<Def, Alt>,
+    ) -> Result<CurrentContextGuard<Def, Alt>, Error> {
+        Device::temporarily_make_context_current(self, context)
+    }
+
     #[inline]
     fn context_descriptor_attributes(
         &self,
@@ -252,6 +311,23 @@ where
         Device::create_surface(self, context, surface_access, surface_type)
     }
 
+    #[inline]
+    fn create_generic_surface_with_attributes(
+        &mut self,
+        context: &Context<Def, Alt>,
+        surface_access: SurfaceAccess,
+        size: &Size2D<i32>,
+        surface_attributes: &SurfaceAttributes,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        Device::create_generic_surface_with_attributes(
+            self,
+            context,
+            surface_access,
+            size,
+            surface_attributes,
+        )
+    }
+
     #[inline]
     fn create_surface_texture(
         &self,
@@ -315,4 +391,94 @@ where
     ) -> Option<Texture> {
         Device::surface_texture_object(self, surface_texture)
     }
+
+    #[inline]
+    fn surface_texture_target(&self, surface_texture: &SurfaceTexture<Def, Alt>) -> u32 {
+        Device::surface_texture_target(self, surface_texture)
+    }
+}
+
+/// Methods that the Unix EGL backends provide beyond `DeviceInterface`.
+///
+/// `Device` implements this when both of its backends do, forwarding to whichever one is in use,
+/// so chained devices such as the default Unix device provide these methods too.
+#[cfg(free_unix)]
+pub trait EGLDeviceInterface: DeviceInterface {
+    /// The native surface type associated with this device.
+    type NativeSurface;
+
+    /// Wraps an existing OpenGL texture in a generic surface.
+    ///
+    /// # Safety
+    ///
+    /// See `create_surface_from_texture()` on the backend devices.
+    unsafe fn create_surface_from_texture(
+        &mut self,
+        context: &Self::Context,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture: Texture,
+    ) -> Result<Self::Surface, Error>;
+
+    /// Creates a surface texture that samples from an EGL image made elsewhere.
+    ///
+    /// # Safety
+    ///
+    /// See `create_surface_texture_from_egl_image()` on the backend devices.
+    unsafe fn create_surface_texture_from_egl_image(
+        &self,
+        context: &mut Self::Context,
+        egl_image: EGLImageKHR,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture_target: u32,
+    ) -> Result<Self::SurfaceTexture, Error>;
+
+    /// Returns the native EGL and OpenGL objects behind this surface.
+    fn native_surface(&self, surface: &Self::Surface) -> Self::NativeSurface;
+}
+
+#[cfg(free_unix)]
+impl<Def, Alt> EGLDeviceInterface for Device<Def, Alt>
+where
+    Def: EGLDeviceInterface,
+    Alt: EGLDeviceInterface,
+    Def::Connection: ConnectionInterface<Device = Def>,
+    Alt::Connection: ConnectionInterface<Device = Alt>,
+{
+    type NativeSurface = NativeSurface<Def, Alt>;
+
+    #[inline]
+    unsafe fn create_surface_from_texture(
+        &mut self,
+        context: &Context<Def, Alt>,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture: Texture,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        Device::create_surface_from_texture(self, context, size, format, texture)
+    }
+
+    #[inline]
+    unsafe fn create_surface_texture_from_egl_image(
+        &self,
+        context: &mut Context<Def, Alt>,
+        egl_image: EGLImageKHR,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture_target: u32,
+    ) -> Result<SurfaceTexture<Def, Alt>, Error> {
+        Device::create_surface_texture_from_egl_image(
+            self,
+            context,
+            egl_image,
+            size,
+            format,
+            texture_target,
+        )
+    }
+    #[inline]
+    fn native_surface(&self, surface: &Surface<Def, Alt>) -> NativeSurface<Def, Alt> {
+        Device::native_surface(self, surface)
+    }
 }
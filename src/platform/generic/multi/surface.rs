@@ -4,9 +4,13 @@
 
 use super::context::Context;
 use super::device::Device;
+#[cfg(free_unix)]
+use super::device::EGLDeviceInterface;
 use crate::connection::Connection as ConnectionInterface;
 use crate::device::Device as DeviceInterface;
-use crate::{Error, SurfaceAccess, SurfaceInfo, SurfaceType};
+#[cfg(free_unix)]
+use crate::platform::generic::egl::ffi::EGLImageKHR;
+use crate::{Error, SurfaceAccess, SurfaceAttributes, SurfaceFormat, SurfaceInfo, SurfaceType};
 use euclid::default::Size2D;
 use glow::Texture;
 
@@ -71,6 +75,19 @@ where
     Alternate(<Alt::Connection as ConnectionInterface>::NativeWidget),
 }
 
+/// The native objects behind a surface, from whichever backend is in use.
+#[cfg(free_unix)]
+pub enum NativeSurface<Def, Alt>
+where
+    Def: EGLDeviceInterface,
+    Alt: EGLDeviceInterface,
+{
+    /// The default native surface type.
+    Default(Def::NativeSurface),
+    /// The alternate native surface type.
+    Alternate(Alt::NativeSurface),
+}
+
 impl<Def, Alt> Debug for Surface<Def, Alt>
 where
     Def: DeviceInterface,
@@ -139,6 +156,36 @@ where
         }
     }
 
+    /// Creates a generic surface whose color format and depth/stencil buffers are given by
+    /// `surface_attributes` rather than by the attributes of the context.
+    pub fn create_generic_surface_with_attributes(
+        &mut self,
+        context: &Context<Def, Alt>,
+        surface_access: SurfaceAccess,
+        size: &Size2D<i32>,
+        surface_attributes: &SurfaceAttributes,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        match (&mut *self, context) {
+            (&mut Device::Default(ref mut device), Context::Default(context)) => device
+                .create_generic_surface_with_attributes(
+                    context,
+                    surface_access,
+                    size,
+                    surface_attributes,
+                )
+                .map(Surface::Default),
+            (&mut Device::Alternate(ref mut device), Context::Alternate(context)) => device
+                .create_generic_surface_with_attributes(
+                    context,
+                    surface_access,
+                    size,
+                    surface_attributes,
+                )
+                .map(Surface::Alternate),
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
     /// Creates a surface texture from an existing generic surface for use with the given context.
     ///
     /// The surface texture is local to the supplied context and takes ownership of the surface.
@@ -341,4 +388,105 @@ where
             _ => panic!("Incompatible context!"),
         }
     }
+
+    /// Returns the OpenGL texture target that the texture object of this surface texture must be
+    /// bound to. See `Device::surface_texture_target()`.
+    pub fn surface_texture_target(&self, surface_texture: &SurfaceTexture<Def, Alt>) -> u32 {
+        match (self, surface_texture) {
+            (Device::Default(device), SurfaceTexture::Default(ref surface_texture)) => {
+                device.surface_texture_target(surface_texture)
+            }
+            (Device::Alternate(device), SurfaceTexture::Alternate(ref surface_texture)) => {
+                device.surface_texture_target(surface_texture)
+            }
+            _ => panic!("Incompatible context!"),
+        }
+    }
+}
+
+#[cfg(free_unix)]
+impl<Def, Alt> Device<Def, Alt>
+where
+    Def: EGLDeviceInterface,
+    Alt: EGLDeviceInterface,
+{
+    /// Wraps an existing OpenGL texture in a generic surface, so that it can be rendered to by the
+    /// context, read from other contexts via `create_surface_texture()`, or handed to a swap
+    /// chain.
+    ///
+    /// # Safety
+    ///
+    /// See `create_surface_from_texture()` on the backend devices. The texture remains owned by
+    /// the caller.
+    pub unsafe fn create_surface_from_texture(
+        &mut self,
+        context: &Context<Def, Alt>,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture: Texture,
+    ) -> Result<Surface<Def, Alt>, Error> {
+        match (&mut *self, context) {
+            (&mut Device::Default(ref mut device), Context::Default(context)) => device
+                .create_surface_from_texture(context, size, format, texture)
+                .map(Surface::Default),
+            (&mut Device::Alternate(ref mut device), Context::Alternate(context)) => device
+                .create_surface_from_texture(context, size, format, texture)
+                .map(Surface::Alternate),
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
+    /// Creates a surface texture that samples from an EGL image made elsewhere, for example by a
+    /// video decoder.
+    ///
+    /// # Safety
+    ///
+    /// See `create_surface_texture_from_egl_image()` on the backend devices. The image remains
+    /// owned by the caller.
+    pub unsafe fn create_surface_texture_from_egl_image(
+        &self,
+        context: &mut Context<Def, Alt>,
+        egl_image: EGLImageKHR,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture_target: u32,
+    ) -> Result<SurfaceTexture<Def, Alt>, Error> {
+        match (self, &mut *context) {
+            (Device::Default(device), &mut Context::Default(ref mut context)) => device
+                .create_surface_texture_from_egl_image(
+                    context,
+                    egl_image,
+                    size,
+                    format,
+                    texture_target,
+                )
+                .map(SurfaceTexture::Default),
+            (Device::Alternate(device), &mut Context::Alternate(ref mut context)) => device
+                .create_surface_texture_from_egl_image(
+                    context,
+                    egl_image,
+                    size,
+                    format,
+                    texture_target,
+                )
+                .map(SurfaceTexture::Alternate),
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
+    /// Returns the native EGL and OpenGL objects behind this surface.
+    ///
+    /// The objects still belong to the surface, so they must not be destroyed by the caller, and
+    /// they become invalid once the surface is destroyed.
+    pub fn native_surface(&self, surface: &Surface<Def, Alt>) -> NativeSurface<Def, Alt> {
+        match (self, surface) {
+            (Device::Default(device), Surface::Default(ref surface)) => {
+                NativeSurface::Default(device.native_surface(surface))
+            }
+            (Device::Alternate(device), Surface::Alternate(ref surface)) => {
+                NativeSurface::Alternate(device.native_surface(surface))
+            }
+            _ => panic!("Incompatible context!"),
+        }
+    }
 }
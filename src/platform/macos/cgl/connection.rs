@@ -18,6 +18,8 @@ use std::os::raw::c_void;
 
 pub use crate::platform::macos::system::connection::NativeConnection;
 
+pub(crate) const BACKEND_NAME: &str = "cgl";
+
 /// A connection to the display server.
 #[derive(Clone)]
 pub struct Connection(pub SystemConnection);
@@ -49,6 +51,16 @@ impl Connection {
         GLApi::GL
     }
 
+    /// Returns this connection if `gl_api` is OpenGL, the only flavor this backend supports.
+    /// Otherwise, returns an `UnsupportedGLType` error.
+    #[inline]
+    pub fn with_gl_api(self, gl_api: GLApi) -> Result<Connection, Error> {
+        match gl_api {
+            GLApi::GL => Ok(self),
+            GLApi::GLES => Err(Error::UnsupportedGLType),
+        }
+    }
+
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
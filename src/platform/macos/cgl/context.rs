@@ -6,7 +6,7 @@ use super::device::Device;
 use super::error::ToWindowingApiError;
 use super::ffi::{CGLReleaseContext, CGLRetainContext};
 use super::surface::Surface;
-use crate::context::{ContextID, CREATE_CONTEXT_MUTEX};
+use crate::context::{self, ContextID, CREATE_CONTEXT_MUTEX};
 use crate::gl_utils;
 use crate::surface::Framebuffer;
 use crate::{ContextAttributeFlags, ContextAttributes, Error, GLVersion, Gl, SurfaceInfo};
@@ -206,6 +206,19 @@ impl Device {
         }
     }
 
+    /// Creates a context descriptor for the first of the given candidate attributes that the
+    /// driver can create a context with.
+    ///
+    /// Each candidate is checked by creating and destroying a temporary context. Whatever context
+    /// was current on this thread beforehand remains current afterward.
+    pub fn create_context_descriptor_with_fallbacks(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor, Error> {
+        let _guard = CurrentContextGuard::new();
+        context::negotiate_context_descriptor(self, candidates)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -320,7 +333,11 @@ impl Device {
         }
     }
 
-    pub(crate) fn temporarily_make_context_current(
+    /// Makes the context current and returns a guard that, when dropped, restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    ///
+    /// This works even if the previously-current context was not created by `surfman`.
+    pub fn temporarily_make_context_current(
         &self,
         context: &Context,
     ) -> Result<CurrentContextGuard, Error> {
@@ -329,6 +346,17 @@ impl Device {
         Ok(guard)
     }
 
+    /// Makes the context current for the duration of the given closure, then restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    #[inline]
+    pub fn with_context_current<F, R>(&self, context: &Context, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&Gl) -> R,
+    {
+        let _guard = self.temporarily_make_context_current(context)?;
+        Ok(f(&context.gl))
+    }
+
     /// Attaches a surface to a context for rendering.
     ///
     /// This function takes ownership of the surface. The surface must have been created with this
@@ -491,8 +519,11 @@ fn get_proc_address(symbol_name: &str) -> *const c_void {
     })
 }
 
+/// Restores the previously-current CGL context when dropped.
+///
+/// This works even if the previous context was not created by `surfman`.
 #[must_use]
-pub(crate) struct CurrentContextGuard {
+pub struct CurrentContextGuard {
     old_cgl_context: CGLContextObj,
 }
 
@@ -505,7 +536,7 @@ impl Drop for CurrentContextGuard {
 }
 
 impl CurrentContextGuard {
-    fn new() -> CurrentContextGuard {
+    pub(crate) fn new() -> CurrentContextGuard {
         unsafe {
             CurrentContextGuard {
                 old_cgl_context: CGLGetCurrentContext(),
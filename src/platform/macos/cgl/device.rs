@@ -3,8 +3,10 @@
 //! A handle to the device. (This is a no-op, because handles are implicit in Apple's Core OpenGL.)
 
 use super::connection::Connection;
+use super::context::CurrentContextGuard;
+use crate::capabilities;
 use crate::platform::macos::system::device::{Adapter as SystemAdapter, Device as SystemDevice};
-use crate::GLApi;
+use crate::{AdapterInfo, Capabilities, CapabilityFlags, Error, GLApi, SurfaceFormat};
 
 pub use crate::platform::macos::system::device::NativeDevice;
 
@@ -44,4 +46,19 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GL
     }
+
+    /// Returns what this device supports. CGL has no extensions, so none are listed.
+    pub fn capabilities(&mut self) -> Result<Capabilities, Error> {
+        let _guard = CurrentContextGuard::new();
+        // Surfaces are backed by BGRA IOSurfaces.
+        capabilities::probe(self, vec![], CapabilityFlags::empty(), |_, format| {
+            matches!(format, SurfaceFormat::RGBA8 | SurfaceFormat::BGRA8)
+        })
+    }
+
+    /// Returns the GPU that this device renders with. See `Device::adapter_info()`.
+    pub fn adapter_info(&mut self) -> Result<AdapterInfo, Error> {
+        let _guard = CurrentContextGuard::new();
+        capabilities::probe_adapter_info(self, false)
+    }
 }
@@ -8,7 +8,9 @@ use crate::context::ContextID;
 use crate::gl_utils;
 use crate::platform::macos::system::surface::Surface as SystemSurface;
 use crate::renderbuffers::Renderbuffers;
-use crate::{gl, Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType, WindowingApiError};
+use crate::WindowingApiError;
+use crate::{gl, Error, SurfaceAccess, SurfaceAttributes, SurfaceFormat, SurfaceID};
+use crate::{SurfaceInfo, SurfaceType};
 use cgl::{kCGLNoError, CGLErrorString, CGLGetCurrentContext, CGLTexImageIOSurface2D, GLenum};
 use glow::Context as Gl;
 
@@ -42,6 +44,7 @@ const SURFACE_GL_TEXTURE_TARGET: u32 = gl::TEXTURE_RECTANGLE;
 /// Surfaces must be destroyed with the `destroy_surface()` method, or a panic will occur.
 pub struct Surface {
     pub(crate) system_surface: SystemSurface,
+    pub(crate) format: SurfaceFormat,
     pub(crate) context_id: ContextID,
     pub(crate) framebuffer_object: Option<glow::Framebuffer>,
     pub(crate) texture_object: Option<Texture>,
@@ -120,6 +123,43 @@ impl Device {
         access: SurfaceAccess,
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
+        let context_descriptor = self.context_descriptor(context);
+        let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+        let surface_attributes = SurfaceAttributes::for_context(&context_attributes);
+        self.create_surface_with_attributes(context, access, surface_type, &surface_attributes)
+    }
+
+    /// Creates a generic surface whose color format and depth/stencil buffers are given by
+    /// `surface_attributes` rather than by the attributes of the context.
+    pub fn create_generic_surface_with_attributes(
+        &mut self,
+        context: &Context,
+        access: SurfaceAccess,
+        size: &Size2D<i32>,
+        surface_attributes: &SurfaceAttributes,
+    ) -> Result<Surface, Error> {
+        let surface_type = SurfaceType::Generic { size: *size };
+        self.create_surface_with_attributes(context, access, surface_type, surface_attributes)
+    }
+
+    fn create_surface_with_attributes(
+        &mut self,
+        context: &Context,
+        access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget>,
+        surface_attributes: &SurfaceAttributes,
+    ) -> Result<Surface, Error> {
+        if surface_attributes.layers != 1 || surface_attributes.samples != 1 {
+            return Err(Error::UnsupportedOnThisPlatform);
+        }
+        // IOSurfaces are always BGRA in memory.
+        match surface_attributes.format {
+            SurfaceFormat::RGBA8 | SurfaceFormat::BGRA8 => {}
+            SurfaceFormat::RGBA16F | SurfaceFormat::RGB10A2 | SurfaceFormat::R8 => {
+                return Err(Error::UnsupportedSurfaceFormat)
+            }
+        }
+
         let mut system_surface = self.0.create_surface(access, surface_type)?;
         self.0.set_surface_flipped(&mut system_surface, true);
 
@@ -141,11 +181,8 @@ impl Device {
                 0,
             );
 
-            let context_descriptor = self.context_descriptor(context);
-            let context_attributes = self.context_descriptor_attributes(&context_descriptor);
-
             let mut renderbuffers =
-                Renderbuffers::new(gl, &system_surface.size, &context_attributes);
+                Renderbuffers::new(gl, &system_surface.size, surface_attributes.attachments);
             renderbuffers.bind_to_current_framebuffer(gl);
 
             if gl.get_error() != gl::NO_ERROR
@@ -164,6 +201,7 @@ impl Device {
 
             Ok(Surface {
                 system_surface,
+                format: surface_attributes.format,
                 context_id: context.id,
                 framebuffer_object: Some(framebuffer_object),
                 texture_object: Some(texture_object),
@@ -307,6 +345,13 @@ impl Device {
         surface_texture.texture_object
     }
 
+    /// Returns the OpenGL texture target that the texture object of this surface texture must be
+    /// bound to, which is always `surface_gl_texture_target()` here.
+    #[inline]
+    pub fn surface_texture_target(&self, _: &SurfaceTexture) -> u32 {
+        self.surface_gl_texture_target()
+    }
+
     /// Returns the OpenGL texture target needed to read from this surface texture.
     ///
     /// This will be `GL_TEXTURE_2D` or `GL_TEXTURE_RECTANGLE`, depending on platform.
@@ -358,9 +403,6 @@ impl Device {
 
         self.0.resize_surface(&mut surface.system_surface, size)?;
 
-        let context_descriptor = self.context_descriptor(context);
-        let context_attributes = self.context_descriptor_attributes(&context_descriptor);
-
         let gl = &context.gl;
         unsafe {
             // Recreate the GL texture and bind it to the FBO
@@ -375,7 +417,7 @@ impl Device {
             );
 
             // Recreate the GL renderbuffers and bind them to the FBO
-            let renderbuffers = Renderbuffers::new(gl, &size, &context_attributes);
+            let renderbuffers = Renderbuffers::new(gl, &size, surface.renderbuffers.attachments());
             renderbuffers.bind_to_current_framebuffer(gl);
 
             if let Some(texture) = surface.texture_object {
@@ -423,11 +465,15 @@ impl Device {
     #[inline]
     pub fn surface_info(&self, surface: &Surface) -> SurfaceInfo {
         let system_surface_info = self.0.surface_info(&surface.system_surface);
+        let framebuffer_object = surface.framebuffer_object;
         SurfaceInfo {
             size: system_surface_info.size,
             id: system_surface_info.id,
             context_id: surface.context_id,
-            framebuffer_object: surface.framebuffer_object,
+            framebuffer_object,
+            format: surface.format,
+            layers: 1,
+            layer_framebuffer_objects: framebuffer_object.into_iter().collect(),
         }
     }
 
@@ -23,6 +23,7 @@ pub mod connection {
 pub mod context {
     use crate::platform::generic::multi::context::Context as MultiContext;
     use crate::platform::generic::multi::context::ContextDescriptor as MultiContextDescriptor;
+    use crate::platform::generic::multi::context::CurrentContextGuard as MultiCurrentContextGuard;
     use crate::platform::generic::multi::context::NativeContext as MultiNativeContext;
     use crate::platform::generic::multi::device::Device as MultiDevice;
     use crate::platform::unix::generic::device::Device as SWDevice;
@@ -56,6 +57,9 @@ pub mod context {
 
     /// Either a Wayland or an X11 native context
     pub type NativeContext = MultiNativeContext<HWDevice, SWDevice>;
+
+    /// Restores the previously-current context when dropped.
+    pub type CurrentContextGuard = MultiCurrentContextGuard<HWDevice, SWDevice>;
 }
 
 /// Thread-local handles to devices.
@@ -87,6 +91,7 @@ pub mod device {
 /// Hardware buffers of pixels.
 pub mod surface {
     use crate::platform::generic::multi::device::Device as MultiDevice;
+    use crate::platform::generic::multi::surface::NativeSurface as MultiNativeSurface;
     use crate::platform::generic::multi::surface::NativeWidget as MultiNativeWidget;
     use crate::platform::generic::multi::surface::Surface as MultiSurface;
     use crate::platform::generic::multi::surface::SurfaceTexture as MultiSurfaceTexture;
@@ -98,6 +103,9 @@ pub mod surface {
     /// A wrapper for a Wayland surface or an X11 `Window`, as appropriate.
     pub type NativeWidget = MultiNativeWidget<HWDevice, SWDevice>;
 
+    /// The native EGL and OpenGL objects behind a Wayland, X11 or surfaceless surface.
+    pub type NativeSurface = MultiNativeSurface<HWDevice, SWDevice>;
+
     /// Represents a hardware buffer of pixels that can be rendered to via the CPU or GPU and
     /// either displayed in a native widget or bound to a texture for reading.
     ///
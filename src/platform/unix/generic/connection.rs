@@ -7,8 +7,10 @@ use super::surface::NativeWidget;
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDisplay};
 use crate::info::GLApi;
+use crate::platform::generic::egl::context;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::ffi::EGL_PLATFORM_SURFACELESS_MESA;
+use crate::platform::unix::default_gl_api;
 use crate::Error;
 
 use euclid::default::Size2D;
@@ -16,10 +18,13 @@ use euclid::default::Size2D;
 use std::os::raw::c_void;
 use std::sync::Arc;
 
+pub(crate) const BACKEND_NAME: &str = "surfaceless";
+
 /// A no-op connection.
 #[derive(Clone)]
 pub struct Connection {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
+    pub(crate) gl_api: GLApi,
 }
 
 /// Native connections.
@@ -72,6 +77,7 @@ impl Connection {
     ) -> Result<Connection, Error> {
         Ok(Connection {
             native_connection: native_connection.0,
+            gl_api: default_gl_api(),
         })
     }
 
@@ -84,7 +90,21 @@ impl Connection {
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Returns this connection, switched to the given OpenGL API flavor.
+    ///
+    /// Devices created from the returned connection bind `EGL_OPENGL_ES_API` and choose
+    /// `EGL_OPENGL_ES3_BIT` (or `EGL_OPENGL_ES2_BIT`) configs when `gl_api` is `GLApi::GLES`.
+    /// If the display has no configs for `gl_api`, an `UnsupportedGLType` error is returned.
+    pub fn with_gl_api(mut self, gl_api: GLApi) -> Result<Connection, Error> {
+        let egl_display = self.native_connection.egl_display;
+        if !unsafe { context::display_supports_gl_api(egl_display, gl_api) } {
+            return Err(Error::UnsupportedGLType);
+        }
+        self.gl_api = gl_api;
+        Ok(self)
     }
 
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
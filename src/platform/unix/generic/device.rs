@@ -3,7 +3,11 @@
 //! A wrapper around surfaceless Mesa `EGLDisplay`s.
 
 use super::connection::{Connection, NativeConnectionWrapper};
-use crate::{Error, GLApi};
+use crate::capabilities;
+use crate::platform::generic::egl::context::CurrentContextGuard;
+use crate::platform::generic::egl::device;
+use crate::platform::generic::egl::surface;
+use crate::{AdapterInfo, Capabilities, Error, GLApi, SurfaceFormat};
 
 use std::env;
 use std::sync::Arc;
@@ -68,6 +72,7 @@ impl Adapter {
 pub struct Device {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
     pub(crate) adapter: Adapter,
+    pub(crate) gl_api: GLApi,
 }
 
 /// Wraps an adapter.
@@ -85,6 +90,7 @@ impl Device {
         Ok(Device {
             native_connection: connection.native_connection.clone(),
             adapter: (*adapter).clone(),
+            gl_api: connection.gl_api,
         })
     }
 
@@ -104,6 +110,7 @@ impl Device {
     pub fn connection(&self) -> Connection {
         Connection {
             native_connection: self.native_connection.clone(),
+            gl_api: self.gl_api,
         }
     }
 
@@ -116,6 +123,29 @@ impl Device {
     /// Returns the OpenGL API flavor that this device supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Returns what this device supports. See `Device::capabilities()`.
+    pub fn capabilities(&mut self) -> Result<Capabilities, Error> {
+        let _guard = CurrentContextGuard::new();
+        unsafe {
+            let egl_display = self.native_connection.egl_display;
+            let extensions = device::query_extensions(egl_display);
+            let flags = device::capability_flags(&extensions);
+            // Without EGL images, generic surfaces fall back to pbuffers in the context's config.
+            capabilities::probe(self, extensions, flags, |gl, format| {
+                format == SurfaceFormat::RGBA8 || surface::egl_images_supported(gl, egl_display)
+            })
+        }
+    }
+
+    /// Returns the GPU that this device renders with. See `Device::adapter_info()`.
+    pub fn adapter_info(&mut self) -> Result<AdapterInfo, Error> {
+        let _guard = CurrentContextGuard::new();
+        unsafe {
+            let is_software = device::device_is_software(self.native_connection.egl_display);
+            capabilities::probe_adapter_info(self, is_software)
+        }
     }
 }
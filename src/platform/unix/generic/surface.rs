@@ -5,13 +5,17 @@
 use super::context::Context;
 use super::device::Device;
 use crate::gl;
+use crate::platform::generic::egl::ffi::EGLImageKHR;
 use crate::platform::generic::egl::surface::{EGLBackedSurface, EGLSurfaceTexture};
-use crate::{Error, SurfaceAccess, SurfaceInfo, SurfaceType};
+use crate::platform::generic::multi::device::EGLDeviceInterface;
+use crate::{Error, SurfaceAccess, SurfaceAttributes, SurfaceFormat, SurfaceInfo, SurfaceType};
 
 use euclid::default::Size2D;
 use glow::Texture;
 use std::marker::PhantomData;
 
+pub use crate::platform::generic::egl::surface::{NativeSurface, NativeTextureImage};
+
 // FIXME(pcwalton): Is this right, or should it be `TEXTURE_EXTERNAL_OES`?
 const SURFACE_GL_TEXTURE_TARGET: u32 = gl::TEXTURE_2D;
 
@@ -60,32 +64,122 @@ impl Device {
     pub fn create_surface(
         &mut self,
         context: &Context,
-        _: SurfaceAccess,
+        access: SurfaceAccess,
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
         match surface_type {
-            SurfaceType::Generic { size } => self.create_generic_surface(context, &size),
+            SurfaceType::Generic { size } => {
+                let context_descriptor = self.context_descriptor(context);
+                let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+                let surface_attributes = SurfaceAttributes::for_context(&context_attributes);
+                self.create_generic_surface_with_attributes(
+                    context,
+                    access,
+                    &size,
+                    &surface_attributes,
+                )
+            }
             SurfaceType::Widget { .. } => Err(Error::UnsupportedOnThisPlatform),
         }
     }
 
-    fn create_generic_surface(
+    /// Creates a generic surface whose color format and depth/stencil buffers are given by
+    /// `surface_attributes` rather than by the attributes of the context.
+    ///
+    /// If the surface falls back to a pbuffer, its depth and stencil buffers are those of the
+    /// context's config.
+    pub fn create_generic_surface_with_attributes(
         &mut self,
         context: &Context,
+        _: SurfaceAccess,
         size: &Size2D<i32>,
+        surface_attributes: &SurfaceAttributes,
     ) -> Result<Surface, Error> {
         let _guard = self.temporarily_make_context_current(context)?;
-        let context_descriptor = self.context_descriptor(context);
-        let context_attributes = self.context_descriptor_attributes(&context_descriptor);
 
         Ok(Surface(EGLBackedSurface::new_generic(
             &context.1,
             self.native_connection.egl_display,
             context.0.egl_context,
             context.0.id,
-            &context_attributes,
+            context.0.share_group,
+            surface_attributes,
+            size,
+        )?))
+    }
+
+    /// Wraps an existing OpenGL texture in a generic surface, so that it can be rendered to by the
+    /// context, read from other contexts via `create_surface_texture()`, or handed to a swap
+    /// chain.
+    ///
+    /// The surface has no depth or stencil buffer and reports the given format. Where the context
+    /// can inspect the texture, that is, with desktop OpenGL or OpenGL ES 3.1, a texture whose size
+    /// or internal format doesn't match returns a `SurfaceImportFailed(BadMatch)` error.
+    ///
+    /// # Safety
+    ///
+    /// `texture` must be a complete `GL_TEXTURE_2D` texture of the given size and format,
+    /// belonging to `context` or to a context that shares objects with it.
+    ///
+    /// The texture remains owned by the caller: `destroy_surface()` does not delete it, and the
+    /// caller must not delete it until the surface has been destroyed.
+    pub unsafe fn create_surface_from_texture(
+        &mut self,
+        context: &Context,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture: Texture,
+    ) -> Result<Surface, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+
+        Ok(Surface(EGLBackedSurface::new_from_texture(
+            &context.1,
+            self.native_connection.egl_display,
+            context.0.egl_context,
+            context.0.id,
+            size,
+            format,
+            texture,
+        )?))
+    }
+
+    /// Creates a surface texture that samples from an EGL image made elsewhere, for example by a
+    /// video decoder.
+    ///
+    /// `texture_target` must be `GL_TEXTURE_2D` or `GL_TEXTURE_EXTERNAL_OES`. The surface texture
+    /// is bound to that target, as reported by `surface_texture_target()`.
+    ///
+    /// Destroying the surface texture returns a surface that wraps the image. That surface can't
+    /// be bound to a context, must be destroyed with `destroy_surface()` as usual, and reports the
+    /// given format, since EGL has no way to query the format of an image.
+    ///
+    /// # Safety
+    ///
+    /// `egl_image` must be a valid image on this device's EGL display, of the given size and
+    /// format. It remains owned by the caller, who must keep it alive until the surface created
+    /// from it has been destroyed.
+    pub unsafe fn create_surface_texture_from_egl_image(
+        &self,
+        context: &mut Context,
+        egl_image: EGLImageKHR,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture_target: u32,
+    ) -> Result<SurfaceTexture, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+
+        let surface = EGLBackedSurface::new_from_egl_image(
+            &context.1,
+            context.0.id,
+            egl_image,
             size,
-        )))
+            format,
+            texture_target,
+        )?;
+        surface
+            .to_surface_texture(&context.1, context.0.share_group)
+            .map(SurfaceTexture)
+            .map_err(|(err, _)| err)
     }
 
     /// Creates a surface texture from an existing generic surface for use with the given context.
@@ -96,6 +190,7 @@ impl Device {
     /// *The supplied context does not have to be the same context that the surface is associated
     /// with.* This allows you to render to a surface in one context and sample from that surface
     /// in another context.
+    /// Surfaces with more than one layer are the exception: see `SurfaceAttributes::layers`.
     ///
     /// Calling this method on a widget surface returns a `WidgetAttached` error.
     pub fn create_surface_texture(
@@ -108,7 +203,10 @@ impl Device {
             Err(err) => return Err((err, surface)),
         };
 
-        match surface.0.to_surface_texture(&context.1) {
+        match surface
+            .0
+            .to_surface_texture(&context.1, context.0.share_group)
+        {
             Ok(surface_texture) => Ok(SurfaceTexture(surface_texture)),
             Err((err, surface)) => Err((err, Surface(surface))),
         }
@@ -205,6 +303,60 @@ impl Device {
     pub fn surface_texture_object(&self, surface_texture: &SurfaceTexture) -> Option<Texture> {
         surface_texture.0.texture_object
     }
+
+    /// Returns the OpenGL texture target that the texture object of this surface texture must be
+    /// bound to. See `Device::surface_texture_target()`.
+    #[inline]
+    pub fn surface_texture_target(&self, surface_texture: &SurfaceTexture) -> u32 {
+        surface_texture.0.texture_target
+    }
+
+    /// Returns the native EGL and OpenGL objects behind this surface.
+    ///
+    /// The objects still belong to the surface, so they must not be destroyed by the caller, and
+    /// they become invalid once the surface is destroyed.
+    #[inline]
+    pub fn native_surface(&self, surface: &Surface) -> NativeSurface {
+        surface.0.native_surface()
+    }
+}
+
+impl EGLDeviceInterface for Device {
+    type NativeSurface = NativeSurface;
+
+    #[inline]
+    unsafe fn create_surface_from_texture(
+        &mut self,
+        context: &Context,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture: Texture,
+    ) -> Result<Surface, Error> {
+        Device::create_surface_from_texture(self, context, size, format, texture)
+    }
+
+    #[inline]
+    unsafe fn create_surface_texture_from_egl_image(
+        &self,
+        context: &mut Context,
+        egl_image: EGLImageKHR,
+        size: &Size2D<i32>,
+        format: SurfaceFormat,
+        texture_target: u32,
+    ) -> Result<SurfaceTexture, Error> {
+        Device::create_surface_texture_from_egl_image(
+            self,
+            context,
+            egl_image,
+            size,
+            format,
+            texture_target,
+        )
+    }
+    #[inline]
+    fn native_surface(&self, surface: &Surface) -> NativeSurface {
+        Device::native_surface(self, surface)
+    }
 }
 
 /// Represents the CPU view of the pixel data of this surface.
@@ -16,3 +16,17 @@ pub mod generic;
 pub mod wayland;
 #[cfg(x11_platform)]
 pub mod x11;
+
+use crate::GLApi;
+
+pub(crate) static FORCE_GLES_ENV_VAR: &str = "SURFMAN_FORCE_GLES";
+
+// Connections use OpenGL unless `SURFMAN_FORCE_GLES` is set or `with_gl_api()` says otherwise.
+// See the README.
+pub(crate) fn default_gl_api() -> GLApi {
+    if std::env::var(FORCE_GLES_ENV_VAR).is_ok() {
+        GLApi::GLES
+    } else {
+        GLApi::GL
+    }
+}
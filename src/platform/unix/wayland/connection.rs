@@ -7,8 +7,11 @@ use super::surface::NativeWidget;
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDisplay};
 use crate::info::GLApi;
+use crate::platform::generic;
+use crate::platform::generic::egl::context;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::ffi::EGL_PLATFORM_WAYLAND_KHR;
+use crate::platform::unix::default_gl_api;
 use crate::Error;
 
 use euclid::default::Size2D;
@@ -17,10 +20,13 @@ use std::ptr;
 use std::sync::Arc;
 use wayland_sys::client::{wayland_client_handle, wl_display, wl_proxy};
 
+pub(crate) const BACKEND_NAME: &str = "wayland";
+
 /// A connection to the Wayland server.
 #[derive(Clone)]
 pub struct Connection {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
+    pub(crate) gl_api: GLApi,
 }
 
 pub(crate) struct NativeConnectionWrapper {
@@ -63,11 +69,21 @@ impl Connection {
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        if std::env::var("SURFMAN_FORCE_GLES").is_ok() {
-            GLApi::GLES
-        } else {
-            GLApi::GL
+        self.gl_api
+    }
+
+    /// Returns this connection, switched to the given OpenGL API flavor.
+    ///
+    /// Devices created from the returned connection bind `EGL_OPENGL_ES_API` and choose
+    /// `EGL_OPENGL_ES3_BIT` (or `EGL_OPENGL_ES2_BIT`) configs when `gl_api` is `GLApi::GLES`.
+    /// If the display has no configs for `gl_api`, an `UnsupportedGLType` error is returned.
+    pub fn with_gl_api(mut self, gl_api: GLApi) -> Result<Connection, Error> {
+        let egl_display = self.native_connection.egl_display;
+        if !unsafe { context::display_supports_gl_api(egl_display, gl_api) } {
+            return Err(Error::UnsupportedGLType);
         }
+        self.gl_api = gl_api;
+        Ok(self)
     }
 
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
@@ -157,6 +173,7 @@ impl Connection {
                 egl_display,
                 wayland_display,
             }),
+            gl_api: default_gl_api(),
         })
     }
 
@@ -251,6 +268,7 @@ impl Drop for NativeConnectionWrapper {
     fn drop(&mut self) {
         unsafe {
             if let Some(wayland_display) = self.wayland_display {
+                generic::egl::device::forget_extensions(self.egl_display);
                 (wayland_client_handle().wl_display_disconnect)(wayland_display);
             }
         }
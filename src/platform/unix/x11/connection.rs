@@ -8,8 +8,11 @@ use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDisplay};
 use crate::error::Error;
 use crate::info::GLApi;
+use crate::platform::generic;
+use crate::platform::generic::egl::context;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::ffi::EGL_PLATFORM_X11_KHR;
+use crate::platform::unix::default_gl_api;
 use crate::platform::unix::generic::device::Adapter;
 
 use euclid::default::Size2D;
@@ -22,10 +25,13 @@ use x11_dl::xlib::{Display, Xlib};
 
 static X_THREADS_INIT: Once = Once::new();
 
+pub(crate) const BACKEND_NAME: &str = "x11";
+
 /// A connection to the X11 display server.
 #[derive(Clone)]
 pub struct Connection {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
+    pub(crate) gl_api: GLApi,
 }
 
 unsafe impl Send for Connection {}
@@ -55,6 +61,7 @@ impl Drop for NativeConnectionWrapper {
     fn drop(&mut self) {
         unsafe {
             if self.x11_display_is_owned {
+                generic::egl::device::forget_extensions(self.egl_display);
                 (self.xlib.XCloseDisplay)(self.x11_display);
             }
             self.x11_display = ptr::null_mut();
@@ -87,6 +94,7 @@ impl Connection {
                     x11_display_is_owned: true,
                     egl_display,
                 }),
+                gl_api: default_gl_api(),
             })
         }
     }
@@ -114,6 +122,7 @@ impl Connection {
                 x11_display: native_connection.x11_display,
                 x11_display_is_owned: false,
             }),
+            gl_api: default_gl_api(),
         })
     }
 
@@ -128,6 +137,7 @@ impl Connection {
                     x11_display,
                     x11_display_is_owned: is_owned,
                 }),
+                gl_api: default_gl_api(),
             })
         }
     }
@@ -144,7 +154,21 @@ impl Connection {
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Returns this connection, switched to the given OpenGL API flavor.
+    ///
+    /// Devices created from the returned connection bind `EGL_OPENGL_ES_API` and choose
+    /// `EGL_OPENGL_ES3_BIT` (or `EGL_OPENGL_ES2_BIT`) configs when `gl_api` is `GLApi::GLES`.
+    /// If the display has no configs for `gl_api`, an `UnsupportedGLType` error is returned.
+    pub fn with_gl_api(mut self, gl_api: GLApi) -> Result<Connection, Error> {
+        let egl_display = self.native_connection.egl_display;
+        if !unsafe { context::display_supports_gl_api(egl_display, gl_api) } {
+            return Err(Error::UnsupportedGLType);
+        }
+        self.gl_api = gl_api;
+        Ok(self)
     }
 
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
@@ -7,12 +7,14 @@ use super::surface::Surface;
 use crate::context::ContextID;
 use crate::egl;
 use crate::egl::types::EGLint;
-use crate::platform::generic::egl::context::{self, CurrentContextGuard, EGLBackedContext};
+use crate::platform::generic::egl::context::{self, EGLBackedContext};
 use crate::{ContextAttributes, Error, Gl, SurfaceInfo};
 
 use std::os::raw::c_void;
 
-pub use crate::platform::generic::egl::context::{ContextDescriptor, NativeContext};
+pub use crate::platform::generic::egl::context::{
+    ContextDescriptor, CurrentContextGuard, NativeContext,
+};
 
 /// Represents an OpenGL rendering context.
 ///
@@ -44,6 +46,7 @@ impl Device {
         // Set environment variables as appropriate.
         self.adapter.set_environment_variables();
 
+        let renderable_type = context::renderable_type_bit(self.gl_api(), attributes)?;
         unsafe {
             ContextDescriptor::new(
                 self.native_connection.egl_display,
@@ -52,12 +55,25 @@ impl Device {
                     egl::SURFACE_TYPE as EGLint,
                     egl::WINDOW_BIT as EGLint,
                     egl::RENDERABLE_TYPE as EGLint,
-                    egl::OPENGL_BIT as EGLint,
+                    renderable_type,
                 ],
             )
         }
     }
 
+    /// Creates a context descriptor for the first of the given candidate attributes that the
+    /// driver can create a context with.
+    ///
+    /// Each candidate is checked by creating and destroying a temporary context. Whatever context
+    /// was current on this thread beforehand remains current afterward.
+    pub fn create_context_descriptor_with_fallbacks(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor, Error> {
+        let _guard = CurrentContextGuard::new();
+        crate::context::negotiate_context_descriptor(self, candidates)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -148,8 +164,12 @@ impl Device {
         unsafe { context::make_no_context_current(self.native_connection.egl_display) }
     }
 
+    /// Makes the context current and returns a guard that, when dropped, restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    ///
+    /// This works even if the previously-current context was not created by `surfman`.
     #[inline]
-    pub(crate) fn temporarily_make_context_current(
+    pub fn temporarily_make_context_current(
         &self,
         context: &Context,
     ) -> Result<CurrentContextGuard, Error> {
@@ -158,6 +178,17 @@ impl Device {
         Ok(guard)
     }
 
+    /// Makes the context current for the duration of the given closure, then restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    #[inline]
+    pub fn with_context_current<F, R>(&self, context: &Context, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&Gl) -> R,
+    {
+        let _guard = self.temporarily_make_context_current(context)?;
+        Ok(f(&context.1))
+    }
+
     /// Returns the attributes that the context descriptor was created with.
     #[inline]
     pub fn context_descriptor_attributes(
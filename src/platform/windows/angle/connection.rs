@@ -22,6 +22,8 @@ use winapi::um::d3dcommon::{D3D_DRIVER_TYPE_UNKNOWN, D3D_DRIVER_TYPE_WARP};
 
 const INTEL_PCI_ID: UINT = 0x8086;
 
+pub(crate) const BACKEND_NAME: &str = "angle";
+
 /// A no-op connection.
 ///
 /// It might seem like this should wrap an `EGLDisplay`, but it doesn't. Unfortunately, in the
@@ -65,6 +67,16 @@ impl Connection {
         GLApi::GLES
     }
 
+    /// Returns this connection if `gl_api` is OpenGL ES, the only flavor this backend supports.
+    /// Otherwise, returns an `UnsupportedGLType` error.
+    #[inline]
+    pub fn with_gl_api(self, gl_api: GLApi) -> Result<Connection, Error> {
+        match gl_api {
+            GLApi::GLES => Ok(self),
+            GLApi::GL => Err(Error::UnsupportedGLType),
+        }
+    }
+
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
@@ -7,7 +7,7 @@ use super::surface::{Surface, Synchronization, Win32Objects};
 use crate::context::{ContextID, CREATE_CONTEXT_MUTEX};
 use crate::egl;
 use crate::egl::types::{EGLConfig, EGLContext, EGLint};
-use crate::platform::generic::egl::context::{self, CurrentContextGuard};
+use crate::platform::generic::egl::context;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::error::ToWindowingApiError;
 use crate::platform::generic::egl::surface::ExternalEGLSurfaces;
@@ -21,7 +21,9 @@ use std::thread;
 use winapi::shared::winerror::S_OK;
 use winapi::um::winbase::INFINITE;
 
-pub use crate::platform::generic::egl::context::{ContextDescriptor, NativeContext};
+pub use crate::platform::generic::egl::context::{
+    ContextDescriptor, CurrentContextGuard, NativeContext,
+};
 
 /// Represents an OpenGL rendering context.
 ///
@@ -81,6 +83,19 @@ impl Device {
         }
     }
 
+    /// Creates a context descriptor for the first of the given candidate attributes that the
+    /// driver can create a context with.
+    ///
+    /// Each candidate is checked by creating and destroying a temporary context. Whatever context
+    /// was current on this thread beforehand remains current afterward.
+    pub fn create_context_descriptor_with_fallbacks(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor, Error> {
+        let _guard = CurrentContextGuard::new();
+        crate::context::negotiate_context_descriptor(self, candidates)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -226,7 +241,11 @@ impl Device {
         unsafe { context::make_no_context_current(self.egl_display) }
     }
 
-    pub(crate) fn temporarily_make_context_current(
+    /// Makes the context current and returns a guard that, when dropped, restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    ///
+    /// This works even if the previously-current context was not created by `surfman`.
+    pub fn temporarily_make_context_current(
         &self,
         context: &Context,
     ) -> Result<CurrentContextGuard, Error> {
@@ -235,6 +254,17 @@ impl Device {
         Ok(guard)
     }
 
+    /// Makes the context current for the duration of the given closure, then restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    #[inline]
+    pub fn with_context_current<F, R>(&self, context: &Context, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&Gl) -> R,
+    {
+        let _guard = self.temporarily_make_context_current(context)?;
+        Ok(f(&context.gl))
+    }
+
     pub(crate) fn context_is_current(&self, context: &Context) -> bool {
         EGL_FUNCTIONS.with(|egl| unsafe { egl.GetCurrentContext() == context.egl_context })
     }
@@ -3,13 +3,15 @@
 //! A thread-local handle to the device.
 
 use super::connection::Connection;
+use crate::capabilities;
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDeviceEXT, EGLDisplay, EGLint};
-use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::context::CurrentContextGuard;
+use crate::platform::generic::egl::device::{self, EGL_FUNCTIONS};
 use crate::platform::generic::egl::ffi::EGL_DEVICE_EXT;
 use crate::platform::generic::egl::ffi::{EGL_D3D11_DEVICE_ANGLE, EGL_EXTENSION_FUNCTIONS};
 use crate::platform::generic::egl::ffi::{EGL_NO_DEVICE_EXT, EGL_PLATFORM_DEVICE_EXT};
-use crate::{Error, GLApi};
+use crate::{AdapterInfo, Capabilities, Error, GLApi, SurfaceFormat};
 
 use std::cell::{RefCell, RefMut};
 use std::mem;
@@ -323,6 +325,28 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GLES
     }
+
+    /// Returns what this device supports. See `Device::capabilities()`.
+    pub fn capabilities(&mut self) -> Result<Capabilities, Error> {
+        let _guard = CurrentContextGuard::new();
+        unsafe {
+            let extensions = device::query_extensions(self.egl_display);
+            let flags = device::capability_flags(&extensions);
+            // Generic surfaces are pbuffers in the context's config.
+            capabilities::probe(self, extensions, flags, |_, format| {
+                format == SurfaceFormat::RGBA8
+            })
+        }
+    }
+
+    /// Returns the GPU that this device renders with. See `Device::adapter_info()`.
+    pub fn adapter_info(&mut self) -> Result<AdapterInfo, Error> {
+        let _guard = CurrentContextGuard::new();
+        unsafe {
+            let is_software = device::device_is_software(self.egl_display);
+            capabilities::probe_adapter_info(self, is_software)
+        }
+    }
 }
 
 impl Drop for Device {
@@ -330,6 +354,7 @@ impl Drop for Device {
         unsafe {
             if self.display_is_owned {
                 EGL_FUNCTIONS.with(|egl| {
+                    device::forget_extensions(self.egl_display);
                     let result = egl.Terminate(self.egl_display);
                     assert_ne!(result, egl::FALSE);
                     self.egl_display = egl::NO_DISPLAY;
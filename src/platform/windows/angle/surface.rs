@@ -15,7 +15,9 @@ use crate::platform::generic::egl::ffi::EGL_D3D_TEXTURE_2D_SHARE_HANDLE_ANGLE;
 use crate::platform::generic::egl::ffi::EGL_D3D_TEXTURE_ANGLE;
 use crate::platform::generic::egl::ffi::EGL_DXGI_KEYED_MUTEX_ANGLE;
 use crate::platform::generic::egl::ffi::EGL_EXTENSION_FUNCTIONS;
-use crate::{Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType};
+use crate::{
+    Error, SurfaceAccess, SurfaceAttributes, SurfaceFormat, SurfaceID, SurfaceInfo, SurfaceType,
+};
 
 use euclid::default::Size2D;
 use glow::HasContext;
@@ -141,6 +143,30 @@ impl Device {
         }
     }
 
+    /// Creates a generic surface with the given color format.
+    ///
+    /// Generic surfaces on ANGLE are pbuffers, so only `RGBA8` is supported, and their depth and
+    /// stencil buffers always come from the context's config, regardless of
+    /// `surface_attributes.attachments`.
+    pub fn create_generic_surface_with_attributes(
+        &mut self,
+        context: &Context,
+        _: SurfaceAccess,
+        size: &Size2D<i32>,
+        surface_attributes: &SurfaceAttributes,
+    ) -> Result<Surface, Error> {
+        if surface_attributes.layers != 1 || surface_attributes.samples != 1 {
+            return Err(Error::UnsupportedOnThisPlatform);
+        }
+        match surface_attributes.format {
+            SurfaceFormat::RGBA8 => self.create_pbuffer_surface(context, size, None),
+            SurfaceFormat::RGBA16F
+            | SurfaceFormat::RGB10A2
+            | SurfaceFormat::R8
+            | SurfaceFormat::BGRA8 => Err(Error::UnsupportedSurfaceFormat),
+        }
+    }
+
     #[allow(non_snake_case)]
     fn create_pbuffer_surface(
         &mut self,
@@ -568,6 +594,9 @@ impl Device {
             id: surface.id(),
             context_id: surface.context_id,
             framebuffer_object: None,
+            format: SurfaceFormat::RGBA8,
+            layers: 1,
+            layer_framebuffer_objects: vec![],
         }
     }
 
@@ -581,6 +610,13 @@ impl Device {
     ) -> Option<glow::Texture> {
         surface_texture.gl_texture
     }
+
+    /// Returns the OpenGL texture target that the texture object of this surface texture must be
+    /// bound to, which is always `surface_gl_texture_target()` here.
+    #[inline]
+    pub fn surface_texture_target(&self, _: &SurfaceTexture) -> u32 {
+        self.surface_gl_texture_target()
+    }
 }
 
 impl Surface {
@@ -15,6 +15,8 @@ use std::os::raw::c_void;
 
 use winapi::shared::windef::HWND;
 
+pub(crate) const BACKEND_NAME: &str = "wgl";
+
 /// Represents a connection to the display server.
 ///
 /// Window server connections are implicit in the Win32 API, so this is a zero-sized type.
@@ -52,6 +54,16 @@ impl Connection {
         GLApi::GL
     }
 
+    /// Returns this connection if `gl_api` is OpenGL, the only flavor this backend supports.
+    /// Otherwise, returns an `UnsupportedGLType` error.
+    #[inline]
+    pub fn with_gl_api(self, gl_api: GLApi) -> Result<Connection, Error> {
+        match gl_api {
+            GLApi::GL => Ok(self),
+            GLApi::GLES => Err(Error::UnsupportedGLType),
+        }
+    }
+
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
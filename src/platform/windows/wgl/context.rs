@@ -59,6 +59,7 @@ pub(crate) struct WGLExtensionFunctions {
         unsafe extern "C" fn(hDC: HDC, shareContext: HGLRC, attribList: *const c_int) -> HGLRC,
     >,
     GetExtensionsStringARB: Option<unsafe extern "C" fn(hdc: HDC) -> *const c_char>,
+    pub(crate) extensions: Vec<String>,
     pub(crate) pixel_format_functions: Option<WGLPixelFormatExtensionFunctions>,
     pub(crate) dx_interop_functions: Option<WGLDXInteropExtensionFunctions>,
 }
@@ -240,6 +241,19 @@ impl Device {
         }
     }
 
+    /// Creates a context descriptor for the first of the given candidate attributes that the
+    /// driver can create a context with.
+    ///
+    /// Each candidate is checked by creating and destroying a temporary context. Whatever context
+    /// was current on this thread beforehand remains current afterward.
+    pub fn create_context_descriptor_with_fallbacks(
+        &mut self,
+        candidates: &[ContextAttributes],
+    ) -> Result<ContextDescriptor, Error> {
+        let _guard = CurrentContextGuard::new();
+        context::negotiate_context_descriptor(self, candidates)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -465,7 +479,11 @@ impl Device {
         }
     }
 
-    pub(crate) fn temporarily_make_context_current(
+    /// Makes the context current and returns a guard that, when dropped, restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    ///
+    /// This works even if the previously-current context was not created by `surfman`.
+    pub fn temporarily_make_context_current(
         &self,
         context: &Context,
     ) -> Result<CurrentContextGuard, Error> {
@@ -474,6 +492,17 @@ impl Device {
         Ok(guard)
     }
 
+    /// Makes the context current for the duration of the given closure, then restores whatever
+    /// context and surfaces were current on this thread beforehand.
+    #[inline]
+    pub fn with_context_current<F, R>(&self, context: &Context, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&Gl) -> R,
+    {
+        let _guard = self.temporarily_make_context_current(context)?;
+        Ok(f(&context.gl))
+    }
+
     /// Makes the context the current OpenGL context for this thread.
     ///
     /// After calling this function, it is valid to use OpenGL rendering commands.
@@ -748,6 +777,12 @@ extern "system" fn extension_loader_window_proc(
                     None => Cow::Borrowed(""),
                 };
 
+                (*wgl_extension_functions).extensions = extensions
+                    .split(' ')
+                    .filter(|extension| !extension.is_empty())
+                    .map(|extension| extension.to_owned())
+                    .collect();
+
                 // Load function pointers.
                 for extension in extensions.split(' ') {
                     if extension == "WGL_ARB_pixel_format" {
@@ -845,8 +880,11 @@ impl<'a> FramebufferGuard<'a> {
     }
 }
 
+/// Restores the previously-current WGL context and device context when dropped.
+///
+/// This works even if the previous context was not created by `surfman`.
 #[must_use]
-pub(crate) struct CurrentContextGuard {
+pub struct CurrentContextGuard {
     old_dc: HDC,
     old_glrc: HGLRC,
 }
@@ -862,7 +900,7 @@ impl Drop for CurrentContextGuard {
 
 impl CurrentContextGuard {
     #[inline]
-    fn new() -> CurrentContextGuard {
+    pub(crate) fn new() -> CurrentContextGuard {
         unsafe {
             CurrentContextGuard {
                 old_dc: wglGetCurrentDC(),
@@ -3,8 +3,9 @@
 //! An implementation of the GPU device for Windows using the WGL API.
 
 use super::connection::Connection;
-use super::context::WGL_EXTENSION_FUNCTIONS;
-use crate::{Error, GLApi};
+use super::context::{CurrentContextGuard, WGL_EXTENSION_FUNCTIONS};
+use crate::capabilities;
+use crate::{AdapterInfo, Capabilities, CapabilityFlags, Error, GLApi};
 
 use std::ffi::CStr;
 use std::marker::PhantomData;
@@ -236,6 +237,26 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GL
     }
+
+    /// Returns what this device supports. See `Device::capabilities()`.
+    pub fn capabilities(&mut self) -> Result<Capabilities, Error> {
+        let _guard = CurrentContextGuard::new();
+        let extensions = WGL_EXTENSION_FUNCTIONS.extensions.clone();
+        let mut flags = CapabilityFlags::empty();
+        if extensions
+            .iter()
+            .any(|extension| extension == "WGL_ARB_create_context_robustness")
+        {
+            flags.insert(CapabilityFlags::ROBUSTNESS);
+        }
+        capabilities::probe(self, extensions, flags, |_, _| true)
+    }
+
+    /// Returns the GPU that this device renders with. See `Device::adapter_info()`.
+    pub fn adapter_info(&mut self) -> Result<AdapterInfo, Error> {
+        let _guard = CurrentContextGuard::new();
+        capabilities::probe_adapter_info(self, false)
+    }
 }
 
 impl Adapter {
@@ -6,7 +6,8 @@ use super::context::{self, Context, WGL_EXTENSION_FUNCTIONS};
 use super::device::Device;
 use crate::error::WindowingApiError;
 use crate::renderbuffers::Renderbuffers;
-use crate::{ContextID, Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType};
+use crate::{ContextID, Error, SurfaceAccess, SurfaceAttributes, SurfaceFormat, SurfaceID};
+use crate::{SurfaceInfo, SurfaceType};
 
 use crate::gl;
 type GLenum = c_uint;
@@ -22,7 +23,9 @@ use std::os::raw::c_void;
 use std::ptr;
 use std::thread;
 use winapi::shared::dxgi::IDXGIResource;
-use winapi::shared::dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
+use winapi::shared::dxgiformat::DXGI_FORMAT_R8_UNORM;
+use winapi::shared::dxgiformat::{DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_R10G10B10A2_UNORM};
+use winapi::shared::dxgiformat::{DXGI_FORMAT_R16G16B16A16_FLOAT, DXGI_FORMAT_R8G8B8A8_UNORM};
 use winapi::shared::dxgitype::DXGI_SAMPLE_DESC;
 use winapi::shared::minwindef::{FALSE, UINT};
 use winapi::shared::ntdef::HANDLE;
@@ -60,6 +63,7 @@ const WGL_ACCESS_READ_WRITE_NV: GLenum = 0x0001;
 /// Surfaces must be destroyed with the `destroy_surface()` method, or a panic will occur.
 pub struct Surface {
     pub(crate) size: Size2D<i32>,
+    pub(crate) format: SurfaceFormat,
     pub(crate) context_id: ContextID,
     pub(crate) win32_objects: Win32Objects,
     pub(crate) destroyed: bool,
@@ -135,22 +139,39 @@ impl Device {
     pub fn create_surface(
         &mut self,
         context: &Context,
-        _: SurfaceAccess,
+        access: SurfaceAccess,
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
         match surface_type {
-            SurfaceType::Generic { size } => self.create_generic_surface(context, &size),
+            SurfaceType::Generic { size } => {
+                let context_descriptor = self.context_descriptor(context);
+                let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+                let surface_attributes = SurfaceAttributes::for_context(&context_attributes);
+                self.create_generic_surface_with_attributes(
+                    context,
+                    access,
+                    &size,
+                    &surface_attributes,
+                )
+            }
             SurfaceType::Widget { native_widget } => {
                 self.create_widget_surface(context, native_widget)
             }
         }
     }
 
-    fn create_generic_surface(
+    /// Creates a generic surface whose color format and depth/stencil buffers are given by
+    /// `surface_attributes` rather than by the attributes of the context.
+    pub fn create_generic_surface_with_attributes(
         &mut self,
         context: &Context,
+        _: SurfaceAccess,
         size: &Size2D<i32>,
+        surface_attributes: &SurfaceAttributes,
     ) -> Result<Surface, Error> {
+        if surface_attributes.layers != 1 || surface_attributes.samples != 1 {
+            return Err(Error::UnsupportedOnThisPlatform);
+        }
         let dx_interop_functions = match WGL_EXTENSION_FUNCTIONS.dx_interop_functions {
             None => return Err(Error::RequiredExtensionUnavailable),
             Some(ref dx_interop_functions) => dx_interop_functions,
@@ -165,7 +186,13 @@ impl Device {
                 Height: size.height as UINT,
                 MipLevels: 1,
                 ArraySize: 1,
-                Format: DXGI_FORMAT_R8G8B8A8_UNORM,
+                Format: match surface_attributes.format {
+                    SurfaceFormat::RGBA8 => DXGI_FORMAT_R8G8B8A8_UNORM,
+                    SurfaceFormat::RGBA16F => DXGI_FORMAT_R16G16B16A16_FLOAT,
+                    SurfaceFormat::RGB10A2 => DXGI_FORMAT_R10G10B10A2_UNORM,
+                    SurfaceFormat::R8 => DXGI_FORMAT_R8_UNORM,
+                    SurfaceFormat::BGRA8 => DXGI_FORMAT_B8G8R8A8_UNORM,
+                },
                 SampleDesc: DXGI_SAMPLE_DESC {
                     Count: 1,
                     Quality: 0,
@@ -246,16 +273,28 @@ impl Device {
             );
 
             // Create renderbuffers as appropriate, and attach them.
-            let context_descriptor = self.context_descriptor(context);
-            let context_attributes = self.context_descriptor_attributes(&context_descriptor);
-            let renderbuffers = Renderbuffers::new(&context.gl, &size, &context_attributes);
+            let mut renderbuffers =
+                Renderbuffers::new(&context.gl, size, surface_attributes.attachments);
             renderbuffers.bind_to_current_framebuffer(&context.gl);
 
+            if context.gl.check_framebuffer_status(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                renderbuffers.destroy(&context.gl);
+                gl_utils::destroy_framebuffer(&context.gl, gl_framebuffer);
+                context.gl.delete_texture(gl_texture);
+                let ok = (dx_interop_functions.DXUnregisterObjectNV)(
+                    self.gl_dx_interop_device,
+                    gl_dx_interop_object,
+                );
+                assert_ne!(ok, FALSE);
+                return Err(Error::SurfaceCreationFailed(WindowingApiError::Failed));
+            }
+
             // FIXME(pcwalton): Do we need to acquire the keyed mutex, or does the GL driver do
             // that?
 
             Ok(Surface {
                 size: *size,
+                format: surface_attributes.format,
                 context_id: context.id,
                 win32_objects: Win32Objects::Texture {
                     d3d11_texture,
@@ -296,6 +335,7 @@ impl Device {
                     widget_rect.right - widget_rect.left,
                     widget_rect.bottom - widget_rect.top,
                 ),
+                format: SurfaceFormat::RGBA8,
                 context_id: context.id,
                 win32_objects: Win32Objects::Widget {
                     window_handle: native_widget.window_handle,
@@ -628,14 +668,18 @@ impl Device {
     /// 0, the default framebuffer, depending on platform.
     #[inline]
     pub fn surface_info(&self, surface: &Surface) -> SurfaceInfo {
+        let framebuffer_object = match surface.win32_objects {
+            Win32Objects::Texture { gl_framebuffer, .. } => gl_framebuffer,
+            Win32Objects::Widget { .. } => None,
+        };
         SurfaceInfo {
             size: surface.size,
             id: surface.id(),
             context_id: surface.context_id,
-            framebuffer_object: match surface.win32_objects {
-                Win32Objects::Texture { gl_framebuffer, .. } => gl_framebuffer,
-                Win32Objects::Widget { .. } => None,
-            },
+            framebuffer_object,
+            format: surface.format,
+            layers: 1,
+            layer_framebuffer_objects: framebuffer_object.into_iter().collect(),
         }
     }
 
@@ -649,6 +693,13 @@ impl Device {
     ) -> Option<glow::Texture> {
         surface_texture.gl_texture
     }
+
+    /// Returns the OpenGL texture target that the texture object of this surface texture must be
+    /// bound to, which is always `surface_gl_texture_target()` here.
+    #[inline]
+    pub fn surface_texture_target(&self, _: &SurfaceTexture) -> u32 {
+        self.surface_gl_texture_target()
+    }
 }
 
 impl Surface {
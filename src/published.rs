@@ -0,0 +1,201 @@
+// surfman/surfman/src/published.rs
+//
+//! Surfaces that many contexts can sample at once.
+//!
+//! `Device::create_surface_texture()` takes ownership of the surface, so only one context at a
+//! time can read a given surface. Publishing a surface freezes it instead: nothing can render to
+//! it any more, but any number of contexts, on any threads, can create read-only surface textures
+//! from it at the same time. Once every surface texture has been destroyed, the surface can be
+//! reclaimed, for example to render the next frame into it or to destroy it.
+
+use crate::device::Device as DeviceAPI;
+use crate::{Error, SurfaceInfo};
+
+use log::warn;
+use std::mem::{self, ManuallyDrop};
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A generic surface that has been frozen so that several contexts can sample it at once.
+///
+/// Handles are cheap to clone and can be sent to other threads.
+///
+/// The surface should be taken back with `reclaim()` and destroyed with `Device::destroy_surface()`
+/// as usual. If the last handle is dropped instead, the surface is leaked, and a warning is
+/// logged, because destroying it needs a device and context.
+///
+/// On ANGLE, surfaces that are shared through a keyed mutex can only have one surface texture at a
+/// time: creating a second one blocks until the first has been destroyed.
+pub struct PublishedSurface<Device: DeviceAPI>(Arc<PublishedSurfaceData<Device>>);
+
+/// A read-only surface texture created from a published surface.
+///
+/// This must be destroyed with `PublishedSurface::destroy_surface_texture()`. If it is dropped
+/// instead, its OpenGL texture is leaked, and the published surface can never be reclaimed.
+pub struct PublishedSurfaceTexture<Device: DeviceAPI> {
+    // Wraps a bitwise copy of the published surface, which must never be dropped.
+    surface_texture: ManuallyDrop<Device::SurfaceTexture>,
+    published: Arc<PublishedSurfaceData<Device>>,
+}
+
+struct PublishedSurfaceData<Device: DeviceAPI> {
+    // This is only dropped by `reclaim()`, since most backends panic when an undestroyed surface
+    // is dropped.
+    surface: ManuallyDrop<Mutex<Device::Surface>>,
+    // The number of surface textures that haven't been destroyed yet.
+    surface_texture_count: AtomicUsize,
+}
+
+// We can't derive Clone unfortunately
+impl<Device: DeviceAPI> Clone for PublishedSurface<Device> {
+    fn clone(&self) -> Self {
+        PublishedSurface(self.0.clone())
+    }
+}
+
+impl<Device: DeviceAPI> PublishedSurface<Device> {
+    /// Publishes a generic surface.
+    ///
+    /// The surface must not be bound to a context. Widget surfaces can be published, but surface
+    /// textures can't be created from them.
+    pub fn new(surface: Device::Surface) -> PublishedSurface<Device> {
+        PublishedSurface(Arc::new(PublishedSurfaceData {
+            surface: ManuallyDrop::new(Mutex::new(surface)),
+            surface_texture_count: AtomicUsize::new(0),
+        }))
+    }
+
+    fn lock(&self) -> MutexGuard<Device::Surface> {
+        self.0.surface.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Returns various information about the published surface.
+    pub fn info(&self, device: &Device) -> SurfaceInfo {
+        device.surface_info(&self.lock())
+    }
+
+    // The number of handles to the published surface, including those held by surface textures.
+    #[cfg(feature = "chains")]
+    pub(crate) fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.0)
+    }
+
+    // Are these handles to the same published surface?
+    #[cfg(feature = "chains")]
+    pub(crate) fn ptr_eq(&self, other: &PublishedSurface<Device>) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns the number of surface textures created from this surface that haven't been
+    /// destroyed yet.
+    pub fn surface_texture_count(&self) -> usize {
+        self.0.surface_texture_count.load(Ordering::Acquire)
+    }
+
+    /// Creates a read-only surface texture from the published surface for use with the given
+    /// context.
+    ///
+    /// As with `Device::create_surface_texture()`, the context doesn't have to be the one that
+    /// created the surface. Any number of surface textures can exist at once.
+    pub fn create_surface_texture(
+        &self,
+        device: &Device,
+        context: &mut Device::Context,
+    ) -> Result<PublishedSurfaceTexture<Device>, Error> {
+        let surface = self.lock();
+        // SAFETY: Surface textures only ever read from their surface (see
+        // `Device::create_surface_texture()`), so they can share one:
+        //
+        // * The generic EGL backends bind the surface's EGL images to new textures, or copy its
+        //   pbuffer or layers into one, and `destroy_surface_texture()` deletes only that texture.
+        // * Android and OpenHarmony create a local EGL image from the hardware buffer, or bind the
+        //   surface's own image, and destroy only the local texture and image.
+        // * macOS binds the IOSurface to a new texture and destroys only that texture.
+        // * WGL opens a local D3D11 texture from the share handle and registers it for DX
+        //   interop, and unregisters and releases only that texture.
+        // * ANGLE creates a local pbuffer from the share handle and destroys only that pbuffer.
+        //
+        // The copies are forgotten rather than dropped, so the COM and Core Foundation references
+        // and heap allocations they share with the original are never released twice, and the
+        // original outlives them all.
+        let alias = unsafe { ptr::read(&*surface) };
+        match device.create_surface_texture(context, alias) {
+            Ok(surface_texture) => {
+                self.0.surface_texture_count.fetch_add(1, Ordering::AcqRel);
+                Ok(PublishedSurfaceTexture {
+                    surface_texture: ManuallyDrop::new(surface_texture),
+                    published: self.0.clone(),
+                })
+            }
+            Err((err, alias)) => {
+                mem::forget(alias);
+                Err(err)
+            }
+        }
+    }
+
+    /// Destroys a surface texture created from this published surface.
+    ///
+    /// The supplied context must be the one the surface texture was created with. Returns an
+    /// `IncompatibleSurfaceTexture` error if the surface texture came from a different published
+    /// surface.
+    pub fn destroy_surface_texture(
+        &self,
+        device: &Device,
+        context: &mut Device::Context,
+        mut surface_texture: PublishedSurfaceTexture<Device>,
+    ) -> Result<(), (Error, PublishedSurfaceTexture<Device>)> {
+        if !Arc::ptr_eq(&self.0, &surface_texture.published) {
+            return Err((Error::IncompatibleSurfaceTexture, surface_texture));
+        }
+
+        let inner = unsafe { ManuallyDrop::take(&mut surface_texture.surface_texture) };
+        match device.destroy_surface_texture(context, inner) {
+            Ok(alias) => {
+                mem::forget(alias);
+                self.0.surface_texture_count.fetch_sub(1, Ordering::AcqRel);
+                Ok(())
+            }
+            Err((err, inner)) => {
+                surface_texture.surface_texture = ManuallyDrop::new(inner);
+                Err((err, surface_texture))
+            }
+        }
+    }
+
+    /// Takes the surface back so that it can be rendered to or destroyed again.
+    ///
+    /// This only succeeds once every surface texture has been destroyed and every other handle to
+    /// the published surface has been dropped. Otherwise, the handle is returned unchanged.
+    pub fn reclaim(self) -> Result<Device::Surface, PublishedSurface<Device>> {
+        if self.surface_texture_count() != 0 {
+            return Err(self);
+        }
+        match Arc::try_unwrap(self.0) {
+            Ok(data) => {
+                let mut data = ManuallyDrop::new(data);
+                let surface = unsafe { ManuallyDrop::take(&mut data.surface) };
+                Ok(surface.into_inner().unwrap_or_else(|err| err.into_inner()))
+            }
+            Err(data) => Err(PublishedSurface(data)),
+        }
+    }
+}
+
+impl<Device: DeviceAPI> Drop for PublishedSurfaceData<Device> {
+    fn drop(&mut self) {
+        warn!("Leaking a published surface that was dropped without being reclaimed");
+    }
+}
+
+impl<Device: DeviceAPI> PublishedSurfaceTexture<Device> {
+    /// Returns the underlying surface texture, e.g. to pass to
+    /// `Device::surface_texture_object()`.
+    ///
+    /// The texture must only be read from.
+    #[inline]
+    pub fn surface_texture(&self) -> &Device::SurfaceTexture {
+        &self.surface_texture
+    }
+}
@@ -2,8 +2,8 @@
 //
 //! A utility module for backends that wrap surfaces in OpenGL FBOs.
 
-use crate::context::{ContextAttributeFlags, ContextAttributes};
 use crate::gl;
+use crate::surface::SurfaceAttachmentFlags;
 use crate::Gl;
 use std::thread;
 
@@ -40,45 +40,44 @@ impl Renderbuffers {
     pub(crate) fn new(
         gl: &Gl,
         size: &Size2D<i32>,
-        attributes: &ContextAttributes,
+        attachments: SurfaceAttachmentFlags,
+    ) -> Renderbuffers {
+        Renderbuffers::new_multisample(gl, size, attachments, 0)
+    }
+
+    // Creates renderbuffers with the given number of samples per pixel, for attaching to a
+    // framebuffer with a multisampled color buffer. Fewer than two samples means no multisampling.
+    pub(crate) fn new_multisample(
+        gl: &Gl,
+        size: &Size2D<i32>,
+        attachments: SurfaceAttachmentFlags,
+        samples: u32,
     ) -> Renderbuffers {
         unsafe {
-            if attributes
-                .flags
-                .contains(ContextAttributeFlags::DEPTH | ContextAttributeFlags::STENCIL)
-            {
-                let renderbuffer = gl.create_renderbuffer().unwrap();
-                gl.bind_renderbuffer(gl::RENDERBUFFER, Some(renderbuffer));
-                gl.renderbuffer_storage(
-                    gl::RENDERBUFFER,
-                    gl::DEPTH24_STENCIL8,
-                    size.width,
-                    size.height,
-                );
-                gl.bind_renderbuffer(gl::RENDERBUFFER, None);
-                return Renderbuffers::CombinedDepthStencil(Some(renderbuffer));
+            let formats = RenderbufferFormats::current(gl);
+
+            if let Some(depth_stencil_format) = formats.depth_stencil {
+                if attachments
+                    .contains(SurfaceAttachmentFlags::DEPTH | SurfaceAttachmentFlags::STENCIL)
+                {
+                    let renderbuffer = gl.create_renderbuffer().unwrap();
+                    gl.bind_renderbuffer(gl::RENDERBUFFER, Some(renderbuffer));
+                    allocate_storage(gl, depth_stencil_format, size, samples);
+                    gl.bind_renderbuffer(gl::RENDERBUFFER, None);
+                    return Renderbuffers::CombinedDepthStencil(Some(renderbuffer));
+                }
             }
 
             let (mut depth_renderbuffer, mut stencil_renderbuffer) = (None, None);
-            if attributes.flags.contains(ContextAttributeFlags::DEPTH) {
+            if attachments.contains(SurfaceAttachmentFlags::DEPTH) {
                 depth_renderbuffer = Some(gl.create_renderbuffer().unwrap());
                 gl.bind_renderbuffer(gl::RENDERBUFFER, depth_renderbuffer);
-                gl.renderbuffer_storage(
-                    gl::RENDERBUFFER,
-                    gl::DEPTH_COMPONENT24,
-                    size.width,
-                    size.height,
-                );
+                allocate_storage(gl, formats.depth, size, samples);
             }
-            if attributes.flags.contains(ContextAttributeFlags::STENCIL) {
+            if attachments.contains(SurfaceAttachmentFlags::STENCIL) {
                 stencil_renderbuffer = Some(gl.create_renderbuffer().unwrap());
                 gl.bind_renderbuffer(gl::RENDERBUFFER, stencil_renderbuffer);
-                gl.renderbuffer_storage(
-                    gl::RENDERBUFFER,
-                    gl::STENCIL_INDEX8,
-                    size.width,
-                    size.height,
-                );
+                allocate_storage(gl, gl::STENCIL_INDEX8, size, samples);
             }
             gl.bind_renderbuffer(gl::RENDERBUFFER, None);
 
@@ -89,6 +88,23 @@ impl Renderbuffers {
         }
     }
 
+    // The attachments that these renderbuffers provide, for recreating them at a new size.
+    #[cfg(macos_platform)]
+    pub(crate) fn attachments(&self) -> SurfaceAttachmentFlags {
+        match *self {
+            Renderbuffers::CombinedDepthStencil(Some(_)) => {
+                SurfaceAttachmentFlags::DEPTH | SurfaceAttachmentFlags::STENCIL
+            }
+            Renderbuffers::CombinedDepthStencil(None) => SurfaceAttachmentFlags::empty(),
+            Renderbuffers::IndividualDepthStencil { depth, stencil } => {
+                let mut attachments = SurfaceAttachmentFlags::empty();
+                attachments.set(SurfaceAttachmentFlags::DEPTH, depth.is_some());
+                attachments.set(SurfaceAttachmentFlags::STENCIL, stencil.is_some());
+                attachments
+            }
+        }
+    }
+
     pub(crate) fn bind_to_current_framebuffer(&self, gl: &Gl) {
         unsafe {
             match *self {
@@ -152,3 +168,58 @@ impl Renderbuffers {
         }
     }
 }
+
+// Allocates storage for the renderbuffer bound to `GL_RENDERBUFFER`, multisampled if `samples` is
+// at least 2. Multisampled storage needs OpenGL 3.0 or OpenGL ES 3.0.
+pub(crate) unsafe fn allocate_storage(
+    gl: &Gl,
+    internal_format: u32,
+    size: &Size2D<i32>,
+    samples: u32,
+) {
+    if samples > 1 {
+        gl.renderbuffer_storage_multisample(
+            gl::RENDERBUFFER,
+            samples as i32,
+            internal_format,
+            size.width,
+            size.height,
+        );
+    } else {
+        gl.renderbuffer_storage(gl::RENDERBUFFER, internal_format, size.width, size.height);
+    }
+}
+
+// The renderbuffer formats that the current context supports. OpenGL ES 2.0 only guarantees 16-bit
+// depth, and packed depth/stencil needs `OES_packed_depth_stencil`. 8-bit stencil is available
+// everywhere.
+struct RenderbufferFormats {
+    depth: u32,
+    depth_stencil: Option<u32>,
+}
+
+impl RenderbufferFormats {
+    fn current(gl: &Gl) -> RenderbufferFormats {
+        let version = gl.version();
+        if !version.is_embedded || version.major >= 3 {
+            return RenderbufferFormats {
+                depth: gl::DEPTH_COMPONENT24,
+                depth_stencil: Some(gl::DEPTH24_STENCIL8),
+            };
+        }
+
+        let extensions = gl.supported_extensions();
+        RenderbufferFormats {
+            depth: if extensions.contains("GL_OES_depth24") {
+                gl::DEPTH_COMPONENT24
+            } else {
+                gl::DEPTH_COMPONENT16
+            },
+            depth_stencil: if extensions.contains("GL_OES_packed_depth_stencil") {
+                Some(gl::DEPTH24_STENCIL8)
+            } else {
+                None
+            },
+        }
+    }
+}
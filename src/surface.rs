@@ -2,7 +2,7 @@
 //
 //! Information related to hardware surfaces.
 
-use crate::context::ContextID;
+use crate::context::{ContextAttributeFlags, ContextAttributes, ContextID};
 
 use euclid::default::Size2D;
 use std::fmt::{self, Display, Formatter};
@@ -27,6 +27,15 @@ pub struct SurfaceInfo {
     ///
     /// This is only valid when the surface is actually attached to a context.
     pub framebuffer_object: Option<glow::Framebuffer>,
+    /// The format of the surface's color buffer.
+    pub format: SurfaceFormat,
+    /// The number of layers in the surface's color buffer.
+    pub layers: u32,
+    /// The OpenGL framebuffer objects that render to each layer of this surface, in layer order.
+    ///
+    /// The first entry is the same as `framebuffer_object`. This is empty if the surface has no
+    /// framebuffer object.
+    pub layer_framebuffer_objects: Vec<glow::Framebuffer>,
 }
 
 // The default framebuffer for a context.
@@ -115,6 +124,107 @@ pub enum SurfaceType<NativeWidget> {
     },
 }
 
+/// The pixel format of a generic surface's color buffer.
+///
+/// Which formats can be rendered to depends on the device; see `Capabilities::surface_formats`.
+/// Requesting any other format fails with `UnsupportedSurfaceFormat`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SurfaceFormat {
+    /// 8 bits per channel of red, green, blue, and alpha.
+    RGBA8,
+    /// 16-bit floating point red, green, blue, and alpha channels.
+    RGBA16F,
+    /// 10 bits each of red, green, and blue, and 2 bits of alpha.
+    RGB10A2,
+    /// A single 8-bit red channel.
+    R8,
+    /// 8 bits per channel, stored in blue, green, red, alpha order in memory.
+    ///
+    /// Shaders see this as RGBA, like `RGBA8`. It's mainly useful for sharing with APIs that
+    /// expect BGRA memory.
+    BGRA8,
+}
+
+impl SurfaceFormat {
+    /// Every surface format, in declaration order.
+    pub const ALL: [SurfaceFormat; 5] = [
+        SurfaceFormat::RGBA8,
+        SurfaceFormat::RGBA16F,
+        SurfaceFormat::RGB10A2,
+        SurfaceFormat::R8,
+        SurfaceFormat::BGRA8,
+    ];
+}
+
+bitflags! {
+    /// The buffers attached to a generic surface alongside its color buffer.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub struct SurfaceAttachmentFlags: u8 {
+        /// The surface has a depth buffer, at least 16 bits and 24 bits where available.
+        const DEPTH   = 0x01;
+        /// The surface has an 8-bit stencil buffer, packed with the depth buffer if possible.
+        const STENCIL = 0x02;
+    }
+}
+
+/// Describes the buffers that make up a generic surface, independently of the attributes of the
+/// context that renders to it.
+///
+/// Pass this to `Device::create_generic_surface_with_attributes()`. For example, a context that
+/// needs depth for its main surface can still create color-only intermediate surfaces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SurfaceAttributes {
+    /// The format of the color buffer.
+    pub format: SurfaceFormat,
+    /// Which depth and stencil buffers to attach.
+    pub attachments: SurfaceAttachmentFlags,
+    /// The number of layers, e.g. two for stereo rendering. Must be at least 1.
+    ///
+    /// A surface with more than one layer renders to a `GL_TEXTURE_2D_ARRAY` texture, and each
+    /// layer gets its own framebuffer object and depth/stencil buffers; see
+    /// `SurfaceInfo::layer_framebuffer_objects`. Surface textures of such a surface are that
+    /// texture array itself rather than a copy, so they can only be created for contexts that
+    /// share objects with the surface's context; other contexts get an
+    /// `IncompatibleSharedContext` error. Only the generic EGL backends support multiple layers so
+    /// far; elsewhere, creation fails with `UnsupportedOnThisPlatform`.
+    pub layers: u32,
+    /// The number of samples per pixel for multisample antialiasing, or 1 for none. Must be at
+    /// least 1.
+    ///
+    /// A multisampled surface is rendered to through its framebuffer objects as usual, and is
+    /// resolved into a single-sampled color buffer when it is unbound from its context, so surface
+    /// textures only ever see the resolved image. Multisampling needs OpenGL 3.0 or OpenGL ES 3.0
+    /// and is only supported by the generic EGL backends so far; elsewhere, creation fails with
+    /// `UnsupportedOnThisPlatform`.
+    pub samples: u32,
+}
+
+impl SurfaceAttributes {
+    /// Returns the attributes that `Device::create_surface()` uses for generic surfaces of a
+    /// context with the given attributes.
+    pub fn for_context(context_attributes: &ContextAttributes) -> SurfaceAttributes {
+        let mut attachments = SurfaceAttachmentFlags::empty();
+        if context_attributes
+            .flags
+            .contains(ContextAttributeFlags::DEPTH)
+        {
+            attachments.insert(SurfaceAttachmentFlags::DEPTH);
+        }
+        if context_attributes
+            .flags
+            .contains(ContextAttributeFlags::STENCIL)
+        {
+            attachments.insert(SurfaceAttachmentFlags::STENCIL);
+        }
+        SurfaceAttributes {
+            format: SurfaceFormat::RGBA8,
+            attachments,
+            layers: 1,
+            samples: 1,
+        }
+    }
+}
+
 impl SurfaceAccess {
     #[allow(dead_code)]
     #[inline]
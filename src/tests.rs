@@ -12,16 +12,44 @@ use super::connection::Connection;
 use super::context::{Context, ContextDescriptor, NativeContext};
 use super::device::{Adapter, Device};
 use super::surface::Surface;
+#[cfg(feature = "chains")]
+use crate::chains::{BroadcastSwapChain, FrameDelivery, PreserveBuffer, PreserveOptions};
+#[cfg(feature = "chains")]
+use crate::chains::{QueueFull, QueueingMode};
+#[cfg(feature = "chains")]
+use crate::chains::{SwapChain, SwapChainAPI, SwapChains, SwapChainsAPI};
+use crate::connection::Connection as ConnectionInterface;
 use crate::gl;
+use crate::owned::OwnedContext;
+#[cfg(free_unix)]
+use crate::platform::generic::egl::surface::ForcePbufferSurfacesGuard;
+use crate::published::PublishedSurface;
+use crate::WindowingApiError;
 use crate::{ContextAttributeFlags, ContextAttributes, Error, GLApi, GLVersion, Gl, SurfaceAccess};
-use crate::{SurfaceType, WindowingApiError};
+use crate::{SurfaceAttachmentFlags, SurfaceAttributes, SurfaceFormat, SurfaceType};
 
 use euclid::default::Size2D;
-use glow::{Framebuffer, HasContext, PixelPackData, Texture};
+#[cfg(feature = "chains")]
+use euclid::default::{Point2D, Rect};
+use glow::{Framebuffer, HasContext, PixelPackData, PixelUnpackData, Texture};
 #[cfg(not(feature = "sm-test"))]
 use serial_test::serial;
+use std::cell::RefCell;
+#[cfg(feature = "chains")]
+use std::future::Future;
+#[cfg(feature = "chains")]
+use std::pin::Pin;
+use std::rc::Rc;
+#[cfg(feature = "chains")]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc;
+#[cfg(feature = "chains")]
+use std::sync::Arc;
+#[cfg(feature = "chains")]
+use std::task::{Context as TaskContext, Poll, Wake, Waker};
 use std::thread;
+#[cfg(feature = "chains")]
+use std::time::Duration;
 
 static GL_VERSIONS: [GLVersion; 6] = [
     GLVersion { major: 2, minor: 0 },
@@ -48,6 +76,24 @@ pub fn test_adapter_creation() {
     connection.create_software_adapter().unwrap();
 }
 
+// Tests that connections can be opened by backend name.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_backend_selection() {
+    let connection = Connection::new().unwrap();
+    let backend_name = connection.backend_name();
+    assert!(Connection::backend_names().contains(&backend_name));
+
+    let other_connection = Connection::new_with_backend(backend_name).unwrap();
+    assert_eq!(other_connection.backend_name(), backend_name);
+
+    match Connection::new_with_backend("no-such-backend") {
+        Err(Error::UnknownBackend) => {}
+        Ok(_) => panic!("Opened a connection with a nonexistent backend!"),
+        Err(err) => panic!("Expected `UnknownBackend` but got {:?}", err),
+    }
+}
+
 #[cfg_attr(not(feature = "sm-test"), test)]
 #[cfg_attr(not(feature = "sm-test"), serial)]
 pub fn test_device_creation() {
@@ -106,7 +152,7 @@ pub fn test_context_creation() {
     };
 
     for &version in versions {
-        for flag_bits in 0..(ContextAttributeFlags::all().bits() + 1) {
+        for flag_bits in 0..=ContextAttributeFlags::all().bits() {
             let flags = ContextAttributeFlags::from_bits_truncate(flag_bits);
             let attributes = ContextAttributes { version, flags };
             let descriptor = match device.create_context_descriptor(&attributes) {
@@ -124,7 +170,9 @@ pub fn test_context_creation() {
                     let actual_descriptor = device.context_descriptor(&context);
                     let actual_attributes =
                         device.context_descriptor_attributes(&actual_descriptor);
-                    if !actual_attributes.flags.contains(attributes.flags) {
+                    // Hints may be dropped if the implementation doesn't support them.
+                    let required_flags = attributes.flags - attributes.flags.hints();
+                    if !actual_attributes.flags.contains(required_flags) {
                         device.destroy_context(&mut context).unwrap();
                         panic!(
                             "Expected at least attribute flags {:?} for {:?} {:?} but got \
@@ -514,6 +562,40 @@ pub fn test_surface_texture_blit_framebuffer() {
     }
 }
 
+// Tests that surface textures work with the pbuffer fallback that EGL backends use when EGL images
+// are unavailable, and that the device only reports the formats that the fallback supports.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_pbuffer_surface_fallback() {
+    let _guard = ForcePbufferSurfacesGuard::new();
+    test_surface_texture_blit_framebuffer();
+    test_surface_formats();
+
+    // A pbuffer that EGL refuses to create is an error, not a panic.
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+    match env.device.create_surface(
+        &env.context,
+        SurfaceAccess::GPUOnly,
+        SurfaceType::Generic {
+            size: Size2D::new(-1, -1),
+        },
+    ) {
+        Err(Error::SurfaceCreationFailed(_)) => {}
+        Err(err) => panic!("Unexpected error creating an invalid pbuffer: {:?}", err),
+        Ok(mut surface) => {
+            env.device
+                .destroy_surface(&mut env.context, &mut surface)
+                .unwrap();
+            panic!("Created an invalid pbuffer!");
+        }
+    }
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
 #[cfg_attr(not(feature = "sm-test"), test)]
 #[cfg_attr(not(feature = "sm-test"), serial)]
 pub fn test_cross_device_surface_texture_blit_framebuffer() {
@@ -910,126 +992,1746 @@ pub fn test_get_native_context() {
     env.device.destroy_context(&mut env.context).unwrap();
 }
 
-fn bind_context_fbo(gl: &Gl, device: &Device, context: &Context) {
-    unsafe {
-        gl.bind_framebuffer(gl::FRAMEBUFFER, context_fbo(device, context));
-        check_gl(gl);
-    }
+// Tests that `with_context_current()` restores whatever was current beforehand.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_with_context_current() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    clear(&env.gl, &[0, 255, 0, 255]);
+
+    let mut other_context = env
+        .device
+        .create_context(&env.context_descriptor, None)
+        .unwrap();
+    let other_surface = make_surface(&mut env.device, &other_context);
+    env.device
+        .bind_surface_to_context(&mut other_context, other_surface)
+        .unwrap();
+    env.device.make_context_current(&env.context).unwrap();
+
+    let pixel = env
+        .device
+        .with_context_current(&other_context, |gl| {
+            bind_context_fbo(gl, &env.device, &other_context);
+            clear(gl, &[255, 0, 0, 255]);
+            get_pixel_from_bottom_row(gl)
+        })
+        .unwrap();
+    assert_eq!(pixel, [255, 0, 0, 255]);
+
+    // The original context should be current again, with its framebuffer untouched.
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+
+    // If nothing was current, nothing should be current afterward.
+    env.device.make_no_context_current().unwrap();
+    env.device
+        .with_context_current(&other_context, |_| ())
+        .unwrap();
+    assert!(NativeContext::current().is_err());
+
+    env.device.destroy_context(&mut other_context).unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
 }
 
-fn context_fbo(device: &Device, context: &Context) -> Option<Framebuffer> {
-    device
-        .context_surface_info(context)
-        .unwrap()
-        .unwrap()
-        .framebuffer_object
+// Tests that device capabilities and adapter info can be queried without disturbing the current context.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_capabilities() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    clear(&env.gl, &[0, 255, 0, 255]);
+
+    let capabilities = env.device.capabilities().unwrap();
+    assert_eq!(capabilities.gl_api, env.device.gl_api());
+    assert!(capabilities.max_version >= capabilities.min_version);
+    assert!(capabilities.max_surface_size.width >= 640);
+    assert!(capabilities.max_surface_size.height >= 480);
+    assert!(!capabilities.renderer.is_empty());
+
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+
+    let adapter_info = env.device.adapter_info().unwrap();
+    assert_eq!(adapter_info.renderer, capabilities.renderer);
+    assert!(!adapter_info.driver_version.is_empty());
+
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+
+    env.device.destroy_context(&mut env.context).unwrap();
 }
 
-fn make_surface(device: &mut Device, context: &Context) -> Surface {
-    device
-        .create_surface(
-            context,
-            SurfaceAccess::GPUOnly,
-            SurfaceType::Generic {
-                size: Size2D::new(640, 480),
-            },
-        )
-        .unwrap()
+// Tests that context descriptors fall back to older versions when newer ones are unavailable.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_context_version_fallbacks() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    clear(&env.gl, &[0, 255, 0, 255]);
+
+    let unavailable = ContextAttributes {
+        version: GLVersion::new(9, 9),
+        flags: ContextAttributeFlags::empty(),
+    };
+    let available = ContextAttributes {
+        version: GLVersion::new(3, 0),
+        flags: ContextAttributeFlags::empty(),
+    };
+
+    let descriptor = env
+        .device
+        .create_context_descriptor_with_fallbacks(&[unavailable, available])
+        .unwrap();
+    let actual_attributes = env.device.context_descriptor_attributes(&descriptor);
+    assert!(actual_attributes.version.major >= 3);
+    assert_ne!(actual_attributes.version, unavailable.version);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+
+    let mut context = env.device.create_context(&descriptor, None).unwrap();
+    env.device.destroy_context(&mut context).unwrap();
+
+    assert!(env
+        .device
+        .create_context_descriptor_with_fallbacks(&[unavailable])
+        .is_err());
+    assert!(env
+        .device
+        .create_context_descriptor_with_fallbacks(&[])
+        .is_err());
+
+    env.device.destroy_context(&mut env.context).unwrap();
 }
 
-fn blit_fbo(gl: &Gl, dest_fbo: Option<Framebuffer>, src_fbo: Option<Framebuffer>) {
-    unsafe {
-        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, dest_fbo);
-        check_gl(gl);
-        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, src_fbo);
-        check_gl(gl);
-        gl.blit_framebuffer(
-            0,
-            0,
-            640,
-            480,
-            0,
-            0,
-            640,
-            480,
-            gl::COLOR_BUFFER_BIT,
-            gl::NEAREST,
-        );
-        check_gl(gl);
+// Tests that contexts can be created with hints, and that only hints that were requested are
+// reported as having taken effect.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_context_hints() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let requested_hints = ContextAttributeFlags::NO_ERROR | ContextAttributeFlags::LOW_PRIORITY;
+    let descriptor = env
+        .device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: requested_hints,
+        })
+        .unwrap();
+    let requested_attributes = env.device.context_descriptor_attributes(&descriptor);
+    assert!(requested_attributes.flags.contains(requested_hints));
+
+    let mut context = env.device.create_context(&descriptor, None).unwrap();
+    let surface = make_surface(&mut env.device, &context);
+    env.device
+        .bind_surface_to_context(&mut context, surface)
+        .unwrap();
+    env.device.make_context_current(&context).unwrap();
+    let gl =
+        unsafe { Gl::from_loader_function(|symbol| env.device.get_proc_address(&context, symbol)) };
+    bind_context_fbo(&gl, &env.device, &context);
+    clear(&gl, &[0, 0, 255, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&gl), [0, 0, 255, 255]);
+
+    let actual_descriptor = env.device.context_descriptor(&context);
+    let actual_attributes = env.device.context_descriptor_attributes(&actual_descriptor);
+    assert!(requested_hints.contains(actual_attributes.flags.hints()));
+
+    env.device.destroy_context(&mut context).unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that a connection switched to OpenGL ES creates OpenGL ES contexts.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_gles_connection() {
+    let connection = match Connection::new().unwrap().with_gl_api(GLApi::GLES) {
+        Ok(connection) => connection,
+        Err(Error::UnsupportedGLType) => {
+            // This backend can't do OpenGL ES.
+            return;
+        }
+        Err(err) => panic!("Failed to switch to OpenGL ES: {:?}", err),
+    };
+    assert_eq!(connection.gl_api(), GLApi::GLES);
+
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+    assert_eq!(device.gl_api(), GLApi::GLES);
+    assert_eq!(device.connection().gl_api(), GLApi::GLES);
+
+    let descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::empty(),
+        })
+        .unwrap();
+    let mut context = match device.create_context(&descriptor, None) {
+        Ok(context) => context,
+        Err(err) => panic!("Failed to create an OpenGL ES context: {:?}", err),
+    };
+
+    let version_string = device
+        .with_context_current(&context, |gl| unsafe {
+            gl.get_parameter_string(gl::VERSION)
+        })
+        .unwrap();
+    assert!(version_string.starts_with("OpenGL ES"));
+
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that setting `SURFMAN_FORCE_GLES` makes new connections use OpenGL ES.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_force_gles_env_var() {
+    use crate::platform::unix::FORCE_GLES_ENV_VAR;
+    use std::env;
+
+    let old_value = env::var_os(FORCE_GLES_ENV_VAR);
+    env::set_var(FORCE_GLES_ENV_VAR, "1");
+    let connection = Connection::new();
+    match old_value {
+        Some(old_value) => env::set_var(FORCE_GLES_ENV_VAR, old_value),
+        None => env::remove_var(FORCE_GLES_ENV_VAR),
     }
+    assert_eq!(connection.unwrap().gl_api(), GLApi::GLES);
 }
 
-fn make_fbo(gl: &Gl, texture_target: u32, texture: Option<Texture>) -> Framebuffer {
+// Tests that depth and stencil buffers can be allocated on OpenGL ES 2.0, which may lack 24-bit
+// depth and packed depth/stencil formats.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_gles2_depth_and_stencil() {
+    let connection = match Connection::new().unwrap().with_gl_api(GLApi::GLES) {
+        Ok(connection) => connection,
+        Err(Error::UnsupportedGLType) => {
+            // This backend can't do OpenGL ES.
+            return;
+        }
+        Err(err) => panic!("Failed to switch to OpenGL ES: {:?}", err),
+    };
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    let descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(2, 0),
+            flags: ContextAttributeFlags::DEPTH | ContextAttributeFlags::STENCIL,
+        })
+        .unwrap();
+    let mut context = device.create_context(&descriptor, None).unwrap();
+    let surface = make_surface(&mut device, &context);
+    device
+        .bind_surface_to_context(&mut context, surface)
+        .unwrap();
+    device.make_context_current(&context).unwrap();
+
+    let gl =
+        unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&context, symbol)) };
     unsafe {
-        let framebuffer_object = gl.create_framebuffer().unwrap();
-        check_gl(gl);
-        gl.bind_framebuffer(gl::FRAMEBUFFER, Some(framebuffer_object));
-        check_gl(gl);
-        gl.framebuffer_texture_2d(
-            gl::FRAMEBUFFER,
-            gl::COLOR_ATTACHMENT0,
-            texture_target,
-            texture,
-            0,
-        );
-        check_gl(gl);
+        bind_context_fbo(&gl, &device, &context);
         assert_eq!(
             gl.check_framebuffer_status(gl::FRAMEBUFFER),
             gl::FRAMEBUFFER_COMPLETE
         );
-        framebuffer_object
+        assert!(gl.get_parameter_i32(gl::DEPTH_BITS) >= 16);
+        assert!(gl.get_parameter_i32(gl::STENCIL_BITS) >= 8);
+        check_gl(&gl);
     }
-}
 
-struct BasicEnvironment {
-    connection: Connection,
-    adapter: Adapter,
-    device: Device,
-    context_descriptor: ContextDescriptor,
-    context: Context,
-    gl: Gl,
+    device.destroy_context(&mut context).unwrap();
 }
 
-impl BasicEnvironment {
-    fn new() -> Option<BasicEnvironment> {
-        let connection = Connection::new().unwrap();
-        let adapter = connection
-            .create_low_power_adapter()
-            .expect("Failed to create adapter!");
-        let mut device = match connection.create_device(&adapter) {
-            Ok(device) => device,
-            Err(Error::RequiredExtensionUnavailable) => {
-                // Can't run these tests on this hardware.
-                return None;
-            }
-            Err(err) => panic!("Failed to create device: {:?}", err),
+// Tests that generic surfaces can have different depth and stencil buffers than their context.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_surface_attributes() {
+    let context_attributes = ContextAttributes {
+        version: GLVersion::new(3, 0),
+        flags: ContextAttributeFlags::DEPTH | ContextAttributeFlags::STENCIL,
+    };
+    let (mut device, mut context) =
+        match create_device_and_context(context_attributes.version, context_attributes.flags) {
+            None => return,
+            Some(device_and_context) => device_and_context,
         };
+    let gl =
+        unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&context, symbol)) };
 
-        let context_descriptor = device
-            .create_context_descriptor(&ContextAttributes {
-                version: GLVersion::new(3, 0),
-                flags: ContextAttributeFlags::empty(),
-            })
+    let color_only = SurfaceAttributes {
+        format: SurfaceFormat::RGBA8,
+        attachments: SurfaceAttachmentFlags::empty(),
+        layers: 1,
+        samples: 1,
+    };
+    let depth_stencil = SurfaceAttributes::for_context(&context_attributes);
+    assert_eq!(
+        depth_stencil.attachments,
+        SurfaceAttachmentFlags::DEPTH | SurfaceAttachmentFlags::STENCIL
+    );
+
+    for (surface_attributes, expected_type) in
+        [(color_only, gl::NONE), (depth_stencil, gl::RENDERBUFFER)]
+    {
+        let surface = device
+            .create_generic_surface_with_attributes(
+                &context,
+                SurfaceAccess::GPUOnly,
+                &Size2D::new(640, 480),
+                &surface_attributes,
+            )
             .unwrap();
-
-        let mut context = device.create_context(&context_descriptor, None).unwrap();
-        let surface = make_surface(&mut device, &context);
         device
             .bind_surface_to_context(&mut context, surface)
             .unwrap();
         device.make_context_current(&context).unwrap();
 
-        let gl =
-            unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&context, symbol)) };
+        let framebuffer_object = device
+            .context_surface_info(&context)
+            .unwrap()
+            .unwrap()
+            .framebuffer_object;
+        if framebuffer_object.is_none() {
+            // Pbuffer surfaces take their depth and stencil buffers from the context.
+            let mut surface = device
+                .unbind_surface_from_context(&mut context)
+                .unwrap()
+                .unwrap();
+            device.destroy_surface(&mut context, &mut surface).unwrap();
+            break;
+        }
 
         unsafe {
-            let framebuffer_object = device
-                .context_surface_info(&context)
-                .unwrap()
-                .unwrap()
-                .framebuffer_object;
             gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer_object);
-            gl.viewport(0, 0, 640, 480);
+            assert_eq!(
+                gl.check_framebuffer_status(gl::FRAMEBUFFER),
+                gl::FRAMEBUFFER_COMPLETE
+            );
+            for attachment in [gl::DEPTH_ATTACHMENT, gl::STENCIL_ATTACHMENT] {
+                let object_type = gl.get_framebuffer_attachment_parameter_i32(
+                    gl::FRAMEBUFFER,
+                    attachment,
+                    gl::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE,
+                );
+                assert_eq!(object_type as u32, expected_type);
+            }
+            check_gl(&gl);
+        }
+
+        let mut surface = device
+            .unbind_surface_from_context(&mut context)
+            .unwrap()
+            .unwrap();
+        device.destroy_surface(&mut context, &mut surface).unwrap();
+    }
+
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that generic surfaces can be created in each color format that the device reports, and
+// that the other formats are rejected.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_surface_formats() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+
+    let capabilities = device.capabilities().unwrap();
+    assert!(capabilities.supports_surface_format(SurfaceFormat::RGBA8));
+
+    for format in SurfaceFormat::ALL {
+        let surface_attributes = SurfaceAttributes {
+            format,
+            attachments: SurfaceAttachmentFlags::empty(),
+            layers: 1,
+            samples: 1,
+        };
+        let result = device.create_generic_surface_with_attributes(
+            &context,
+            SurfaceAccess::GPUOnly,
+            &Size2D::new(640, 480),
+            &surface_attributes,
+        );
+        let surface = match result {
+            Ok(surface) => surface,
+            Err(Error::UnsupportedSurfaceFormat) => {
+                assert!(!capabilities.supports_surface_format(format));
+                continue;
+            }
+            Err(err) => panic!("Failed to create {:?} surface: {:?}", format, err),
+        };
+        assert!(capabilities.supports_surface_format(format));
+        assert_eq!(device.surface_info(&surface).format, format);
+
+        let surface_texture = device
+            .create_surface_texture(&mut context, surface)
+            .unwrap();
+        assert!(device.surface_texture_object(&surface_texture).is_some());
+        let mut surface = device
+            .destroy_surface_texture(&mut context, surface_texture)
+            .unwrap();
+        device.destroy_surface(&mut context, &mut surface).unwrap();
+    }
+
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that each layer of a layered surface can be rendered to separately, and that contexts
+// sharing objects with the surface's context can sample the layers from the texture array.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_layered_surfaces() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+    let gl =
+        unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&context, symbol)) };
+
+    let surface_attributes = SurfaceAttributes {
+        format: SurfaceFormat::RGBA8,
+        attachments: SurfaceAttachmentFlags::empty(),
+        layers: 2,
+        samples: 1,
+    };
+    let surface = match device.create_generic_surface_with_attributes(
+        &context,
+        SurfaceAccess::GPUOnly,
+        &Size2D::new(640, 480),
+        &surface_attributes,
+    ) {
+        Ok(surface) => surface,
+        Err(Error::UnsupportedOnThisPlatform) | Err(Error::UnsupportedGLVersion) => {
+            device.destroy_context(&mut context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to create layered surface: {:?}", err),
+    };
+
+    let surface_info = device.surface_info(&surface);
+    assert_eq!(surface_info.layers, 2);
+    assert_eq!(surface_info.layer_framebuffer_objects.len(), 2);
+    assert_eq!(
+        surface_info.framebuffer_object,
+        Some(surface_info.layer_framebuffer_objects[0])
+    );
+
+    device
+        .bind_surface_to_context(&mut context, surface)
+        .unwrap();
+    device.make_context_current(&context).unwrap();
+    let colors = [[255, 0, 0, 255], [0, 0, 255, 255]];
+    unsafe {
+        for (framebuffer_object, color) in surface_info
+            .layer_framebuffer_objects
+            .iter()
+            .zip(colors.iter())
+        {
+            gl.bind_framebuffer(gl::FRAMEBUFFER, Some(*framebuffer_object));
+            gl.viewport(0, 0, 640, 480);
+            clear(&gl, color);
+            check_gl(&gl);
+        }
+    }
+    let surface = device
+        .unbind_surface_from_context(&mut context)
+        .unwrap()
+        .unwrap();
+
+    // The texture array can't be shared with contexts outside the surface's share group.
+    let context_descriptor = device.context_descriptor(&context);
+    let mut unshared_context = device.create_context(&context_descriptor, None).unwrap();
+    let surface = match device.create_surface_texture(&mut unshared_context, surface) {
+        Err((Error::IncompatibleSharedContext, surface)) => surface,
+        Err((err, _)) => panic!("Unexpected error creating surface texture: {:?}", err),
+        Ok(_) => panic!("Created a surface texture outside the surface's share group"),
+    };
+    device.destroy_context(&mut unshared_context).unwrap();
+
+    let mut shared_context = device
+        .create_context(&context_descriptor, Some(&context))
+        .unwrap();
+    let surface_texture = device
+        .create_surface_texture(&mut shared_context, surface)
+        .unwrap();
+    assert_eq!(
+        device.surface_texture_target(&surface_texture),
+        gl::TEXTURE_2D_ARRAY
+    );
+    let texture_object = device.surface_texture_object(&surface_texture);
+    device.make_context_current(&shared_context).unwrap();
+    unsafe {
+        let framebuffer_object = gl.create_framebuffer().unwrap();
+        gl.bind_framebuffer(gl::FRAMEBUFFER, Some(framebuffer_object));
+        for (layer, color) in colors.iter().enumerate() {
+            gl.framebuffer_texture_layer(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                texture_object,
+                0,
+                layer as i32,
+            );
+            check_gl(&gl);
+            assert_eq!(get_pixel_from_bottom_row(&gl), *color);
+        }
+        gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        gl.delete_framebuffer(framebuffer_object);
+    }
+
+    let mut surface = device
+        .destroy_surface_texture(&mut shared_context, surface_texture)
+        .unwrap();
+    device.destroy_context(&mut shared_context).unwrap();
+    device.destroy_surface(&mut context, &mut surface).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that multisampled surfaces are rendered to through a multisampled framebuffer and resolved
+// when unbound, so that surface textures see what was rendered.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_multisampled_surfaces() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+    let gl =
+        unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&context, symbol)) };
+
+    let mut surface_attributes = SurfaceAttributes {
+        format: SurfaceFormat::RGBA8,
+        attachments: SurfaceAttachmentFlags::DEPTH | SurfaceAttachmentFlags::STENCIL,
+        layers: 1,
+        samples: 0,
+    };
+    match device.create_generic_surface_with_attributes(
+        &context,
+        SurfaceAccess::GPUOnly,
+        &Size2D::new(640, 480),
+        &surface_attributes,
+    ) {
+        Err(Error::SurfaceCreationFailed(WindowingApiError::BadValue))
+        | Err(Error::UnsupportedOnThisPlatform) => {}
+        _ => panic!("A surface with no samples shouldn't have been created!"),
+    }
+
+    surface_attributes.samples = 4;
+    let surface = match device.create_generic_surface_with_attributes(
+        &context,
+        SurfaceAccess::GPUOnly,
+        &Size2D::new(640, 480),
+        &surface_attributes,
+    ) {
+        Ok(surface) => surface,
+        Err(Error::UnsupportedOnThisPlatform)
+        | Err(Error::UnsupportedGLVersion)
+        | Err(Error::SurfaceCreationFailed(WindowingApiError::BadValue)) => {
+            device.destroy_context(&mut context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to create multisampled surface: {:?}", err),
+    };
+
+    device
+        .bind_surface_to_context(&mut context, surface)
+        .unwrap();
+    device.make_context_current(&context).unwrap();
+    unsafe {
+        bind_context_fbo(&gl, &device, &context);
+        assert!(gl.get_parameter_i32(gl::SAMPLES) >= 4);
+        gl.viewport(0, 0, 640, 480);
+        clear(&gl, &[0, 255, 0, 255]);
+        check_gl(&gl);
+    }
+    let surface = device
+        .unbind_surface_from_context(&mut context)
+        .unwrap()
+        .unwrap();
+
+    let surface_texture = device
+        .create_surface_texture(&mut context, surface)
+        .unwrap();
+    unsafe {
+        let framebuffer_object = make_fbo(
+            &gl,
+            device.surface_gl_texture_target(),
+            device.surface_texture_object(&surface_texture),
+        );
+        assert_eq!(get_pixel_from_bottom_row(&gl), [0, 255, 0, 255]);
+        gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        gl.delete_framebuffer(framebuffer_object);
+    }
+
+    let mut surface = device
+        .destroy_surface_texture(&mut context, surface_texture)
+        .unwrap();
+    device.destroy_surface(&mut context, &mut surface).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that a caller-owned texture can be wrapped in a surface, that textures of the wrong size
+// or format are rejected, and that destroying the surface leaves the texture alone.
+#[cfg(any(free_unix, android_platform, ohos_platform))]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_surface_from_texture() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+    device.make_context_current(&context).unwrap();
+    let gl =
+        unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&context, symbol)) };
+
+    let texture = unsafe {
+        let texture = gl.create_texture().unwrap();
+        gl.bind_texture(gl::TEXTURE_2D, Some(texture));
+        gl.tex_image_2d(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA as i32,
+            640,
+            480,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            PixelUnpackData::Slice(None),
+        );
+        gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
+        gl.bind_texture(gl::TEXTURE_2D, None);
+        check_gl(&gl);
+        texture
+    };
+
+    let surface = match unsafe {
+        device.create_surface_from_texture(
+            &context,
+            &Size2D::new(640, 480),
+            SurfaceFormat::RGBA8,
+            texture,
+        )
+    } {
+        Ok(surface) => surface,
+        Err(Error::RequiredExtensionUnavailable) => {
+            unsafe { gl.delete_texture(texture) };
+            device.destroy_context(&mut context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to wrap texture: {:?}", err),
+    };
+    let surface_info = device.surface_info(&surface);
+    assert_eq!(surface_info.context_id, device.context_id(&context));
+    assert_eq!(surface_info.format, SurfaceFormat::RGBA8);
+
+    // The texture can only be inspected with desktop OpenGL or OpenGL ES 3.1.
+    let version = gl.version();
+    if !version.is_embedded || (version.major, version.minor) >= (3, 1) {
+        let mismatches = [
+            (Size2D::new(320, 240), SurfaceFormat::RGBA8),
+            (Size2D::new(640, 480), SurfaceFormat::R8),
+        ];
+        for (size, format) in mismatches {
+            match unsafe { device.create_surface_from_texture(&context, &size, format, texture) } {
+                Err(Error::SurfaceImportFailed(WindowingApiError::BadMatch)) => {}
+                Err(err) => panic!("Unexpected error wrapping texture: {:?}", err),
+                Ok(_) => panic!("Wrapped a texture of the wrong size or format"),
+            }
+        }
+    }
+
+    device
+        .bind_surface_to_context(&mut context, surface)
+        .unwrap();
+    device.make_context_current(&context).unwrap();
+    bind_context_fbo(&gl, &device, &context);
+    unsafe {
+        gl.viewport(0, 0, 640, 480);
+    }
+    clear(&gl, &[0, 255, 0, 255]);
+    let surface = device
+        .unbind_surface_from_context(&mut context)
+        .unwrap()
+        .unwrap();
+
+    let surface_texture = device
+        .create_surface_texture(&mut context, surface)
+        .unwrap();
+    let framebuffer_object = make_fbo(
+        &gl,
+        device.surface_gl_texture_target(),
+        device.surface_texture_object(&surface_texture),
+    );
+    assert_eq!(get_pixel_from_bottom_row(&gl), [0, 255, 0, 255]);
+    unsafe {
+        gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        gl.delete_framebuffer(framebuffer_object);
+    }
+
+    let mut surface = device
+        .destroy_surface_texture(&mut context, surface_texture)
+        .unwrap();
+    device.destroy_surface(&mut context, &mut surface).unwrap();
+
+    // The texture still belongs to us, contents and all.
+    let framebuffer_object = make_fbo(&gl, gl::TEXTURE_2D, Some(texture));
+    assert_eq!(get_pixel_from_bottom_row(&gl), [0, 255, 0, 255]);
+    unsafe {
+        gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        gl.delete_framebuffer(framebuffer_object);
+        gl.delete_texture(texture);
+        check_gl(&gl);
+    }
+
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that the current context guard restores the bound EGL client API as well as the context.
+#[cfg(any(free_unix, android_platform, ohos_platform))]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_current_context_guard_restores_egl_api() {
+    use crate::egl;
+    use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+
+    let (device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+
+    let old_api = EGL_FUNCTIONS.with(|egl| unsafe { egl.QueryAPI() });
+    let other_api = if old_api == egl::OPENGL_API {
+        egl::OPENGL_ES_API
+    } else {
+        egl::OPENGL_API
+    };
+    let bound_other_api = {
+        let _guard = device.temporarily_make_context_current(&context).unwrap();
+        EGL_FUNCTIONS.with(|egl| unsafe { egl.BindAPI(other_api) }) != egl::FALSE
+    };
+    if !bound_other_api {
+        // The other client API isn't supported here.
+        device.destroy_context(&mut context).unwrap();
+        return;
+    }
+    assert_eq!(EGL_FUNCTIONS.with(|egl| unsafe { egl.QueryAPI() }), old_api);
+
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that an EGL image made outside surfman can be sampled through a surface texture.
+#[cfg(any(free_unix, android_platform, ohos_platform))]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_surface_texture_from_egl_image() {
+    use crate::egl;
+    use crate::egl::types::EGLint;
+    use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+    use crate::platform::generic::egl::ffi::{EGLClientBuffer, EGL_EXTENSION_FUNCTIONS};
+    use crate::platform::generic::egl::ffi::{EGL_GL_TEXTURE_2D_KHR, EGL_IMAGE_PRESERVED_KHR};
+
+    let (device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+    device.make_context_current(&context).unwrap();
+    let gl =
+        unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&context, symbol)) };
+
+    let create_image = match EGL_EXTENSION_FUNCTIONS.CreateImageKHR {
+        Some(create_image) => create_image,
+        None => {
+            device.destroy_context(&mut context).unwrap();
+            return;
+        }
+    };
+
+    // Stand in for a video decoder by rendering into a texture and exporting it. The format is
+    // something other than RGBA8, so that it's clear the surface reports the format it was given.
+    let texture = unsafe {
+        let texture = gl.create_texture().unwrap();
+        gl.bind_texture(gl::TEXTURE_2D, Some(texture));
+        gl.tex_image_2d(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGB10_A2 as i32,
+            640,
+            480,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_INT_2_10_10_10_REV,
+            PixelUnpackData::Slice(None),
+        );
+        gl.tex_parameter_i32(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
+        gl.bind_texture(gl::TEXTURE_2D, None);
+        let framebuffer_object = make_fbo(&gl, gl::TEXTURE_2D, Some(texture));
+        gl.viewport(0, 0, 640, 480);
+        clear(&gl, &[0, 0, 255, 255]);
+        gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        gl.delete_framebuffer(framebuffer_object);
+        gl.finish();
+        check_gl(&gl);
+        texture
+    };
+    let egl_display = EGL_FUNCTIONS.with(|egl| unsafe { egl.GetCurrentDisplay() });
+    let attributes = [
+        EGL_IMAGE_PRESERVED_KHR as EGLint,
+        egl::TRUE as EGLint,
+        egl::NONE as EGLint,
+        0,
+    ];
+    let egl_image = create_image(
+        egl_display,
+        device.native_context(&context).egl_context,
+        EGL_GL_TEXTURE_2D_KHR,
+        texture.0.get() as usize as EGLClientBuffer,
+        attributes.as_ptr(),
+    );
+    assert!(!egl_image.is_null());
+
+    let surface_texture = match unsafe {
+        device.create_surface_texture_from_egl_image(
+            &mut context,
+            egl_image,
+            &Size2D::new(640, 480),
+            SurfaceFormat::RGB10A2,
+            gl::TEXTURE_2D,
+        )
+    } {
+        Ok(surface_texture) => surface_texture,
+        Err(Error::RequiredExtensionUnavailable) => {
+            unsafe { gl.delete_texture(texture) };
+            device.destroy_context(&mut context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to import EGL image: {:?}", err),
+    };
+    assert_eq!(
+        device.surface_texture_target(&surface_texture),
+        gl::TEXTURE_2D
+    );
+    let framebuffer_object = make_fbo(
+        &gl,
+        gl::TEXTURE_2D,
+        device.surface_texture_object(&surface_texture),
+    );
+    assert_eq!(get_pixel_from_bottom_row(&gl), [0, 0, 255, 255]);
+    unsafe {
+        gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+        gl.delete_framebuffer(framebuffer_object);
+    }
+
+    // The surface that wraps the image can't be rendered to.
+    let surface = device
+        .destroy_surface_texture(&mut context, surface_texture)
+        .unwrap();
+    assert_eq!(device.surface_info(&surface).format, SurfaceFormat::RGB10A2);
+    let mut surface = match device.bind_surface_to_context(&mut context, surface) {
+        Err((Error::IncompatibleSurface, surface)) => surface,
+        other => panic!(
+            "Expected an IncompatibleSurface error, got {:?}",
+            other.err()
+        ),
+    };
+    device.destroy_surface(&mut context, &mut surface).unwrap();
+
+    unsafe {
+        let result = (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR.unwrap())(egl_display, egl_image);
+        assert_ne!(result, egl::FALSE);
+        gl.delete_texture(texture);
+        check_gl(&gl);
+    }
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that the native objects of a generic surface match its surface info.
+#[cfg(free_unix)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_native_surface() {
+    use super::surface::NativeSurface;
+
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+
+    let mut surface = make_surface(&mut device, &context);
+    let surface_info = device.surface_info(&surface);
+    match device.native_surface(&surface) {
+        NativeSurface::TextureImage { image } => {
+            assert!(!image.egl_image.is_null());
+            assert!(image.texture_object.is_some());
+            assert_eq!(image.framebuffer_object, surface_info.framebuffer_object);
+        }
+        NativeSurface::Pbuffer { egl_surface } => {
+            assert!(!egl_surface.is_null());
+            assert!(surface_info.framebuffer_object.is_none());
+        }
+        native_surface => panic!("Unexpected generic surface: {:?}", native_surface),
+    }
+
+    device.destroy_surface(&mut context, &mut surface).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that a published surface can be sampled by two contexts at once, and reclaimed once both
+// of their surface textures are gone.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_published_surface() {
+    let (mut device, context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+    let descriptor = device.context_descriptor(&context);
+    let mut contexts = [context, device.create_context(&descriptor, None).unwrap()];
+
+    let surface = make_surface(&mut device, &contexts[0]);
+    device
+        .bind_surface_to_context(&mut contexts[0], surface)
+        .unwrap();
+    device.make_context_current(&contexts[0]).unwrap();
+    let gl =
+        unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&contexts[0], symbol)) };
+    bind_context_fbo(&gl, &device, &contexts[0]);
+    unsafe {
+        gl.viewport(0, 0, 640, 480);
+    }
+    clear(&gl, &[255, 255, 0, 255]);
+    let surface = device
+        .unbind_surface_from_context(&mut contexts[0])
+        .unwrap()
+        .unwrap();
+    let surface_id = device.surface_info(&surface).id;
+
+    let published = PublishedSurface::new(surface);
+    assert_eq!(published.info(&device).id, surface_id);
+    let mut surface_textures = vec![];
+    for context in &mut contexts {
+        surface_textures.push(published.create_surface_texture(&device, context).unwrap());
+    }
+    assert_eq!(published.surface_texture_count(), 2);
+
+    for (context, surface_texture) in contexts.iter().zip(surface_textures.iter()) {
+        device.make_context_current(context).unwrap();
+        let gl =
+            unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(context, symbol)) };
+        let framebuffer_object = make_fbo(
+            &gl,
+            device.surface_gl_texture_target(),
+            device.surface_texture_object(surface_texture.surface_texture()),
+        );
+        assert_eq!(get_pixel_from_bottom_row(&gl), [255, 255, 0, 255]);
+        unsafe {
+            gl.bind_framebuffer(gl::FRAMEBUFFER, None);
+            gl.delete_framebuffer(framebuffer_object);
+        }
+    }
+
+    // The surface can't be reclaimed while it's still being sampled.
+    let published = published.reclaim().unwrap_err();
+    for (context, surface_texture) in contexts.iter_mut().zip(surface_textures.drain(..)) {
+        if published
+            .destroy_surface_texture(&device, context, surface_texture)
+            .is_err()
+        {
+            panic!("Failed to destroy published surface texture!");
+        }
+    }
+    assert_eq!(published.surface_texture_count(), 0);
+
+    let mut surface = match published.reclaim() {
+        Ok(surface) => surface,
+        Err(_) => panic!("Failed to reclaim published surface!"),
+    };
+    assert_eq!(device.surface_info(&surface).id, surface_id);
+    device
+        .destroy_surface(&mut contexts[0], &mut surface)
+        .unwrap();
+    for context in &mut contexts {
+        device.destroy_context(context).unwrap();
+    }
+}
+
+// Tests that a broadcast swap chain sends frames to every consumer, and only recycles a surface
+// once all of them have returned it.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_broadcast_swap_chain() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+
+    let swap_chain = BroadcastSwapChain::create_detached(
+        &mut device,
+        &mut context,
+        SurfaceAccess::GPUOnly,
+        Size2D::new(640, 480),
+    )
+    .unwrap();
+    assert!(swap_chain
+        .register_consumer(FrameDelivery::EveryFrame { depth: 0 })
+        .is_err());
+    let every_frame = swap_chain
+        .register_consumer(FrameDelivery::EveryFrame { depth: 3 })
+        .unwrap();
+    let latest_frame = swap_chain
+        .register_consumer(FrameDelivery::LatestFrame)
+        .unwrap();
+    assert!(!swap_chain.is_attached());
+
+    for _ in 0..3 {
+        swap_chain
+            .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+            .unwrap();
+    }
+
+    // A consumer that falls `depth` frames behind holds up the producer.
+    match swap_chain.swap_buffers(&mut device, &mut context, PreserveBuffer::No) {
+        Err(Error::SwapChainFull) => {}
+        result => panic!("Expected a SwapChainFull error, got {:?}", result),
+    }
+
+    // The slow consumer only sees the latest frame.
+    let latest = swap_chain.take_frame(latest_frame).unwrap();
+    assert!(swap_chain.take_frame(latest_frame).is_none());
+    let mut frames = vec![];
+    while let Some(frame) = swap_chain.take_frame(every_frame) {
+        frames.push(frame);
+    }
+    assert_eq!(frames.len(), 3);
+    let latest_id = latest.info(&device).id;
+    assert_eq!(frames[2].info(&device).id, latest_id);
+
+    // Both consumers can sample the latest frame at once.
+    let surface_texture = latest
+        .create_surface_texture(&device, &mut context)
+        .unwrap();
+    assert_eq!(latest.surface_texture_count(), 1);
+    if latest
+        .destroy_surface_texture(&device, &mut context, surface_texture)
+        .is_err()
+    {
+        panic!("Failed to destroy published surface texture!");
+    }
+
+    // The latest frame isn't reused while either consumer still holds it.
+    swap_chain.recycle_frame(latest);
+    swap_chain.unregister_consumer(latest_frame);
+    let last_frame = frames.pop().unwrap();
+    for frame in frames.drain(..) {
+        swap_chain.recycle_frame(frame);
+    }
+    for _ in 0..2 {
+        swap_chain
+            .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+            .unwrap();
+        let frame = swap_chain.take_frame(every_frame).unwrap();
+        assert_ne!(frame.info(&device).id, latest_id);
+        swap_chain.recycle_frame(frame);
+    }
+
+    // Destroying the swap chain fails while a consumer still holds a frame, and leaves it
+    // working.
+    assert!(swap_chain.destroy(&mut device, &mut context).is_err());
+    swap_chain
+        .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+        .unwrap();
+    swap_chain.recycle_frame(last_frame);
+    swap_chain.destroy(&mut device, &mut context).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that swap chain consumers can wait for a new frame instead of polling.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_swap_chain_wait_for_frame() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+
+    let swap_chain = SwapChain::create_detached(
+        &mut device,
+        &mut context,
+        SurfaceAccess::GPUOnly,
+        Size2D::new(640, 480),
+    )
+    .unwrap();
+    assert!(swap_chain
+        .wait_for_frame(Duration::from_millis(10))
+        .is_none());
+
+    let swap_count = Arc::new(AtomicUsize::new(0));
+    let callback_swap_count = swap_count.clone();
+    swap_chain.set_swap_callback(Some(Box::new(move || {
+        callback_swap_count.fetch_add(1, Ordering::SeqCst);
+    })));
+
+    // A pending future is woken by the next swap.
+    struct FlagWaker(AtomicBool);
+    impl Wake for FlagWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+    let flag_waker = Arc::new(FlagWaker(AtomicBool::new(false)));
+    let waker = Waker::from(flag_waker.clone());
+    let mut task_context = TaskContext::from_waker(&waker);
+    let mut next_frame = swap_chain.next_frame();
+    assert!(Pin::new(&mut next_frame)
+        .poll(&mut task_context)
+        .is_pending());
+
+    swap_chain
+        .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+        .unwrap();
+    assert!(flag_waker.0.load(Ordering::SeqCst));
+    assert_eq!(swap_count.load(Ordering::SeqCst), 1);
+    let surface = match Pin::new(&mut next_frame).poll(&mut task_context) {
+        Poll::Ready(surface) => surface,
+        Poll::Pending => panic!("Swapping didn't resolve the future!"),
+    };
+    swap_chain.recycle_surface(surface);
+
+    swap_chain
+        .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+        .unwrap();
+    assert_eq!(swap_count.load(Ordering::SeqCst), 2);
+    let surface = swap_chain
+        .wait_for_frame(Duration::from_millis(10))
+        .expect("No frame after swapping!");
+    swap_chain.recycle_surface(surface);
+    assert!(swap_chain
+        .wait_for_frame(Duration::from_millis(10))
+        .is_none());
+
+    swap_chain.set_swap_callback(None);
+    swap_chain.destroy(&mut device, &mut context).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that swapped frames carry their metadata to the consumer, and that swap chains keep
+// statistics about them.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_swap_chain_frame_info() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+
+    let swap_chain = SwapChain::create_detached(
+        &mut device,
+        &mut context,
+        SurfaceAccess::GPUOnly,
+        Size2D::new(640, 480),
+    )
+    .unwrap();
+    assert!(swap_chain.take_pending_frame().is_none());
+
+    // The first frame is never taken, so it's dropped.
+    for timestamp in [10u64, 20] {
+        swap_chain
+            .swap_buffers_with_user_data(
+                &mut device,
+                &mut context,
+                PreserveBuffer::No,
+                Some(Arc::new(timestamp)),
+            )
+            .unwrap();
+    }
+    assert_eq!(swap_chain.next_frame_id(), 2);
+    let (surface, frame_info) = swap_chain.take_pending_frame().unwrap();
+    assert_eq!(frame_info.frame_id, 1);
+    let user_data = frame_info.user_data.unwrap();
+    assert_eq!(user_data.downcast_ref::<u64>(), Some(&20));
+    swap_chain.recycle_surface(surface);
+
+    swap_chain
+        .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+        .unwrap();
+    let stats = swap_chain.stats();
+    assert_eq!(stats.frames_swapped, 3);
+    assert_eq!(stats.frames_taken, 1);
+    assert_eq!(stats.frames_dropped, 1);
+    assert_eq!(stats.recycle_hits, 2);
+    assert_eq!(stats.surfaces_allocated, 2);
+    assert_eq!(stats.mean_latency(), Some(stats.max_latency));
+
+    swap_chain.destroy(&mut device, &mut context).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that swap chains implemented outside this crate only need `take_surface()` and
+// `recycle_surface()`, and report no frame information.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_swap_chain_api_default_take_frame() {
+    use std::sync::Mutex;
+
+    #[derive(Clone)]
+    struct SingleSurface(Arc<Mutex<Option<u32>>>);
+
+    impl SwapChainAPI for SingleSurface {
+        type Surface = u32;
+
+        fn take_surface(&self) -> Option<u32> {
+            self.0.lock().unwrap().take()
+        }
+
+        fn recycle_surface(&self, surface: u32) {
+            *self.0.lock().unwrap() = Some(surface);
+        }
+    }
+
+    let swap_chain = SingleSurface(Arc::new(Mutex::new(Some(1))));
+    assert!(swap_chain.take_frame().is_none());
+    assert_eq!(swap_chain.take_surface(), Some(1));
+}
+
+// Tests that FIFO swap chains deliver every frame in order, and stop the producer when full.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_swap_chain_fifo_queueing() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+
+    let size = Size2D::new(640, 480);
+    match SwapChain::create_detached_with_queueing_mode(
+        &mut device,
+        &mut context,
+        SurfaceAccess::GPUOnly,
+        size,
+        QueueingMode::Fifo {
+            depth: 0,
+            when_full: QueueFull::Fail,
+        },
+    ) {
+        Err(Error::Failed) => {}
+        _ => panic!("An empty FIFO queue should be rejected!"),
+    }
+
+    let swap_chain = SwapChain::create_detached_with_queueing_mode(
+        &mut device,
+        &mut context,
+        SurfaceAccess::GPUOnly,
+        size,
+        QueueingMode::Fifo {
+            depth: 2,
+            when_full: QueueFull::Fail,
+        },
+    )
+    .unwrap();
+    for _ in 0..2 {
+        swap_chain
+            .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+            .unwrap();
+    }
+    match swap_chain.swap_buffers(&mut device, &mut context, PreserveBuffer::No) {
+        Err(Error::SwapChainFull) => {}
+        _ => panic!("Swapping into a full queue should fail!"),
+    }
+    for frame_id in 0..2 {
+        let (surface, frame_info) = swap_chain.take_pending_frame().unwrap();
+        assert_eq!(frame_info.frame_id, frame_id);
+        swap_chain.recycle_surface(surface);
+    }
+    assert!(swap_chain.take_pending_frame().is_none());
+    assert_eq!(swap_chain.stats().frames_dropped, 0);
+    swap_chain.destroy(&mut device, &mut context).unwrap();
+
+    // A blocking producer waits for the consumer to make room.
+    let swap_chain = SwapChain::create_detached_with_queueing_mode(
+        &mut device,
+        &mut context,
+        SurfaceAccess::GPUOnly,
+        size,
+        QueueingMode::Fifo {
+            depth: 1,
+            when_full: QueueFull::Block,
+        },
+    )
+    .unwrap();
+    swap_chain
+        .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+        .unwrap();
+    let consumer_swap_chain = swap_chain.clone();
+    let consumer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        let (surface, frame_info) = consumer_swap_chain.take_pending_frame().unwrap();
+        consumer_swap_chain.recycle_surface(surface);
+        frame_info.frame_id
+    });
+    swap_chain
+        .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+        .unwrap();
+    assert_eq!(consumer.join().unwrap(), 0);
+    let (surface, frame_info) = swap_chain.take_pending_frame().unwrap();
+    assert_eq!(frame_info.frame_id, 1);
+    swap_chain.recycle_surface(surface);
+
+    swap_chain.destroy(&mut device, &mut context).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that the producer and consumer halves of a split swap chain share frames.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_split_swap_chain() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+
+    let (producer, consumer) = SwapChain::create_detached(
+        &mut device,
+        &mut context,
+        SurfaceAccess::GPUOnly,
+        Size2D::new(640, 480),
+    )
+    .unwrap()
+    .split();
+    assert_eq!(producer.context_id(), device.context_id(&context));
+    assert!(!producer.is_attached());
+
+    let other_consumer = producer.consumer();
+    let consumer_thread = thread::spawn(move || {
+        let surface = consumer
+            .wait_for_frame(Duration::from_secs(10))
+            .expect("No frame arrived!");
+        consumer.recycle_surface(surface);
+        consumer.stats().frames_taken
+    });
+    producer
+        .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+        .unwrap();
+    assert_eq!(consumer_thread.join().unwrap(), 1);
+
+    producer
+        .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+        .unwrap();
+    let (surface, frame_info) = other_consumer.take_frame().unwrap();
+    assert_eq!(frame_info.frame_id, 1);
+    other_consumer.recycle_surface(surface);
+
+    // The producer refuses any context but its own.
+    let context_descriptor = device.context_descriptor(&context);
+    let mut other_context = device.create_context(&context_descriptor, None).unwrap();
+    match producer.swap_buffers(&mut device, &mut other_context, PreserveBuffer::No) {
+        Err(Error::IncompatibleContext) => {}
+        _ => panic!("Swapped with the wrong context!"),
+    }
+    match producer.destroy(&mut device, &mut other_context) {
+        Err(Error::IncompatibleContext) => {}
+        _ => panic!("Destroyed with the wrong context!"),
+    }
+    device.destroy_context(&mut other_context).unwrap();
+
+    producer.destroy(&mut device, &mut context).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that swap chains can preserve part of the back buffer across swaps and resizes.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_swap_chain_preserve_buffer() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+    let surface = make_surface(&mut device, &context);
+    device
+        .bind_surface_to_context(&mut context, surface)
+        .unwrap();
+    device.make_context_current(&context).unwrap();
+    let gl =
+        unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&context, symbol)) };
+    if context_fbo(&device, &context).is_none() {
+        // Pbuffer surfaces can't be read from while they're unbound, so there's nothing to copy.
+        let mut surface = device
+            .unbind_surface_from_context(&mut context)
+            .unwrap()
+            .unwrap();
+        device.destroy_surface(&mut context, &mut surface).unwrap();
+        device.destroy_context(&mut context).unwrap();
+        return;
+    }
+
+    let swap_chain =
+        SwapChain::create_attached(&mut device, &mut context, SurfaceAccess::GPUOnly).unwrap();
+    bind_context_fbo(&gl, &device, &context);
+    unsafe {
+        gl.viewport(0, 0, 640, 480);
+    }
+    clear(&gl, &[255, 0, 0, 255]);
+
+    // The overlapping region survives a resize.
+    let color_only = PreserveOptions {
+        depth_stencil: false,
+        damage: None,
+    };
+    swap_chain
+        .resize_preserving_buffer(
+            &mut device,
+            &mut context,
+            Size2D::new(320, 240),
+            PreserveBuffer::With(&gl, color_only),
+        )
+        .unwrap();
+    assert_eq!(swap_chain.size(), Size2D::new(320, 240));
+    bind_context_fbo(&gl, &device, &context);
+    check_gl(&gl);
+    assert_eq!(get_pixel_from_bottom_row(&gl), [255, 0, 0, 255]);
+
+    // Only the damaged bottom row is copied into the recycled back buffer.
+    clear(&gl, &[0, 0, 255, 255]);
+    swap_chain
+        .swap_buffers(&mut device, &mut context, PreserveBuffer::No)
+        .unwrap();
+    bind_context_fbo(&gl, &device, &context);
+    clear(&gl, &[0, 255, 0, 255]);
+    swap_chain
+        .swap_buffers(
+            &mut device,
+            &mut context,
+            PreserveBuffer::With(
+                &gl,
+                PreserveOptions {
+                    damage: Some(Rect::new(Point2D::origin(), Size2D::new(320, 1))),
+                    ..color_only
+                },
+            ),
+        )
+        .unwrap();
+    bind_context_fbo(&gl, &device, &context);
+    check_gl(&gl);
+    assert_eq!(get_pixel_from_bottom_row(&gl), [0, 255, 0, 255]);
+    assert_eq!(get_pixel_from_second_from_bottom_row(&gl), [0, 0, 255, 255]);
+
+    swap_chain.destroy(&mut device, &mut context).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests looking up, iterating over and destroying swap chains in a collection.
+#[cfg(feature = "chains")]
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_swap_chains() {
+    let (mut device, mut context) =
+        match create_device_and_context(GLVersion::new(3, 0), ContextAttributeFlags::empty()) {
+            None => return,
+            Some(device_and_context) => device_and_context,
+        };
+
+    let swap_chains = SwapChains::new();
+    for id in 0..40 {
+        swap_chains
+            .create_detached_swap_chain(
+                id,
+                Size2D::new(1, 1),
+                &mut device,
+                &mut context,
+                SurfaceAccess::GPUOnly,
+            )
+            .unwrap();
+    }
+    assert!(swap_chains
+        .create_detached_swap_chain(
+            7,
+            Size2D::new(1, 1),
+            &mut device,
+            &mut context,
+            SurfaceAccess::GPUOnly,
+        )
+        .is_err());
+    assert!(swap_chains.get(39).is_some());
+    assert!(swap_chains.get(40).is_none());
+    assert!(swap_chains.get_consumer(39).is_some());
+    assert!(swap_chains.get_consumer(40).is_none());
+
+    // Iteration isn't affected by swap chains destroyed in the meantime.
+    let mut iter = swap_chains.iter(&mut device, &mut context);
+    swap_chains.destroy(7, &mut device, &mut context).unwrap();
+    assert!(swap_chains.get(7).is_none());
+    assert_eq!(iter.by_ref().count(), 40);
+    let mut ids: Vec<i32> = swap_chains
+        .iter(&mut device, &mut context)
+        .map(|(id, _)| id)
+        .collect();
+    ids.sort();
+    assert_eq!(ids, (0..40).filter(|&id| id != 7).collect::<Vec<_>>());
+
+    swap_chains.destroy_all(&mut device, &mut context).unwrap();
+    assert!(swap_chains.get(0).is_none());
+    assert_eq!(swap_chains.iter(&mut device, &mut context).count(), 0);
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that owned handles clean up after themselves when dropped.
+#[cfg_attr(not(feature = "sm-test"), test)]
+#[cfg_attr(not(feature = "sm-test"), serial)]
+pub fn test_owned_handles() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let device = match connection.create_device(&adapter) {
+        Ok(device) => Rc::new(RefCell::new(device)),
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    let descriptor = device
+        .borrow()
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::empty(),
+        })
+        .unwrap();
+
+    let context = OwnedContext::new(&device, &descriptor, None).unwrap();
+    let surface = context
+        .create_surface(
+            SurfaceAccess::GPUOnly,
+            SurfaceType::Generic {
+                size: Size2D::new(640, 480),
+            },
+        )
+        .unwrap();
+    assert_eq!(surface.info().context_id, context.id());
+    if context.bind_surface(surface).is_err() {
+        panic!("Failed to bind surface!");
+    }
+    context.make_current().unwrap();
+
+    let gl = unsafe {
+        Gl::from_loader_function(|symbol| {
+            device.borrow().get_proc_address(&context.context(), symbol)
+        })
+    };
+    unsafe {
+        let framebuffer_object = context.surface_info().unwrap().unwrap().framebuffer_object;
+        gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer_object);
+        gl.viewport(0, 0, 640, 480);
+    }
+    clear(&gl, &[0, 255, 0, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&gl), [0, 255, 0, 255]);
+
+    // A context can't be released while a surface created from it is alive.
+    let surface = context.unbind_surface().unwrap().unwrap();
+    let context = match context.into_raw() {
+        Ok(_) => panic!("Released a context that still had a live surface!"),
+        Err(context) => context,
+    };
+
+    // Dropping the surface texture also destroys the surface, and dropping the last handle to
+    // the context destroys it.
+    let surface_texture = match context.create_surface_texture(surface) {
+        Ok(surface_texture) => surface_texture,
+        Err((err, _)) => panic!("Failed to create surface texture: {:?}", err),
+    };
+    assert!(surface_texture.texture_object().is_some());
+
+    // Destroying the surface texture and surface doesn't leave their context current.
+    let other_surface = context
+        .create_surface(
+            SurfaceAccess::GPUOnly,
+            SurfaceType::Generic {
+                size: Size2D::new(64, 64),
+            },
+        )
+        .unwrap();
+    let other_surface_texture = match context.create_surface_texture(other_surface) {
+        Ok(surface_texture) => surface_texture,
+        Err((err, _)) => panic!("Failed to create surface texture: {:?}", err),
+    };
+    device.borrow().make_no_context_current().unwrap();
+    drop(other_surface_texture);
+    assert!(NativeContext::current().is_err());
+
+    drop(context);
+    drop(surface_texture);
+}
+
+fn bind_context_fbo(gl: &Gl, device: &Device, context: &Context) {
+    unsafe {
+        gl.bind_framebuffer(gl::FRAMEBUFFER, context_fbo(device, context));
+        check_gl(gl);
+    }
+}
+
+fn context_fbo(device: &Device, context: &Context) -> Option<Framebuffer> {
+    device
+        .context_surface_info(context)
+        .unwrap()
+        .unwrap()
+        .framebuffer_object
+}
+
+fn make_surface(device: &mut Device, context: &Context) -> Surface {
+    device
+        .create_surface(
+            context,
+            SurfaceAccess::GPUOnly,
+            SurfaceType::Generic {
+                size: Size2D::new(640, 480),
+            },
+        )
+        .unwrap()
+}
+
+fn blit_fbo(gl: &Gl, dest_fbo: Option<Framebuffer>, src_fbo: Option<Framebuffer>) {
+    unsafe {
+        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, dest_fbo);
+        check_gl(gl);
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, src_fbo);
+        check_gl(gl);
+        gl.blit_framebuffer(
+            0,
+            0,
+            640,
+            480,
+            0,
+            0,
+            640,
+            480,
+            gl::COLOR_BUFFER_BIT,
+            gl::NEAREST,
+        );
+        check_gl(gl);
+    }
+}
+
+fn make_fbo(gl: &Gl, texture_target: u32, texture: Option<Texture>) -> Framebuffer {
+    unsafe {
+        let framebuffer_object = gl.create_framebuffer().unwrap();
+        check_gl(gl);
+        gl.bind_framebuffer(gl::FRAMEBUFFER, Some(framebuffer_object));
+        check_gl(gl);
+        gl.framebuffer_texture_2d(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            texture_target,
+            texture,
+            0,
+        );
+        check_gl(gl);
+        assert_eq!(
+            gl.check_framebuffer_status(gl::FRAMEBUFFER),
+            gl::FRAMEBUFFER_COMPLETE
+        );
+        framebuffer_object
+    }
+}
+
+struct BasicEnvironment {
+    connection: Connection,
+    adapter: Adapter,
+    device: Device,
+    context_descriptor: ContextDescriptor,
+    context: Context,
+    gl: Gl,
+}
+
+impl BasicEnvironment {
+    fn new() -> Option<BasicEnvironment> {
+        let connection = Connection::new().unwrap();
+        let adapter = connection
+            .create_low_power_adapter()
+            .expect("Failed to create adapter!");
+        let mut device = match connection.create_device(&adapter) {
+            Ok(device) => device,
+            Err(Error::RequiredExtensionUnavailable) => {
+                // Can't run these tests on this hardware.
+                return None;
+            }
+            Err(err) => panic!("Failed to create device: {:?}", err),
+        };
+
+        let context_descriptor = device
+            .create_context_descriptor(&ContextAttributes {
+                version: GLVersion::new(3, 0),
+                flags: ContextAttributeFlags::empty(),
+            })
+            .unwrap();
+
+        let mut context = device.create_context(&context_descriptor, None).unwrap();
+        let surface = make_surface(&mut device, &context);
+        device
+            .bind_surface_to_context(&mut context, surface)
+            .unwrap();
+        device.make_context_current(&context).unwrap();
+
+        let gl =
+            unsafe { Gl::from_loader_function(|symbol| device.get_proc_address(&context, symbol)) };
+
+        unsafe {
+            let framebuffer_object = device
+                .context_surface_info(&context)
+                .unwrap()
+                .unwrap()
+                .framebuffer_object;
+            gl.bind_framebuffer(gl::FRAMEBUFFER, framebuffer_object);
+            gl.viewport(0, 0, 640, 480);
         }
 
         Some(BasicEnvironment {
@@ -1043,6 +2745,32 @@ impl BasicEnvironment {
     }
 }
 
+// Opens the default device and creates a context on it with no surface bound. Returns `None` if
+// the tests can't run on this hardware.
+fn create_device_and_context(
+    version: GLVersion,
+    flags: ContextAttributeFlags,
+) -> Option<(Device, Context)> {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return None;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    let descriptor = device
+        .create_context_descriptor(&ContextAttributes { version, flags })
+        .unwrap();
+    let context = device.create_context(&descriptor, None).unwrap();
+    Some((device, context))
+}
+
 fn clear(gl: &Gl, color: &[u8; 4]) {
     unsafe {
         gl.clear_color(